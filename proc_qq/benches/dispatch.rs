@@ -0,0 +1,68 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use proc_qq::re_exports::rq_engine::protocol::device::Device;
+use proc_qq::re_exports::rs_qq::version::ANDROID_WATCH;
+use proc_qq::re_exports::serde_json;
+use proc_qq::Authentication::QRCode;
+use proc_qq::{
+    event, module, ClientBuilder, DeviceSource, MessageContentTrait, MessageEvent, Module,
+    SyntheticEvents,
+};
+
+#[event(regexp = "^签到$")]
+async fn checkin(_event: &MessageEvent) -> anyhow::Result<bool> {
+    Ok(true)
+}
+
+#[event(regexp = "^天气\\s+(\\S+)$")]
+async fn weather(_event: &MessageEvent, _city: String) -> anyhow::Result<bool> {
+    Ok(true)
+}
+
+#[event]
+async fn keyword(event: &MessageEvent) -> anyhow::Result<bool> {
+    Ok(event.message_content().contains("打游戏"))
+}
+
+#[event]
+async fn pass(_event: &MessageEvent) -> anyhow::Result<bool> {
+    Ok(false)
+}
+
+fn modules(n: usize) -> Vec<Module> {
+    (0..n)
+        .map(|i| {
+            let mut m = module!("bench", "压测", pass, keyword, weather, checkin);
+            m.id = format!("bench_{}", i);
+            m
+        })
+        .collect()
+}
+
+fn dispatch(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    for n in [1, 10, 50] {
+        let client = runtime.block_on(async {
+            ClientBuilder::new()
+                .device(DeviceSource::JsonString(
+                    serde_json::to_string(&Device::random()).unwrap(),
+                ))
+                .version(&ANDROID_WATCH)
+                .authentication(QRCode)
+                .show_registration_report(false)
+                .build(modules(n))
+                .await
+                .unwrap()
+        });
+        let mut events = SyntheticEvents::new(1);
+        c.bench_function(&format!("dispatch_{}_modules", n), |b| {
+            b.to_async(&runtime).iter_batched(
+                || events.next_event(&client.rq_client),
+                |e| client.dispatch_event(e),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+}
+
+criterion_group!(benches, dispatch);
+criterion_main!(benches);
@@ -0,0 +1,89 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// 编译时记录git hash和rs-qq的版本, 用于 proc_qq::build_info()
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    println!("cargo:rustc-env=PROC_QQ_GIT_HASH={}", git_hash(&manifest_dir));
+    println!("cargo:rustc-env=PROC_QQ_RS_QQ_VERSION={}", rs_qq_version(&manifest_dir));
+}
+
+fn git_hash(dir: &Path) -> String {
+    if let Some(git_dir) = dir.ancestors().map(|d| d.join(".git")).find(|d| d.exists()) {
+        watch_git_head(&git_dir);
+    }
+    Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .current_dir(dir)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_owned())
+        .filter(|hash| !hash.is_empty())
+        .unwrap_or_else(|| "unknown".to_owned())
+}
+
+// 提交时HEAD本身不变, 变的是它指向的分支, 分支也可能只在packed-refs中
+fn watch_git_head(git_dir: &Path) {
+    // 工作树中的.git是一个文件, 内容为 gitdir: <路径>
+    let git_dir = match std::fs::read_to_string(git_dir) {
+        Ok(content) => match content.trim().strip_prefix("gitdir:") {
+            Some(path) => git_dir.parent().unwrap_or(git_dir).join(path.trim()),
+            None => return,
+        },
+        Err(_) => git_dir.to_path_buf(),
+    };
+    // 工作树的分支和packed-refs在主仓库中
+    let common_dir = std::fs::read_to_string(git_dir.join("commondir"))
+        .map(|common| git_dir.join(common.trim()))
+        .unwrap_or_else(|_| git_dir.clone());
+    let head = git_dir.join("HEAD");
+    println!("cargo:rerun-if-changed={}", head.display());
+    let head = std::fs::read_to_string(head).unwrap_or_default();
+    if let Some(reference) = head.trim().strip_prefix("ref:") {
+        // 分支只在packed-refs中时文件不存在, cargo每次都会重新运行, 提交后仍然能更新
+        let reference = common_dir.join(reference.trim());
+        println!("cargo:rerun-if-changed={}", reference.display());
+    }
+    let packed_refs = common_dir.join("packed-refs");
+    if packed_refs.exists() {
+        println!("cargo:rerun-if-changed={}", packed_refs.display());
+    }
+}
+
+// 作为依赖使用时Cargo.lock在使用者的工作区, 从OUT_DIR (target目录之内) 向上查找
+fn rs_qq_version(dir: &Path) -> String {
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap_or_default());
+    let lock_file = dir
+        .ancestors()
+        .chain(out_dir.ancestors())
+        .map(|d| d.join("Cargo.lock"))
+        .find(|f| f.exists());
+    let lock_file = match lock_file {
+        Some(lock_file) => lock_file,
+        None => return "unknown".to_owned(),
+    };
+    println!("cargo:rerun-if-changed={}", lock_file.display());
+    let lock = std::fs::read_to_string(lock_file).unwrap_or_default();
+    for package in lock.split("[[package]]") {
+        let field = |name: &str| {
+            package.lines().find_map(|line| {
+                line.strip_prefix(name)
+                    .and_then(|v| v.trim().strip_prefix('='))
+                    .map(|v| v.trim().trim_matches('"').to_owned())
+            })
+        };
+        if field("name").as_deref() != Some("rs-qq") {
+            continue;
+        }
+        let version = field("version").unwrap_or_default();
+        // git依赖带上commit
+        return match field("source").and_then(|s| s.split('#').nth(1).map(str::to_owned)) {
+            Some(commit) => format!("{} ({})", version, &commit[..commit.len().min(7)]),
+            None => version,
+        };
+    }
+    "unknown".to_owned()
+}
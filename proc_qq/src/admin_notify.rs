@@ -0,0 +1,72 @@
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use rq_engine::msg::MessageChain;
+use std::sync::RwLock;
+
+use crate::{Alert, AlertSink, ClientTrait, MessageChainParseTrait, MessageTarget, ProcClient};
+
+lazy_static! {
+    static ref ADMIN_TARGETS: RwLock<Vec<MessageTarget>> = RwLock::new(vec![]);
+}
+
+/// 设置管理员的QQ号或群, notify_admins 会发送到这些目标
+pub fn set_admin_targets(targets: Vec<MessageTarget>) {
+    *ADMIN_TARGETS.write().unwrap() = targets;
+}
+
+pub fn admin_targets() -> Vec<MessageTarget> {
+    ADMIN_TARGETS.read().unwrap().clone()
+}
+
+#[async_trait]
+pub trait AdminNotifyTrait: ClientTrait {
+    /// 发送消息给所有管理员, 返回发送成功的数量
+    async fn notify_admins<S: Into<MessageChain> + Send + Sync>(&self, message: S) -> usize;
+}
+
+#[async_trait]
+impl<C: ClientTrait> AdminNotifyTrait for C {
+    async fn notify_admins<S: Into<MessageChain> + Send + Sync>(&self, message: S) -> usize {
+        let message = message.into();
+        let mut sent = 0;
+        for target in admin_targets() {
+            match self.send_message_to_target(&target, message.clone()).await {
+                Ok(_) => sent += 1,
+                Err(err) => tracing::warn!(
+                    target = "proc_qq",
+                    "通知管理员失败 ({}) : {:?}",
+                    crate::log_target(&target),
+                    err
+                ),
+            }
+        }
+        sent
+    }
+}
+
+/// 通过QQ消息把告警发送给管理员
+///
+/// 登录失败等机器人不在线时的告警无法送达, 需要时请使用其他的AlertSink
+pub struct AdminAlertSink {
+    client: ProcClient,
+}
+
+impl AdminAlertSink {
+    pub fn new(client: ProcClient) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl AlertSink for AdminAlertSink {
+    async fn send_alert(&self, alert: &Alert) -> anyhow::Result<()> {
+        if admin_targets().is_empty() {
+            return Err(anyhow::Error::msg("未设置管理员"));
+        }
+        let text = format!("[proc_qq] {}", alert);
+        if self.client.notify_admins(text.parse_message_chain()).await == 0 {
+            return Err(anyhow::Error::msg("没有发送成功的管理员"));
+        }
+        Ok(())
+    }
+}
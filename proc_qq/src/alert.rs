@@ -0,0 +1,229 @@
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// 需要通知运维人员的事件
+#[derive(Debug, Clone)]
+pub enum Alert {
+    /// 机器人被挤下线
+    KickedOffline(String),
+    /// 登录失败
+    LoginFailed(String),
+    /// 一段时间内处理器出错次数过多
+    ErrorRateSpike { errors: usize, window: Duration },
+    /// 自检失败
+    SelfTestFailed(String),
+}
+
+impl Alert {
+    fn kind(&self) -> &'static str {
+        match self {
+            Alert::KickedOffline(_) => "kicked_offline",
+            Alert::LoginFailed(_) => "login_failed",
+            Alert::ErrorRateSpike { .. } => "error_rate_spike",
+            Alert::SelfTestFailed(_) => "self_test_failed",
+        }
+    }
+}
+
+impl Display for Alert {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Alert::KickedOffline(message) => write!(f, "机器人被挤下线 : {}", message),
+            Alert::LoginFailed(message) => write!(f, "登录失败 : {}", message),
+            Alert::ErrorRateSpike { errors, window } => {
+                write!(f, "{}秒内处理器出错{}次", window.as_secs(), errors)
+            }
+            Alert::SelfTestFailed(message) => write!(f, "自检失败 : {}", message),
+        }
+    }
+}
+
+/// 告警的发送方式
+#[async_trait]
+pub trait AlertSink: Send + Sync {
+    async fn send_alert(&self, alert: &Alert) -> anyhow::Result<()>;
+}
+
+#[derive(Debug, Clone)]
+pub struct AlertConfig {
+    pub kicked_offline: bool,
+    pub login_failed: bool,
+    pub self_test_failed: bool,
+    // (次数, 时间窗口), 窗口内处理器出错达到次数时告警
+    pub error_rate: Option<(usize, Duration)>,
+    // 同一种告警的最小间隔
+    pub cooldown: Duration,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            kicked_offline: true,
+            login_failed: true,
+            self_test_failed: true,
+            error_rate: Some((20, Duration::from_secs(60))),
+            cooldown: Duration::from_secs(600),
+        }
+    }
+}
+
+struct AlertState {
+    sink: Arc<dyn AlertSink>,
+    config: AlertConfig,
+}
+
+lazy_static! {
+    static ref ALERT: RwLock<Option<AlertState>> = RwLock::new(None);
+    static ref LAST_ALERTS: Mutex<HashMap<&'static str, Instant>> = Mutex::new(HashMap::new());
+    static ref HANDLER_ERRORS: Mutex<VecDeque<Instant>> = Mutex::new(VecDeque::new());
+}
+
+/// 设置告警的发送方式和条件, 未设置时不发送告警
+pub fn set_alert_sink<S: AlertSink + 'static>(sink: S, config: AlertConfig) {
+    *ALERT.write().unwrap() = Some(AlertState {
+        sink: Arc::new(sink),
+        config,
+    });
+}
+
+pub(crate) fn raise_alert(alert: Alert) {
+    let sink = match ALERT.read().unwrap().as_ref() {
+        Some(state) => {
+            let enabled = match &alert {
+                Alert::KickedOffline(_) => state.config.kicked_offline,
+                Alert::LoginFailed(_) => state.config.login_failed,
+                Alert::ErrorRateSpike { .. } => state.config.error_rate.is_some(),
+                Alert::SelfTestFailed(_) => state.config.self_test_failed,
+            };
+            if !enabled || !cooled_down(alert.kind(), state.config.cooldown) {
+                return;
+            }
+            state.sink.clone()
+        }
+        None => return,
+    };
+    tokio::spawn(async move {
+        if let Err(err) = sink.send_alert(&alert).await {
+            tracing::error!(target = "proc_qq", "发送告警失败 ({}) : {:?}", alert, err);
+        }
+    });
+}
+
+fn cooled_down(kind: &'static str, cooldown: Duration) -> bool {
+    let mut lock = LAST_ALERTS.lock().unwrap();
+    let now = Instant::now();
+    if let Some(last) = lock.get(kind) {
+        if now.duration_since(*last) < cooldown {
+            return false;
+        }
+    }
+    lock.insert(kind, now);
+    true
+}
+
+/// 记录一次处理器错误, 用于计算错误率
+pub(crate) fn record_handler_error() {
+    let (threshold, window) = match ALERT
+        .read()
+        .unwrap()
+        .as_ref()
+        .and_then(|state| state.config.error_rate)
+    {
+        Some(error_rate) => error_rate,
+        None => return,
+    };
+    let errors = {
+        let mut lock = HANDLER_ERRORS.lock().unwrap();
+        let now = Instant::now();
+        lock.push_back(now);
+        while let Some(first) = lock.front() {
+            if now.duration_since(*first) > window {
+                lock.pop_front();
+            } else {
+                break;
+            }
+        }
+        lock.len()
+    };
+    if errors >= threshold {
+        raise_alert(Alert::ErrorRateSpike { errors, window });
+    }
+}
+
+/// 通过SMTP发送告警邮件
+#[cfg(feature = "smtp-alert")]
+pub struct SmtpAlertSink {
+    transport: lettre::AsyncSmtpTransport<lettre::Tokio1Executor>,
+    from: lettre::message::Mailbox,
+    to: Vec<lettre::message::Mailbox>,
+    subject_prefix: String,
+}
+
+#[cfg(feature = "smtp-alert")]
+impl SmtpAlertSink {
+    /// 使用TLS连接relay (如 smtp.qq.com), from和to为邮箱地址
+    pub fn new(
+        relay: &str,
+        username: &str,
+        password: &str,
+        from: &str,
+        to: &[&str],
+    ) -> anyhow::Result<Self> {
+        let transport = lettre::AsyncSmtpTransport::<lettre::Tokio1Executor>::relay(relay)?
+            .credentials(lettre::transport::smtp::authentication::Credentials::new(
+                username.to_owned(),
+                password.to_owned(),
+            ))
+            .build();
+        Ok(Self {
+            transport,
+            from: from.parse()?,
+            to: to
+                .iter()
+                .map(|to| to.parse())
+                .collect::<Result<Vec<_>, _>>()?,
+            subject_prefix: "[proc_qq]".to_owned(),
+        })
+    }
+
+    /// 邮件标题的前缀, 默认为 [proc_qq]
+    pub fn subject_prefix<S: Into<String>>(mut self, subject_prefix: S) -> Self {
+        self.subject_prefix = subject_prefix.into();
+        self
+    }
+}
+
+#[cfg(feature = "smtp-alert")]
+#[async_trait]
+impl AlertSink for SmtpAlertSink {
+    async fn send_alert(&self, alert: &Alert) -> anyhow::Result<()> {
+        use lettre::AsyncTransport;
+        let mut builder = lettre::Message::builder()
+            .from(self.from.clone())
+            .subject(format!("{} {}", self.subject_prefix, alert.kind()));
+        for to in &self.to {
+            builder = builder.to(to.clone());
+        }
+        self.transport
+            .send(builder.body(alert.to_string())?)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alerts_cool_down_per_kind() {
+        assert!(cooled_down("alert_test_a", Duration::from_secs(60)));
+        assert!(!cooled_down("alert_test_a", Duration::from_secs(60)));
+        assert!(cooled_down("alert_test_b", Duration::from_secs(60)));
+        assert!(cooled_down("alert_test_a", Duration::ZERO));
+    }
+}
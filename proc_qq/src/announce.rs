@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use crate::{admin_targets, ClientTrait, MessageChainParseTrait, MessageTarget};
+
+/// 启动和关闭时发送给管理员和指定群的通知
+///
+/// 模版中可以使用 {version} {modules} {uin} 作为占位符
+#[derive(Debug, Clone)]
+pub struct Announcement {
+    pub(crate) groups: Vec<i64>,
+    pub(crate) startup: Option<String>,
+    pub(crate) shutdown: Option<String>,
+}
+
+impl Default for Announcement {
+    fn default() -> Self {
+        Self {
+            groups: vec![],
+            startup: Some("[proc_qq {version}] 已启动, 模块 : {modules}".to_owned()),
+            shutdown: Some("[proc_qq {version}] 正在关闭".to_owned()),
+        }
+    }
+}
+
+impl Announcement {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 除了管理员 (set_admin_targets) 之外同时通知的群
+    pub fn groups(mut self, groups: Vec<i64>) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    /// 启动通知的模版, None为不发送
+    pub fn startup<S: Into<String>>(mut self, template: Option<S>) -> Self {
+        self.startup = template.map(Into::into);
+        self
+    }
+
+    /// 关闭通知的模版, None为不发送
+    pub fn shutdown<S: Into<String>>(mut self, template: Option<S>) -> Self {
+        self.shutdown = template.map(Into::into);
+        self
+    }
+
+    fn targets(&self) -> Vec<MessageTarget> {
+        let mut targets = admin_targets();
+        for group_code in &self.groups {
+            let target = MessageTarget::Group(*group_code, 0);
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+        targets
+    }
+
+    pub(crate) async fn announce_startup(&self, client: &crate::Client) {
+        if let Some(template) = &self.startup {
+            self.announce(client, template).await
+        }
+    }
+
+    /// 关闭时连接可能已经不可用, 最多等待五秒
+    pub(crate) async fn announce_shutdown(&self, client: &crate::Client) {
+        if let Some(template) = &self.shutdown {
+            let _ =
+                tokio::time::timeout(Duration::from_secs(5), self.announce(client, template)).await;
+        }
+    }
+
+    async fn announce(&self, client: &crate::Client, template: &str) {
+        let message = template
+            .replace("{version}", env!("CARGO_PKG_VERSION"))
+            .replace("{modules}", &client.modules.ids().join(", "))
+            .replace("{uin}", &client.rq_client.uin().await.to_string())
+            .parse_message_chain();
+        for target in self.targets() {
+            if let Err(err) = client
+                .send_message_to_target(&target, message.clone())
+                .await
+            {
+                tracing::warn!(
+                    target = "proc_qq",
+                    "发送启动或关闭通知失败 ({}) : {:?}",
+                    crate::log_target(&target),
+                    err
+                );
+            }
+        }
+    }
+}
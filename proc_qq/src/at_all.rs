@@ -0,0 +1,130 @@
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use rq_engine::msg::elem::At;
+use rq_engine::msg::MessageChain;
+use rq_engine::structs::MessageReceipt;
+use rq_engine::RQError;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::Mutex;
+
+use crate::{group_today, ClientTrait, MessageTarget};
+
+/// 群中剩余的@全体成员次数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtAllQuota {
+    /// 机器人是否可以@全体成员 (需要是管理员)
+    pub can_at_all: bool,
+    /// 群今天剩余的次数
+    pub remaining_for_group: u32,
+    /// 机器人今天剩余的次数
+    pub remaining_for_bot: u32,
+}
+
+impl AtAllQuota {
+    pub fn available(&self) -> bool {
+        self.can_at_all && self.remaining_for_group > 0 && self.remaining_for_bot > 0
+    }
+}
+
+#[derive(Debug)]
+pub enum AtAllError {
+    /// 机器人不能@全体成员
+    NotAllowed,
+    /// 今天的次数已经用完
+    Exhausted(AtAllQuota),
+    Request(RQError),
+}
+
+impl Display for AtAllError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AtAllError::NotAllowed => write!(f, "机器人不能@全体成员"),
+            AtAllError::Exhausted(quota) => write!(
+                f,
+                "@全体成员次数已用完 (群剩余{}, 机器人剩余{})",
+                quota.remaining_for_group, quota.remaining_for_bot
+            ),
+            AtAllError::Request(err) => write!(f, "@全体成员失败 : {:?}", err),
+        }
+    }
+}
+
+impl std::error::Error for AtAllError {}
+
+impl From<RQError> for AtAllError {
+    fn from(err: RQError) -> Self {
+        AtAllError::Request(err)
+    }
+}
+
+lazy_static! {
+    // (群号, 日期) -> 机器人发送的次数
+    static ref AT_ALL_USAGE: Mutex<HashMap<(i64, i64), u32>> = Mutex::new(HashMap::new());
+}
+
+/// 机器人今天在群中@全体成员的次数
+pub fn at_all_usage(group_code: i64) -> u32 {
+    AT_ALL_USAGE
+        .lock()
+        .unwrap()
+        .get(&(group_code, group_today(group_code)))
+        .copied()
+        .unwrap_or_default()
+}
+
+/// 今天所有群@全体成员的次数
+pub fn at_all_usage_total() -> u32 {
+    AT_ALL_USAGE
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|((group_code, day), _)| *day == group_today(*group_code))
+        .map(|(_, count)| count)
+        .sum()
+}
+
+fn record_at_all(group_code: i64) {
+    let today = group_today(group_code);
+    let mut lock = AT_ALL_USAGE.lock().unwrap();
+    // 只保留最近的记录
+    lock.retain(|(_, day), _| *day >= today - 1);
+    *lock.entry((group_code, today)).or_default() += 1;
+}
+
+#[async_trait]
+pub trait AtAllTrait: ClientTrait {
+    /// 检查剩余次数后发送@全体成员的消息, 次数不足时返回 AtAllError 而不是发送失败
+    async fn send_at_all<S: Into<MessageChain> + Send + Sync>(
+        &self,
+        group_code: i64,
+        message: S,
+    ) -> Result<MessageReceipt, AtAllError>;
+}
+
+#[async_trait]
+impl<C: ClientTrait> AtAllTrait for C {
+    async fn send_at_all<S: Into<MessageChain> + Send + Sync>(
+        &self,
+        group_code: i64,
+        message: S,
+    ) -> Result<MessageReceipt, AtAllError> {
+        let quota = self.at_all_quota(group_code).await?;
+        if !quota.can_at_all {
+            return Err(AtAllError::NotAllowed);
+        }
+        if !quota.available() {
+            return Err(AtAllError::Exhausted(quota));
+        }
+        let mut chain = MessageChain::new(At {
+            target: 0,
+            display: "@全体成员".to_owned(),
+        });
+        chain.0.extend(message.into().0);
+        let receipt = self
+            .send_message_to_target(&MessageTarget::Group(group_code, 0), chain)
+            .await?;
+        record_at_all(group_code);
+        Ok(receipt)
+    }
+}
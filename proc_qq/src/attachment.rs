@@ -0,0 +1,220 @@
+use image::ImageFormat;
+use lazy_static::lazy_static;
+use rq_engine::RQError;
+use std::fmt::{Display, Formatter};
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// 上传附件之前的检查, 不符合时直接返回错误, 而不是上传后得到难以理解的协议错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttachmentLimits {
+    /// 图片的最大字节数 (开启image_compress时为压缩后的大小)
+    pub max_image_bytes: Option<usize>,
+    /// 允许的图片类型 (如 image/png), 为None时不检查
+    pub allowed_image_types: Option<Vec<String>>,
+    pub max_audio_bytes: Option<usize>,
+    pub max_audio_duration: Option<Duration>,
+}
+
+impl Default for AttachmentLimits {
+    fn default() -> Self {
+        Self {
+            max_image_bytes: Some(30 * 1024 * 1024),
+            allowed_image_types: Some(
+                [
+                    "image/jpeg",
+                    "image/png",
+                    "image/gif",
+                    "image/bmp",
+                    "image/webp",
+                ]
+                .iter()
+                .map(|t| t.to_string())
+                .collect(),
+            ),
+            max_audio_bytes: None,
+            max_audio_duration: None,
+        }
+    }
+}
+
+impl AttachmentLimits {
+    /// 不做任何检查
+    pub fn unlimited() -> Self {
+        Self {
+            max_image_bytes: None,
+            allowed_image_types: None,
+            max_audio_bytes: None,
+            max_audio_duration: None,
+        }
+    }
+}
+
+/// 附件检查的错误, 上传方法中转换为 RQError::Other
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttachmentError {
+    ImageTooLarge {
+        size: usize,
+        max: usize,
+    },
+    /// 不能识别的图片为 None
+    ImageTypeNotAllowed(Option<String>),
+    AudioTooLarge {
+        size: usize,
+        max: usize,
+    },
+    AudioTooLong {
+        duration: Duration,
+        max: Duration,
+    },
+}
+
+impl Display for AttachmentError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttachmentError::ImageTooLarge { size, max } => {
+                write!(f, "图片过大 : {} 字节, 最大 {} 字节", size, max)
+            }
+            AttachmentError::ImageTypeNotAllowed(Some(mime)) => {
+                write!(f, "不支持的图片类型 : {}", mime)
+            }
+            AttachmentError::ImageTypeNotAllowed(None) => write!(f, "不能识别的图片格式"),
+            AttachmentError::AudioTooLarge { size, max } => {
+                write!(f, "语音过大 : {} 字节, 最大 {} 字节", size, max)
+            }
+            AttachmentError::AudioTooLong { duration, max } => write!(
+                f,
+                "语音过长 : {}秒, 最长 {}秒",
+                duration.as_secs(),
+                max.as_secs()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AttachmentError {}
+
+impl From<AttachmentError> for RQError {
+    fn from(err: AttachmentError) -> Self {
+        RQError::Other(err.to_string())
+    }
+}
+
+lazy_static! {
+    static ref ATTACHMENT_LIMITS: RwLock<AttachmentLimits> =
+        RwLock::new(AttachmentLimits::default());
+}
+
+pub fn set_attachment_limits(limits: AttachmentLimits) {
+    *ATTACHMENT_LIMITS.write().unwrap() = limits;
+}
+
+pub fn attachment_limits() -> AttachmentLimits {
+    ATTACHMENT_LIMITS.read().unwrap().clone()
+}
+
+/// 根据文件头识别图片的类型
+pub fn image_mime_type(data: &[u8]) -> Option<&'static str> {
+    Some(match image::guess_format(data).ok()? {
+        ImageFormat::Jpeg => "image/jpeg",
+        ImageFormat::Png => "image/png",
+        ImageFormat::Gif => "image/gif",
+        ImageFormat::Bmp => "image/bmp",
+        ImageFormat::WebP => "image/webp",
+        ImageFormat::Tiff => "image/tiff",
+        ImageFormat::Ico => "image/x-icon",
+        _ => return None,
+    })
+}
+
+/// 按照当前的限制检查图片
+pub fn validate_image(data: &[u8]) -> Result<(), AttachmentError> {
+    let limits = attachment_limits();
+    if let Some(allowed) = &limits.allowed_image_types {
+        match image_mime_type(data) {
+            Some(mime) if allowed.iter().any(|t| t == mime) => {}
+            mime => {
+                return Err(AttachmentError::ImageTypeNotAllowed(
+                    mime.map(str::to_owned),
+                ))
+            }
+        }
+    }
+    if let Some(max) = limits.max_image_bytes {
+        if data.len() > max {
+            return Err(AttachmentError::ImageTooLarge {
+                size: data.len(),
+                max,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// 按照当前的限制检查语音, 不知道时长时传入 Duration::ZERO
+pub fn validate_audio(data: &[u8], duration: Duration) -> Result<(), AttachmentError> {
+    let limits = attachment_limits();
+    if let Some(max) = limits.max_audio_bytes {
+        if data.len() > max {
+            return Err(AttachmentError::AudioTooLarge {
+                size: data.len(),
+                max,
+            });
+        }
+    }
+    if let Some(max) = limits.max_audio_duration {
+        if duration > max {
+            return Err(AttachmentError::AudioTooLong { duration, max });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    #[test]
+    fn detects_mime_types() {
+        assert_eq!(image_mime_type(PNG), Some("image/png"));
+        assert_eq!(image_mime_type(b"GIF89a...."), Some("image/gif"));
+        assert_eq!(image_mime_type(b"hello world"), None);
+    }
+
+    // 限制是全局的, 放在同一个测试中避免并发修改
+    #[test]
+    fn validates_with_current_limits() {
+        set_attachment_limits(AttachmentLimits::default());
+        assert_eq!(validate_image(PNG), Ok(()));
+        assert_eq!(
+            validate_image(b"hello world"),
+            Err(AttachmentError::ImageTypeNotAllowed(None))
+        );
+        assert_eq!(validate_audio(&[0; 1024], Duration::from_secs(600)), Ok(()));
+        set_attachment_limits(AttachmentLimits {
+            max_image_bytes: Some(8),
+            allowed_image_types: Some(vec!["image/jpeg".to_owned()]),
+            max_audio_bytes: Some(100),
+            max_audio_duration: Some(Duration::from_secs(60)),
+        });
+        assert_eq!(
+            validate_image(PNG),
+            Err(AttachmentError::ImageTypeNotAllowed(Some(
+                "image/png".to_owned()
+            )))
+        );
+        assert_eq!(
+            validate_audio(&[0; 1024], Duration::ZERO),
+            Err(AttachmentError::AudioTooLarge {
+                size: 1024,
+                max: 100
+            })
+        );
+        assert!(validate_audio(&[0; 10], Duration::from_secs(61)).is_err());
+        set_attachment_limits(AttachmentLimits::unlimited());
+        assert_eq!(validate_image(b"hello world"), Ok(()));
+        set_attachment_limits(AttachmentLimits::default());
+    }
+}
@@ -0,0 +1,159 @@
+use lazy_static::lazy_static;
+use serde_derive::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+use crate::{current_actor, Actor, KvStore};
+
+const AUDIT_STORE: &str = "_audit";
+// 每个群 (以及群之外的操作) 最多保留的记录数量
+const AUDIT_LIMIT: usize = 500;
+
+lazy_static! {
+    // 读取-追加-写回之间不能插入另一条记录, 否则会丢失记录
+    static ref AUDIT_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// 机器人执行的一次改变状态的操作 (踢人, 禁言, 撤回, 处理请求等)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub time: i64,
+    pub action: String,
+    pub group_code: Option<i64>,
+    pub target_uin: Option<i64>,
+    pub detail: String,
+    /// 发起操作的处理器, 在处理器之外 (如定时任务) 执行时为None
+    pub actor: Option<Actor>,
+}
+
+/// 查询审计日志的条件, 为None的条件不过滤
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    pub group_code: Option<i64>,
+    pub target_uin: Option<i64>,
+    pub action: Option<String>,
+    pub module_id: Option<String>,
+    pub limit: Option<usize>,
+}
+
+fn audit_store(group_code: Option<i64>) -> KvStore {
+    KvStore::new(AUDIT_STORE, group_code, None)
+}
+
+/// 记录一次操作, 发起者为当前的处理器
+pub async fn record_audit(
+    action: &str,
+    group_code: Option<i64>,
+    target_uin: Option<i64>,
+    detail: String,
+) {
+    let entry = AuditEntry {
+        time: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64,
+        action: action.to_owned(),
+        group_code,
+        target_uin,
+        detail,
+        actor: current_actor(),
+    };
+    tracing::info!(
+        target = "proc_qq",
+        "AUDIT {} (GROUP={:?}, UIN={:?}) by {} : {}",
+        entry.action,
+        entry.group_code,
+        entry.target_uin.map(crate::log_uin),
+        entry
+            .actor
+            .as_ref()
+            .map(|actor| format!("{}::{}", actor.module_id, actor.handler))
+            .unwrap_or_else(|| "-".to_owned()),
+        entry.detail
+    );
+    if let Err(err) = append_audit(entry).await {
+        tracing::warn!(target = "proc_qq", "写入审计日志失败 : {:?}", err);
+    }
+}
+
+async fn append_audit(entry: AuditEntry) -> anyhow::Result<()> {
+    let _guard = AUDIT_LOCK.lock().await;
+    let store = audit_store(entry.group_code);
+    let mut entries: Vec<AuditEntry> = store.get("log").await?.unwrap_or_default();
+    entries.push(entry);
+    if entries.len() > AUDIT_LIMIT {
+        entries.drain(..entries.len() - AUDIT_LIMIT);
+    }
+    store.set("log", &entries).await
+}
+
+/// 查询审计日志, 最新的在前, 没有指定group_code时查询群之外的操作
+pub async fn query_audit(query: &AuditQuery) -> anyhow::Result<Vec<AuditEntry>> {
+    let entries: Vec<AuditEntry> = audit_store(query.group_code)
+        .get("log")
+        .await?
+        .unwrap_or_default();
+    Ok(entries
+        .into_iter()
+        .rev()
+        .filter(|entry| query.target_uin.is_none() || entry.target_uin == query.target_uin)
+        .filter(|entry| query.action.is_none() || query.action.as_ref() == Some(&entry.action))
+        .filter(|entry| {
+            query.module_id.is_none()
+                || entry.actor.as_ref().map(|actor| &actor.module_id) == query.module_id.as_ref()
+        })
+        .take(query.limit.unwrap_or(usize::MAX))
+        .collect())
+}
+
+impl AuditEntry {
+    /// 适合发送到聊天中的一行文字
+    pub fn to_line(&self) -> String {
+        let actor = match &self.actor {
+            Some(actor) => format!(
+                "{}::{} ({}{})",
+                actor.module_id,
+                actor.handler,
+                actor.event,
+                actor
+                    .from_uin
+                    .map(|uin| format!(" from {}", uin))
+                    .unwrap_or_default()
+            ),
+            None => "-".to_owned(),
+        };
+        format!(
+            "[{}] {} {} {} by {}",
+            self.time,
+            self.action,
+            self.target_uin
+                .map(|uin| uin.to_string())
+                .unwrap_or_default(),
+            self.detail,
+            actor
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_records_are_kept() {
+        let group_code = Some(7110001);
+        let tasks: Vec<_> = (0..50)
+            .map(|i| tokio::spawn(record_audit("test", group_code, Some(i), String::new())))
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        let entries = query_audit(&AuditQuery {
+            group_code,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        assert_eq!(entries.len(), 50);
+    }
+}
@@ -0,0 +1,42 @@
+use lazy_static::lazy_static;
+use std::collections::HashSet;
+use std::sync::RwLock;
+
+/// 机器人被拉入白名单之外的群时, 发送一条消息后自动退群
+#[derive(Debug, Clone, Default)]
+pub struct AutoLeavePolicy {
+    pub whitelist: HashSet<i64>,
+    /// 退群之前在群中发送的消息, 为None时直接退群
+    pub message: Option<String>,
+}
+
+impl AutoLeavePolicy {
+    pub fn new<I: IntoIterator<Item = i64>>(whitelist: I) -> Self {
+        Self {
+            whitelist: whitelist.into_iter().collect(),
+            message: None,
+        }
+    }
+
+    pub fn message<S: Into<String>>(mut self, message: S) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    pub fn allows(&self, group_code: i64) -> bool {
+        self.whitelist.contains(&group_code)
+    }
+}
+
+lazy_static! {
+    static ref AUTO_LEAVE_POLICY: RwLock<Option<AutoLeavePolicy>> = RwLock::new(None);
+}
+
+/// 设置自动退群的策略, 为None时不自动退群 (默认)
+pub fn set_auto_leave_policy(policy: Option<AutoLeavePolicy>) {
+    *AUTO_LEAVE_POLICY.write().unwrap() = policy;
+}
+
+pub fn auto_leave_policy() -> Option<AutoLeavePolicy> {
+    AUTO_LEAVE_POLICY.read().unwrap().clone()
+}
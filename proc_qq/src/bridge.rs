@@ -0,0 +1,106 @@
+use async_trait::async_trait;
+use rq_engine::msg::elem::{FlashImage, RQElem, Text};
+use rq_engine::msg::MessageChain;
+
+use crate::MessageTarget;
+
+/// 和平台无关的消息片段
+#[derive(Debug, Clone, PartialEq)]
+pub enum BridgeSegment {
+    Text(String),
+    // 图片的原始数据
+    Image(Vec<u8>),
+}
+
+/// 在QQ和其他平台之间传递的消息
+#[derive(Debug, Clone, PartialEq)]
+pub struct BridgeMessage {
+    // 发送者在来源平台上显示的名字
+    pub sender_name: String,
+    pub segments: Vec<BridgeSegment>,
+}
+
+/// 其他聊天平台 (Telegram/Discord等) 的客户端, 由使用者实现
+#[async_trait]
+pub trait BridgeTransport: Send + Sync {
+    /// 平台名称, 用于日志
+    fn name(&self) -> &str;
+
+    /// 把QQ上的消息发送到平台
+    async fn send(&self, message: BridgeMessage) -> anyhow::Result<()>;
+
+    /// 等待平台上的下一条消息, 返回None表示连接已经关闭
+    async fn receive(&self) -> anyhow::Result<Option<BridgeMessage>>;
+}
+
+/// QQ消息转换为BridgeMessage, 图片会被下载, 无法转换的元素会被忽略
+pub async fn bridge_message_from_chain(
+    sender_name: String,
+    chain: &MessageChain,
+) -> anyhow::Result<BridgeMessage> {
+    let mut segments = vec![];
+    for elem in chain.clone().into_iter() {
+        let url = match elem {
+            RQElem::GroupImage(image) => image.url(),
+            RQElem::FriendImage(image) => image.url(),
+            RQElem::FlashImage(FlashImage::GroupImage(image)) => image.url(),
+            RQElem::FlashImage(FlashImage::FriendImage(image)) => image.url(),
+            RQElem::Text(text) => {
+                push_text(&mut segments, &text.content);
+                continue;
+            }
+            RQElem::At(at) => {
+                push_text(&mut segments, &at.display);
+                continue;
+            }
+            RQElem::Face(face) => {
+                push_text(&mut segments, &format!("[{}]", face.name));
+                continue;
+            }
+            _ => continue,
+        };
+        let data = reqwest::get(&url)
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        segments.push(BridgeSegment::Image(data.to_vec()));
+    }
+    Ok(BridgeMessage {
+        sender_name,
+        segments,
+    })
+}
+
+// 相邻的文字合并为一个片段
+fn push_text(segments: &mut Vec<BridgeSegment>, text: &str) {
+    if let Some(BridgeSegment::Text(last)) = segments.last_mut() {
+        last.push_str(text);
+    } else {
+        segments.push(BridgeSegment::Text(text.to_owned()));
+    }
+}
+
+/// BridgeMessage转换为可以发送到target的QQ消息, 图片会上传到target
+pub async fn chain_from_bridge_message(
+    client: &rs_qq::Client,
+    message: &BridgeMessage,
+    target: &MessageTarget,
+) -> anyhow::Result<MessageChain> {
+    let mut chain = MessageChain::default();
+    for segment in &message.segments {
+        match segment {
+            BridgeSegment::Text(text) => chain.push(Text::new(text.clone())),
+            BridgeSegment::Image(data) => match target {
+                MessageTarget::Group(group_code, _) => {
+                    chain.push(client.upload_group_image(*group_code, data.clone()).await?)
+                }
+                MessageTarget::Private(uin) => {
+                    chain.push(client.upload_friend_image(*uin, data.clone()).await?)
+                }
+                MessageTarget::Temp(..) => chain.push(Text::new("[图片]".to_owned())),
+            },
+        }
+    }
+    Ok(chain)
+}
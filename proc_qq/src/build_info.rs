@@ -0,0 +1,30 @@
+use serde_derive::Serialize;
+use std::fmt::{Display, Formatter};
+
+/// 编译时的版本信息, 用于确认部署的版本
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    /// 编译时proc_qq所在仓库的commit, 不在git仓库中编译时为unknown
+    pub git_hash: &'static str,
+    /// Cargo.lock中rs-qq的版本 (git依赖带有commit)
+    pub rs_qq_version: &'static str,
+}
+
+impl Display for BuildInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "proc_qq {} ({}), rs-qq {}",
+            self.version, self.git_hash, self.rs_qq_version
+        )
+    }
+}
+
+pub fn build_info() -> BuildInfo {
+    BuildInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        git_hash: env!("PROC_QQ_GIT_HASH"),
+        rs_qq_version: env!("PROC_QQ_RS_QQ_VERSION"),
+    }
+}
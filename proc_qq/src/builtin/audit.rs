@@ -0,0 +1,93 @@
+use crate::{
+    admin_targets, group_framework_messages, module, query_audit, AuditQuery, ClientTrait,
+    GroupMessageEventProcess, GroupTrait, MemberTrait, MessageChainParseTrait,
+    MessageSendToSourceTrait, MessageTarget, Module, ModuleEventHandler, ModuleEventProcess,
+};
+use async_trait::async_trait;
+use rq_engine::msg::elem::RQElem;
+use rs_qq::client::event::GroupMessageEvent;
+
+const DEFAULT_LINES: usize = 10;
+
+/// 群管理员通过命令查看机器人在本群的操作记录
+///
+/// - 审计日志 : 最近10条
+/// - 审计日志 20 : 最近20条
+/// - 审计日志 @成员 / QQ号 : 对某个成员的操作
+pub struct AuditAdmin;
+
+#[async_trait]
+impl GroupMessageEventProcess for AuditAdmin {
+    async fn handle(&self, event: &GroupMessageEvent) -> anyhow::Result<bool> {
+        let group_code = event.message.group_code;
+        // @成员 按元素读取目标QQ号, 而不是解析显示出来的名字
+        let mut content = String::new();
+        let mut at_targets = vec![];
+        for elem in event.message.elements.clone() {
+            match elem {
+                RQElem::Text(text) => content.push_str(&text.content),
+                RQElem::At(at) => at_targets.push(at.target),
+                _ => (),
+            }
+        }
+        let words: Vec<&str> = content.split_whitespace().collect();
+        let mut query = AuditQuery {
+            group_code: Some(group_code),
+            limit: Some(DEFAULT_LINES),
+            ..Default::default()
+        };
+        match (words.as_slice(), at_targets.as_slice()) {
+            (["审计日志"], []) => (),
+            (["审计日志"], [uin]) => query.target_uin = Some(*uin),
+            (["审计日志", arg], []) => match arg.parse::<i64>() {
+                Ok(n) if n > 0 && n <= 100 => query.limit = Some(n as usize),
+                Ok(uin) => query.target_uin = Some(uin),
+                Err(_) => return Ok(false),
+            },
+            _ => return Ok(false),
+        }
+        let reply = if !self.is_admin(event).await? {
+            group_framework_messages(group_code).permission_denied()
+        } else {
+            let entries = query_audit(&query).await?;
+            if entries.is_empty() {
+                "没有操作记录".to_owned()
+            } else {
+                entries
+                    .iter()
+                    .map(|entry| entry.to_line())
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            }
+        };
+        event
+            .send_message_to_source(reply.parse_message_chain())
+            .await?;
+        Ok(true)
+    }
+}
+
+impl AuditAdmin {
+    async fn is_admin(&self, event: &GroupMessageEvent) -> anyhow::Result<bool> {
+        let from_uin = event.message.from_uin;
+        if admin_targets().contains(&MessageTarget::Private(from_uin)) {
+            return Ok(true);
+        }
+        let group = event
+            .must_find_group(event.message.group_code, true)
+            .await?;
+        let member = group.must_find_member(from_uin).await?;
+        Ok(!member.is_member())
+    }
+}
+
+impl Into<ModuleEventHandler> for AuditAdmin {
+    fn into(self) -> ModuleEventHandler {
+        ModuleEventHandler::new("audit", ModuleEventProcess::GroupMessage(Box::new(self)))
+    }
+}
+
+/// 审计日志查询模块
+pub fn audit_module() -> Module {
+    module!("audit", "审计日志", AuditAdmin)
+}
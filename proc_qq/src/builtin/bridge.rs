@@ -0,0 +1,130 @@
+use crate::{
+    bridge_message_from_chain, chain_from_bridge_message, module, BridgeTransport, ClientTrait,
+    ConnectedAndOnlineEvent, ConnectedAndOnlineEventProcess, GroupMessageEventProcess,
+    MessageTarget, Module, ModuleEventHandler, ModuleEventProcess,
+};
+use async_trait::async_trait;
+use rq_engine::msg::elem::Text;
+use rq_engine::msg::MessageChain;
+use rs_qq::client::event::GroupMessageEvent;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+/// 把一个QQ群和其他平台的频道互通, 频道由BridgeTransport的实现决定
+struct BridgeState {
+    group_code: i64,
+    transport: Arc<dyn BridgeTransport>,
+    receiver: Mutex<Option<JoinHandle<()>>>,
+}
+
+struct BridgeOutgoing(Arc<BridgeState>);
+
+struct BridgeIncoming(Arc<BridgeState>);
+
+#[async_trait]
+impl GroupMessageEventProcess for BridgeOutgoing {
+    async fn handle(&self, event: &GroupMessageEvent) -> anyhow::Result<bool> {
+        let state = &self.0;
+        if event.message.group_code != state.group_code
+            || event.message.from_uin == event.bot_uin().await
+        {
+            return Ok(false);
+        }
+        let sender_name = if event.message.group_card.is_empty() {
+            event.message.from_uin.to_string()
+        } else {
+            event.message.group_card.clone()
+        };
+        let message = bridge_message_from_chain(sender_name, &event.message.elements).await?;
+        if let Err(err) = state.transport.send(message).await {
+            tracing::warn!(
+                target = "proc_qq",
+                "发送消息到 {} 失败 : {:?}",
+                state.transport.name(),
+                err
+            );
+        }
+        Ok(false)
+    }
+}
+
+#[async_trait]
+impl ConnectedAndOnlineEventProcess for BridgeIncoming {
+    async fn handle(&self, event: &ConnectedAndOnlineEvent) -> anyhow::Result<bool> {
+        let state = self.0.clone();
+        let client = event.client.clone();
+        let handle = crate::spawn_with_actor(async move {
+            let target = MessageTarget::Group(state.group_code, 0);
+            loop {
+                let message = match state.transport.receive().await {
+                    Ok(Some(message)) => message,
+                    Ok(None) => break,
+                    Err(err) => {
+                        tracing::warn!(
+                            target = "proc_qq",
+                            "接收 {} 的消息失败 : {:?}",
+                            state.transport.name(),
+                            err
+                        );
+                        continue;
+                    }
+                };
+                let mut chain = MessageChain::default();
+                chain.push(Text::new(format!("[{}] ", message.sender_name)));
+                match chain_from_bridge_message(&client, &message, &target).await {
+                    Ok(converted) => chain.0.extend(converted.0),
+                    Err(err) => {
+                        tracing::warn!(target = "proc_qq", "转换消息失败 : {:?}", err);
+                        continue;
+                    }
+                }
+                if let Err(err) = client.send_message_to_target(&target, chain).await {
+                    tracing::warn!(target = "proc_qq", "发送消息到群失败 : {:?}", err);
+                }
+            }
+            tracing::info!(
+                target = "proc_qq",
+                "{} 的连接已关闭",
+                state.transport.name()
+            );
+        });
+        // 重新连接后替换掉上一次的接收任务
+        if let Some(old) = self.0.receiver.lock().unwrap().replace(handle) {
+            old.abort();
+        }
+        Ok(false)
+    }
+}
+
+impl Into<ModuleEventHandler> for BridgeOutgoing {
+    fn into(self) -> ModuleEventHandler {
+        ModuleEventHandler::new(
+            "bridge_outgoing",
+            ModuleEventProcess::GroupMessage(Box::new(self)),
+        )
+    }
+}
+
+impl Into<ModuleEventHandler> for BridgeIncoming {
+    fn into(self) -> ModuleEventHandler {
+        ModuleEventHandler::new(
+            "bridge_incoming",
+            ModuleEventProcess::ConnectedAndOnline(Box::new(self)),
+        )
+    }
+}
+
+/// 跨平台互通模块, 群消息发送到transport, transport收到的消息发送到群
+pub fn bridge_module<T: BridgeTransport + 'static>(group_code: i64, transport: T) -> Module {
+    let state = Arc::new(BridgeState {
+        group_code,
+        transport: Arc::new(transport),
+        receiver: Mutex::new(None),
+    });
+    module!(
+        "bridge",
+        "跨平台互通",
+        BridgeOutgoing(state.clone()),
+        BridgeIncoming(state)
+    )
+}
@@ -0,0 +1,194 @@
+use crate::{
+    group_today, module, ClientTrait, GroupMessageEventProcess, GroupTrait, KvStore, Leaderboard,
+    MessageChainParseTrait, MessageSendToSourceTrait, Module, ModuleEventHandler,
+    ModuleEventProcess,
+};
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use rs_qq::client::event::GroupMessageEvent;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OwnedMutexGuard;
+
+const MODULE_ID: &str = "checkin";
+
+type MemberLock = Arc<tokio::sync::Mutex<()>>;
+
+lazy_static! {
+    // 每个群成员一把锁, 不同成员的签到互不等待
+    static ref MEMBER_LOCKS: Mutex<HashMap<(i64, i64), MemberLock>> = Mutex::new(HashMap::new());
+}
+
+struct MemberGuard {
+    key: (i64, i64),
+    lock: MemberLock,
+    _guard: OwnedMutexGuard<()>,
+}
+
+impl Drop for MemberGuard {
+    fn drop(&mut self) {
+        let mut locks = MEMBER_LOCKS.lock().unwrap();
+        // 表中, lock和_guard各持有一个引用, 没有其他等待者时移除
+        if Arc::strong_count(&self.lock) <= 3 {
+            locks.remove(&self.key);
+        }
+    }
+}
+
+async fn lock_member(group_code: i64, uin: i64) -> MemberGuard {
+    let key = (group_code, uin);
+    let lock = MEMBER_LOCKS.lock().unwrap().entry(key).or_default().clone();
+    let guard = lock.clone().lock_owned().await;
+    MemberGuard {
+        key,
+        lock,
+        _guard: guard,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckinConfig {
+    pub command: String,
+    pub rank_command: String,
+    // 每次签到获得的积分
+    pub points: i64,
+    // 连续签到每多一天额外获得的积分
+    pub streak_bonus: i64,
+    // 额外积分的上限
+    pub max_streak_bonus: i64,
+    // 签到成功的回复, 可以使用 {points} {streak} {total}
+    pub reply: String,
+    // 已经签到过的回复
+    pub already_reply: String,
+    pub rank_size: usize,
+}
+
+impl Default for CheckinConfig {
+    fn default() -> Self {
+        Self {
+            command: "签到".to_owned(),
+            rank_command: "签到排行".to_owned(),
+            points: 10,
+            streak_bonus: 2,
+            max_streak_bonus: 20,
+            reply: "签到成功, 获得{points}积分, 已连续签到{streak}天, 共有{total}积分".to_owned(),
+            already_reply: "今天已经签到过了, 已连续签到{streak}天".to_owned(),
+            rank_size: 10,
+        }
+    }
+}
+
+/// 每个群独立的每日签到, 积分保存在Leaderboard中
+pub struct Checkin {
+    config: CheckinConfig,
+}
+
+#[async_trait]
+impl GroupMessageEventProcess for Checkin {
+    async fn handle(&self, event: &GroupMessageEvent) -> anyhow::Result<bool> {
+        let content = event.message.elements.to_string();
+        let content = content.trim();
+        let reply = if content == self.config.command {
+            self.checkin(event).await?
+        } else if content == self.config.rank_command {
+            self.rank(event).await?
+        } else {
+            return Ok(false);
+        };
+        event
+            .send_message_to_source(reply.parse_message_chain())
+            .await?;
+        Ok(true)
+    }
+}
+
+impl Checkin {
+    async fn checkin(&self, event: &GroupMessageEvent) -> anyhow::Result<String> {
+        let group_code = event.message.group_code;
+        let uin = event.message.from_uin;
+        // 读取和写入之间不能有同一个人的另一次签到, 否则会重复获得积分
+        let _guard = lock_member(group_code, uin).await;
+        let store = KvStore::new(MODULE_ID, Some(group_code), Some(uin));
+        let today = group_today(group_code);
+        let last_day: Option<i64> = store.get("last_day").await?;
+        let streak: i64 = store.get("streak").await?.unwrap_or_default();
+        if last_day == Some(today) {
+            return Ok(self
+                .config
+                .already_reply
+                .replace("{streak}", &streak.to_string()));
+        }
+        let streak = if last_day == Some(today - 1) {
+            streak + 1
+        } else {
+            1
+        };
+        let points = self.config.points
+            + ((streak - 1) * self.config.streak_bonus).min(self.config.max_streak_bonus);
+        store.set("last_day", &today).await?;
+        store.set("streak", &streak).await?;
+        let total = Leaderboard::new(MODULE_ID, Some(group_code), "points")
+            .incr(uin, points)
+            .await?;
+        Ok(self
+            .config
+            .reply
+            .replace("{points}", &points.to_string())
+            .replace("{streak}", &streak.to_string())
+            .replace("{total}", &total.to_string()))
+    }
+
+    async fn rank(&self, event: &GroupMessageEvent) -> anyhow::Result<String> {
+        let group_code = event.message.group_code;
+        let top = Leaderboard::new(MODULE_ID, Some(group_code), "points")
+            .top_n(self.config.rank_size)
+            .await?;
+        if top.is_empty() {
+            return Ok("还没有人签到".to_owned());
+        }
+        let group = event.must_find_group(group_code, false).await?;
+        let mut lines = vec![];
+        for (index, (uin, score)) in top.into_iter().enumerate() {
+            let name = match group.must_find_member(uin).await {
+                Ok(member) if !member.card_name.is_empty() => member.card_name,
+                Ok(member) => member.nickname,
+                Err(_) => uin.to_string(),
+            };
+            lines.push(format!("{}. {} : {}", index + 1, name, score));
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+impl Into<ModuleEventHandler> for Checkin {
+    fn into(self) -> ModuleEventHandler {
+        ModuleEventHandler::new("checkin", ModuleEventProcess::GroupMessage(Box::new(self)))
+    }
+}
+
+/// 签到模块
+pub fn checkin_module(config: CheckinConfig) -> Module {
+    module!(MODULE_ID, "签到", Checkin { config })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn locks_each_member() {
+        let first = lock_member(7_000_684, 1).await;
+        // 同群的其他成员不需要等待
+        let other = lock_member(7_000_684, 2).await;
+        let same = tokio::time::timeout(Duration::from_millis(20), lock_member(7_000_684, 1)).await;
+        assert!(same.is_err());
+        drop(first);
+        let second = lock_member(7_000_684, 1).await;
+        drop(second);
+        drop(other);
+        // 释放后从表中移除
+        let locks = MEMBER_LOCKS.lock().unwrap();
+        assert!(!locks.contains_key(&(7_000_684, 1)) && !locks.contains_key(&(7_000_684, 2)));
+    }
+}
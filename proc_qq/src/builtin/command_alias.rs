@@ -0,0 +1,87 @@
+use crate::{
+    command_aliases, group_framework_messages, module, remove_group_command_alias,
+    set_group_command_alias, ClientTrait, GroupMessageEventProcess, GroupTrait, MemberTrait,
+    MessageChainParseTrait, MessageSendToSourceTrait, Module, ModuleEventHandler,
+    ModuleEventProcess,
+};
+use async_trait::async_trait;
+use rs_qq::client::event::GroupMessageEvent;
+
+/// 群管理员通过命令维护本群的命令别名
+///
+/// - 别名 签到 checkin : 设置别名
+/// - 删除别名 签到 : 删除别名
+/// - 别名列表 : 查看生效的别名
+pub struct CommandAliasAdmin;
+
+#[async_trait]
+impl GroupMessageEventProcess for CommandAliasAdmin {
+    async fn handle(&self, event: &GroupMessageEvent) -> anyhow::Result<bool> {
+        let group_code = event.message.group_code;
+        let content = event.message.elements.to_string();
+        let words: Vec<&str> = content.split_whitespace().collect();
+        let reply = match words.as_slice() {
+            ["别名列表"] => {
+                let mut aliases: Vec<(String, String)> =
+                    command_aliases(Some(group_code)).into_iter().collect();
+                aliases.sort();
+                if aliases.is_empty() {
+                    "没有设置别名".to_owned()
+                } else {
+                    aliases
+                        .iter()
+                        .map(|(alias, command)| format!("{} -> {}", alias, command))
+                        .collect::<Vec<String>>()
+                        .join("\n")
+                }
+            }
+            ["别名", alias, command] => {
+                if !self.is_admin(event).await? {
+                    group_framework_messages(group_code).permission_denied()
+                } else {
+                    set_group_command_alias(group_code, *alias, *command);
+                    format!("已设置别名 : {} -> {}", alias, command)
+                }
+            }
+            ["删除别名", alias] => {
+                if !self.is_admin(event).await? {
+                    group_framework_messages(group_code).permission_denied()
+                } else {
+                    match remove_group_command_alias(group_code, alias) {
+                        Some(command) => format!("已删除别名 : {} -> {}", alias, command),
+                        None => format!("没有找到别名 : {}", alias),
+                    }
+                }
+            }
+            _ => return Ok(false),
+        };
+        event
+            .send_message_to_source(reply.parse_message_chain())
+            .await?;
+        Ok(true)
+    }
+}
+
+impl CommandAliasAdmin {
+    async fn is_admin(&self, event: &GroupMessageEvent) -> anyhow::Result<bool> {
+        let group = event
+            .must_find_group(event.message.group_code, true)
+            .await?;
+        let member = group.must_find_member(event.message.from_uin).await?;
+        Ok(!member.is_member())
+    }
+}
+
+impl Into<ModuleEventHandler> for CommandAliasAdmin {
+    fn into(self) -> ModuleEventHandler {
+        ModuleEventHandler::new(
+            "command_alias",
+            ModuleEventProcess::GroupMessage(Box::new(self)),
+        )
+    }
+}
+
+/// 命令别名管理模块
+pub fn command_alias_module() -> Module {
+    module!("command_alias", "命令别名", CommandAliasAdmin)
+}
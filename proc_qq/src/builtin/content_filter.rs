@@ -0,0 +1,373 @@
+use crate::{
+    module, ClientTrait, GroupMessageEventProcess, GroupMessageRecallTrait, GroupTrait, KvStore,
+    MemberTrait, MessageChainParseTrait, MessageSendToSourceTrait, Module, ModuleEventHandler,
+    ModuleEventProcess,
+};
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use regex::Regex;
+use rs_qq::client::event::GroupMessageEvent;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+const MODULE_ID: &str = "content_filter";
+// 每个群最多保留的审计记录数
+const AUDIT_LIMIT: usize = 500;
+
+lazy_static! {
+    // 读取-追加-写回之间不能插入另一条记录, 否则会丢失记录
+    static ref AUDIT_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// 违规的严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+/// 违规时执行的操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterAction {
+    // 撤回消息, 需要机器人是管理员
+    Recall,
+    // 在群里回复警告
+    Warn(String),
+    // 禁言发送者
+    Mute(Duration),
+}
+
+/// 外部的内容识别服务, 返回None表示没有违规
+#[async_trait]
+pub trait ContentClassifier: Send + Sync {
+    async fn classify(&self, content: &str) -> anyhow::Result<Option<Severity>>;
+}
+
+enum FilterRule {
+    Words(Vec<String>, Severity),
+    Regex(Regex, Severity),
+}
+
+/// 严重程度对应的操作
+pub type FilterPolicy = HashMap<Severity, Vec<FilterAction>>;
+
+pub struct ContentFilterConfig {
+    rules: Vec<FilterRule>,
+    classifier: Option<Arc<dyn ContentClassifier>>,
+    default_policy: FilterPolicy,
+    group_policies: HashMap<i64, FilterPolicy>,
+    // 不检查群主和管理员的消息
+    skip_admins: bool,
+}
+
+impl Default for ContentFilterConfig {
+    fn default() -> Self {
+        let mut default_policy = FilterPolicy::new();
+        default_policy.insert(
+            Severity::Low,
+            vec![FilterAction::Warn("请注意发言".to_owned())],
+        );
+        default_policy.insert(
+            Severity::Medium,
+            vec![
+                FilterAction::Recall,
+                FilterAction::Warn("消息包含违规内容, 已撤回".to_owned()),
+            ],
+        );
+        default_policy.insert(
+            Severity::High,
+            vec![
+                FilterAction::Recall,
+                FilterAction::Mute(Duration::from_secs(600)),
+            ],
+        );
+        Self {
+            rules: vec![],
+            classifier: None,
+            default_policy,
+            group_policies: HashMap::new(),
+            skip_admins: true,
+        }
+    }
+}
+
+impl ContentFilterConfig {
+    /// 包含任意一个词时违规
+    pub fn words<S: Into<String>>(mut self, severity: Severity, words: Vec<S>) -> Self {
+        self.rules.push(FilterRule::Words(
+            words.into_iter().map(Into::into).collect(),
+            severity,
+        ));
+        self
+    }
+
+    /// 匹配正则表达式时违规
+    pub fn regex(mut self, severity: Severity, pattern: &str) -> Result<Self, regex::Error> {
+        self.rules
+            .push(FilterRule::Regex(Regex::new(pattern)?, severity));
+        Ok(self)
+    }
+
+    /// 规则都没有匹配时再交给classifier识别
+    pub fn classifier<C: ContentClassifier + 'static>(mut self, classifier: C) -> Self {
+        self.classifier = Some(Arc::new(classifier));
+        self
+    }
+
+    /// 所有群默认的操作
+    pub fn policy(mut self, severity: Severity, actions: Vec<FilterAction>) -> Self {
+        self.default_policy.insert(severity, actions);
+        self
+    }
+
+    /// 单独设置某个群的操作, 没有设置的严重程度使用默认操作
+    pub fn group_policy(
+        mut self,
+        group_code: i64,
+        severity: Severity,
+        actions: Vec<FilterAction>,
+    ) -> Self {
+        self.group_policies
+            .entry(group_code)
+            .or_default()
+            .insert(severity, actions);
+        self
+    }
+
+    pub fn skip_admins(mut self, skip_admins: bool) -> Self {
+        self.skip_admins = skip_admins;
+        self
+    }
+
+    fn actions(&self, group_code: i64, severity: Severity) -> &[FilterAction] {
+        self.group_policies
+            .get(&group_code)
+            .and_then(|policy| policy.get(&severity))
+            .or_else(|| self.default_policy.get(&severity))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    async fn check(&self, content: &str) -> anyhow::Result<Option<Severity>> {
+        let severity = self
+            .rules
+            .iter()
+            .filter(|rule| match rule {
+                FilterRule::Words(words, _) => words.iter().any(|word| content.contains(word)),
+                FilterRule::Regex(regex, _) => regex.is_match(content),
+            })
+            .map(|rule| match rule {
+                FilterRule::Words(_, severity) | FilterRule::Regex(_, severity) => *severity,
+            })
+            .max();
+        if severity.is_some() {
+            return Ok(severity);
+        }
+        match &self.classifier {
+            Some(classifier) => classifier.classify(content).await,
+            None => Ok(None),
+        }
+    }
+}
+
+/// 一次违规的审计记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilterRecord {
+    pub time: i64,
+    pub group_code: i64,
+    pub uin: i64,
+    // 设置了set_log_redactor时为隐藏敏感信息后的内容
+    pub content: String,
+    pub severity: Severity,
+    // 执行的操作, 失败的操作带有错误信息
+    pub actions: Vec<String>,
+}
+
+/// 查询群里最近的违规记录, 从旧到新
+pub async fn content_filter_audit(group_code: i64) -> anyhow::Result<Vec<FilterRecord>> {
+    Ok(KvStore::new(MODULE_ID, Some(group_code), None)
+        .get("audit")
+        .await?
+        .unwrap_or_default())
+}
+
+async fn append_audit(record: FilterRecord) -> anyhow::Result<()> {
+    let _guard = AUDIT_LOCK.lock().await;
+    let store = KvStore::new(MODULE_ID, Some(record.group_code), None);
+    let mut records: Vec<FilterRecord> = store.get("audit").await?.unwrap_or_default();
+    records.push(record);
+    if records.len() > AUDIT_LIMIT {
+        records.drain(..records.len() - AUDIT_LIMIT);
+    }
+    store.set("audit", &records).await
+}
+
+/// 检查群消息, 按照严重程度撤回/警告/禁言
+pub struct ContentFilter {
+    config: ContentFilterConfig,
+}
+
+#[async_trait]
+impl GroupMessageEventProcess for ContentFilter {
+    async fn handle(&self, event: &GroupMessageEvent) -> anyhow::Result<bool> {
+        let group_code = event.message.group_code;
+        let uin = event.message.from_uin;
+        if uin == event.bot_uin().await {
+            return Ok(false);
+        }
+        let content = event.message.elements.to_string();
+        let severity = match self.config.check(&content).await? {
+            Some(severity) => severity,
+            None => return Ok(false),
+        };
+        if self.config.skip_admins {
+            let group = event.must_find_group(group_code, false).await?;
+            if let Ok(member) = group.must_find_member(uin).await {
+                if !member.is_member() {
+                    return Ok(false);
+                }
+            }
+        }
+        let mut actions = vec![];
+        for action in self.config.actions(group_code, severity) {
+            let (name, result) = match action {
+                FilterAction::Recall => ("recall".to_owned(), event.recall_message().await),
+                FilterAction::Warn(text) => (
+                    "warn".to_owned(),
+                    event
+                        .send_message_to_source(text.clone().parse_message_chain())
+                        .await
+                        .map(|_| ()),
+                ),
+                FilterAction::Mute(duration) => (
+                    format!("mute {}s", duration.as_secs()),
+                    event.mute_member(group_code, uin, *duration).await,
+                ),
+            };
+            actions.push(match result {
+                Ok(_) => name,
+                Err(err) => format!("{} ({:?})", name, err),
+            });
+        }
+        tracing::info!(
+            target = "proc_qq",
+            "群 {} 的成员 {} 发送了违规消息 ({:?}) : {:?}",
+            group_code,
+            uin,
+            severity,
+            actions
+        );
+        append_audit(FilterRecord {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs() as i64,
+            group_code,
+            uin,
+            content: crate::log_text(&content),
+            severity,
+            actions,
+        })
+        .await?;
+        Ok(true)
+    }
+}
+
+impl Into<ModuleEventHandler> for ContentFilter {
+    fn into(self) -> ModuleEventHandler {
+        ModuleEventHandler::new(
+            "content_filter",
+            ModuleEventProcess::GroupMessage(Box::new(self)),
+        )
+    }
+}
+
+/// 内容过滤模块, 需要放在其他模块前面注册
+pub fn content_filter_module(config: ContentFilterConfig) -> Module {
+    module!(MODULE_ID, "内容过滤", ContentFilter { config })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Always(Severity);
+
+    #[async_trait]
+    impl ContentClassifier for Always {
+        async fn classify(&self, _: &str) -> anyhow::Result<Option<Severity>> {
+            Ok(Some(self.0))
+        }
+    }
+
+    #[tokio::test]
+    async fn highest_matching_rule_wins() {
+        let config = ContentFilterConfig::default()
+            .words(Severity::Low, vec!["笨"])
+            .regex(Severity::High, r"加群\d+")
+            .unwrap();
+        assert_eq!(config.check("你好").await.unwrap(), None);
+        assert_eq!(config.check("笨").await.unwrap(), Some(Severity::Low));
+        assert_eq!(
+            config.check("笨, 加群123").await.unwrap(),
+            Some(Severity::High)
+        );
+        // 规则都没有匹配时才使用classifier
+        let config = config.classifier(Always(Severity::Medium));
+        assert_eq!(config.check("笨").await.unwrap(), Some(Severity::Low));
+        assert_eq!(config.check("你好").await.unwrap(), Some(Severity::Medium));
+    }
+
+    #[test]
+    fn group_policy_overrides_default() {
+        let config = ContentFilterConfig::default()
+            .policy(Severity::Low, vec![])
+            .group_policy(1, Severity::High, vec![FilterAction::Recall]);
+        assert!(config.actions(2, Severity::Low).is_empty());
+        assert_eq!(config.actions(1, Severity::High), [FilterAction::Recall]);
+        assert_eq!(config.actions(1, Severity::Medium).len(), 2);
+        assert_eq!(
+            config.actions(2, Severity::High)[1],
+            FilterAction::Mute(Duration::from_secs(600))
+        );
+    }
+
+    fn record(group_code: i64, uin: i64) -> FilterRecord {
+        FilterRecord {
+            time: 0,
+            group_code,
+            uin,
+            content: String::new(),
+            severity: Severity::Low,
+            actions: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn audit_keeps_latest_records() {
+        let group_code = 7_000_685;
+        for uin in 0..AUDIT_LIMIT as i64 + 2 {
+            append_audit(record(group_code, uin)).await.unwrap();
+        }
+        let records = content_filter_audit(group_code).await.unwrap();
+        assert_eq!(records.len(), AUDIT_LIMIT);
+        assert_eq!(records[0].uin, 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_records_are_kept() {
+        let group_code = 7_000_686;
+        let tasks: Vec<_> = (0..200)
+            .map(|uin| tokio::spawn(append_audit(record(group_code, uin))))
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+        let records = content_filter_audit(group_code).await.unwrap();
+        assert_eq!(records.len(), 200);
+    }
+}
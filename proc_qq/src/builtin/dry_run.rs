@@ -0,0 +1,92 @@
+use crate::{
+    admin_targets, dry_run_actions, dry_run_modules, dry_run_sends, module, set_dry_run,
+    MessageChainParseTrait, MessageContentTrait, MessageEvent, MessageEventProcess,
+    MessageSendToSourceTrait, MessageTarget, Module, ModuleEventHandler, ModuleEventProcess,
+};
+use async_trait::async_trait;
+use std::time::SystemTime;
+
+// 试运行记录最多回复的条数
+const RECENT_RECORDS: usize = 10;
+
+/// 管理员 (set_admin_targets 中的QQ号) 通过命令让模块试运行, 试运行的模块发送的消息和执行的操作只记录不执行
+///
+/// - 试运行 模块ID : 开始试运行
+/// - 试运行 模块ID 关闭 : 停止试运行
+/// - 试运行记录 : 最近试运行的模块发送的消息和执行的操作
+pub struct DryRunCommand;
+
+#[async_trait]
+impl MessageEventProcess for DryRunCommand {
+    async fn handle(&self, event: &MessageEvent) -> anyhow::Result<bool> {
+        let content = event.message_content();
+        if !content.trim_start().starts_with("试运行")
+            || !admin_targets().contains(&MessageTarget::Private(event.from_uin()))
+        {
+            return Ok(false);
+        }
+        let words: Vec<&str> = content.split_whitespace().collect();
+        let reply = match words.as_slice() {
+            ["试运行", module_id] => {
+                set_dry_run(module_id, true);
+                format!(
+                    "模块 {} 开始试运行, 试运行中 : {}",
+                    module_id,
+                    dry_run_modules().join(", ")
+                )
+            }
+            ["试运行", module_id, "关闭"] => {
+                set_dry_run(module_id, false);
+                format!("模块 {} 停止试运行", module_id)
+            }
+            ["试运行记录"] => {
+                let mut records: Vec<(SystemTime, String)> = dry_run_sends()
+                    .into_iter()
+                    .map(|send| {
+                        let line = format!(
+                            "[{}] {} : {}",
+                            send.actor.module_id,
+                            crate::log_target(&send.target),
+                            send.chain.message_content()
+                        );
+                        (send.time, line)
+                    })
+                    .chain(dry_run_actions().into_iter().map(|action| {
+                        let line = format!(
+                            "[{}] {} {}",
+                            action.actor.module_id, action.action, action.detail
+                        );
+                        (action.time, line)
+                    }))
+                    .collect();
+                if records.is_empty() {
+                    "没有试运行的记录".to_owned()
+                } else {
+                    records.sort_by_key(|(time, _)| std::cmp::Reverse(*time));
+                    records
+                        .into_iter()
+                        .take(RECENT_RECORDS)
+                        .map(|(_, line)| line)
+                        .collect::<Vec<String>>()
+                        .join("\n")
+                }
+            }
+            _ => return Ok(false),
+        };
+        event
+            .send_message_to_source(reply.parse_message_chain())
+            .await?;
+        Ok(true)
+    }
+}
+
+impl Into<ModuleEventHandler> for DryRunCommand {
+    fn into(self) -> ModuleEventHandler {
+        ModuleEventHandler::new("dry_run", ModuleEventProcess::Message(Box::new(self)))
+    }
+}
+
+/// 试运行命令模块
+pub fn dry_run_module() -> Module {
+    module!("dry_run", "试运行", DryRunCommand)
+}
@@ -0,0 +1,247 @@
+use crate::{
+    module, ClientTrait, FriendMessageEventProcess, GroupRequestEventProcess,
+    MessageChainParseTrait, MessageSendToSourceTrait, MessageTarget, Module, ModuleEventHandler,
+    ModuleEventProcess, RequestTrait,
+};
+use async_trait::async_trait;
+use rs_qq::client::event::{FriendMessageEvent, GroupRequestEvent};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const MODULE_ID: &str = "join_approval";
+
+#[derive(Debug, Clone)]
+pub struct JoinApprovalConfig {
+    /// 接收加群请求并可以审批的QQ号 (需要是机器人的好友)
+    pub admins: Vec<i64>,
+    /// 超过这个时间没有处理的请求会被丢弃, 交给群管理员处理
+    pub expire: Duration,
+}
+
+impl Default for JoinApprovalConfig {
+    fn default() -> Self {
+        Self {
+            admins: vec![],
+            expire: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+// 编号从1到999, 方便在手机上回复
+const MAX_PENDING: u32 = 999;
+
+struct PendingRequests<T> {
+    next_code: u32,
+    requests: HashMap<u32, (Instant, T)>,
+}
+
+impl<T> Default for PendingRequests<T> {
+    fn default() -> Self {
+        Self {
+            next_code: 0,
+            requests: HashMap::new(),
+        }
+    }
+}
+
+impl<T> PendingRequests<T> {
+    /// 先清理过期的请求, 编号全部被占用时返回None
+    fn insert(&mut self, request: T, expire: Duration) -> Option<u32> {
+        self.requests.retain(|_, (time, _)| time.elapsed() < expire);
+        for _ in 0..MAX_PENDING {
+            self.next_code = self.next_code % MAX_PENDING + 1;
+            if let Entry::Vacant(entry) = self.requests.entry(self.next_code) {
+                entry.insert((Instant::now(), request));
+                return Some(self.next_code);
+            }
+        }
+        None
+    }
+
+    fn take(&mut self, code: u32, expire: Duration) -> Option<T> {
+        match self.requests.remove(&code) {
+            Some((time, request)) if time.elapsed() < expire => Some(request),
+            _ => None,
+        }
+    }
+}
+
+/// 把加群请求转发给管理员
+pub struct JoinRequestForwarder {
+    config: JoinApprovalConfig,
+    pending: Arc<Mutex<PendingRequests<GroupRequestEvent>>>,
+}
+
+#[async_trait]
+impl GroupRequestEventProcess for JoinRequestForwarder {
+    async fn handle(&self, event: &GroupRequestEvent) -> anyhow::Result<bool> {
+        if self.config.admins.is_empty() {
+            return Ok(false);
+        }
+        let request = &event.request;
+        let code = self
+            .pending
+            .lock()
+            .unwrap()
+            .insert(event.clone(), self.config.expire);
+        let code = match code {
+            Some(code) => code,
+            None => {
+                tracing::warn!(
+                    target = "proc_qq",
+                    "待审批的加群请求已满 ({}), 交给群管理员处理",
+                    MAX_PENDING
+                );
+                return Ok(false);
+            }
+        };
+        let text = format!(
+            "#{} {}({}) 申请加入群 {}({})\n{}\n回复 \"approve {}\" 同意, \"reject {} 理由\" 拒绝",
+            code,
+            request.req_nick,
+            request.req_uin,
+            request.group_name,
+            request.group_code,
+            request.message,
+            code,
+            code
+        );
+        let mut forwarded = false;
+        for admin in &self.config.admins {
+            match event
+                .client
+                .send_message_to_target(
+                    &MessageTarget::Private(*admin),
+                    text.clone().parse_message_chain(),
+                )
+                .await
+            {
+                Ok(_) => forwarded = true,
+                Err(err) => tracing::warn!(
+                    target = "proc_qq",
+                    "转发加群请求失败 ({}) : {:?}",
+                    crate::log_uin(*admin),
+                    err
+                ),
+            }
+        }
+        Ok(forwarded)
+    }
+}
+
+impl Into<ModuleEventHandler> for JoinRequestForwarder {
+    fn into(self) -> ModuleEventHandler {
+        ModuleEventHandler::new(
+            "forward_request",
+            ModuleEventProcess::GroupRequest(Box::new(self)),
+        )
+    }
+}
+
+/// 管理员回复 approve / reject 处理请求
+pub struct JoinRequestApprover {
+    config: JoinApprovalConfig,
+    pending: Arc<Mutex<PendingRequests<GroupRequestEvent>>>,
+}
+
+#[async_trait]
+impl FriendMessageEventProcess for JoinRequestApprover {
+    async fn handle(&self, event: &FriendMessageEvent) -> anyhow::Result<bool> {
+        if !self.config.admins.contains(&event.message.from_uin) {
+            return Ok(false);
+        }
+        let content = event.message.elements.to_string();
+        let mut words = content.trim().splitn(3, char::is_whitespace);
+        let accept = match words.next() {
+            Some("approve") | Some("同意") => true,
+            Some("reject") | Some("拒绝") => false,
+            _ => return Ok(false),
+        };
+        let code = match words.next().and_then(|code| code.parse::<u32>().ok()) {
+            Some(code) => code,
+            None => return Ok(false),
+        };
+        let reason = words.next().unwrap_or_default().trim();
+        let request = self.pending.lock().unwrap().take(code, self.config.expire);
+        let reply = match request {
+            None => format!("没有找到请求 #{}, 可能已经过期或被处理", code),
+            Some(request) => {
+                let result = if accept {
+                    request.accept().await
+                } else {
+                    request.reject(reason).await
+                };
+                match result {
+                    Ok(_) if accept => format!("已同意 #{}", code),
+                    Ok(_) => format!("已拒绝 #{}", code),
+                    Err(err) => format!("处理 #{} 失败 : {:?}", code, err),
+                }
+            }
+        };
+        event
+            .send_message_to_source(reply.parse_message_chain())
+            .await?;
+        Ok(true)
+    }
+}
+
+impl Into<ModuleEventHandler> for JoinRequestApprover {
+    fn into(self) -> ModuleEventHandler {
+        ModuleEventHandler::new(
+            "approve_request",
+            ModuleEventProcess::FriendMessage(Box::new(self)),
+        )
+    }
+}
+
+/// 加群请求转发给管理员, 管理员回复编号审批
+pub fn join_approval_module(config: JoinApprovalConfig) -> Module {
+    let pending = Arc::new(Mutex::new(PendingRequests::default()));
+    module!(
+        MODULE_ID,
+        "加群审批",
+        JoinRequestForwarder {
+            config: config.clone(),
+            pending: pending.clone(),
+        },
+        JoinRequestApprover { config, pending },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_wrap_around_and_skip_pending() {
+        let expire = Duration::from_secs(60);
+        let mut pending = PendingRequests::default();
+        assert_eq!(pending.insert("a", expire), Some(1));
+        assert_eq!(pending.insert("b", expire), Some(2));
+        pending.next_code = MAX_PENDING;
+        assert_eq!(pending.insert("c", expire), Some(3));
+        assert_eq!(pending.take(2, expire), Some("b"));
+        assert_eq!(pending.take(2, expire), None);
+    }
+
+    #[test]
+    fn full_returns_none() {
+        let expire = Duration::from_secs(60);
+        let mut pending = PendingRequests::default();
+        for code in 1..=MAX_PENDING {
+            assert_eq!(pending.insert(code, expire), Some(code));
+        }
+        assert_eq!(pending.insert(0, expire), None);
+    }
+
+    #[test]
+    fn expired_requests_free_their_codes() {
+        let mut pending = PendingRequests::default();
+        for code in 1..=MAX_PENDING {
+            pending.insert(code, Duration::from_secs(60));
+        }
+        assert_eq!(pending.insert(0, Duration::ZERO), Some(1));
+    }
+}
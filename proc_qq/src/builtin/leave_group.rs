@@ -0,0 +1,58 @@
+use crate::{
+    admin_targets, module, ClientTrait, MessageChainParseTrait, MessageContentTrait, MessageEvent,
+    MessageEventProcess, MessageSendToSourceTrait, MessageTarget, Module, ModuleEventHandler,
+    ModuleEventProcess,
+};
+use async_trait::async_trait;
+
+/// 管理员 (set_admin_targets 中的QQ号) 通过命令让机器人退群
+///
+/// - 退群 : 退出当前群
+/// - 退群 群号 : 退出指定的群
+///
+/// 开启了退群确认 (set_leave_confirmation) 时需要发送两次
+pub struct LeaveGroupCommand;
+
+#[async_trait]
+impl MessageEventProcess for LeaveGroupCommand {
+    async fn handle(&self, event: &MessageEvent) -> anyhow::Result<bool> {
+        let content = event.message_content();
+        let words: Vec<&str> = content.split_whitespace().collect();
+        let group_code = match words.as_slice() {
+            ["退群"] => match event.as_group_message() {
+                Ok(group_message) => group_message.message.group_code,
+                Err(_) => return Ok(false),
+            },
+            ["退群", group_code] => match group_code.parse::<i64>() {
+                Ok(group_code) => group_code,
+                Err(_) => return Ok(false),
+            },
+            _ => return Ok(false),
+        };
+        if !admin_targets().contains(&MessageTarget::Private(event.from_uin())) {
+            return Ok(false);
+        }
+        let current_group = event.as_group_message().ok().map(|e| e.message.group_code);
+        let reply = match event.leave_group(group_code).await {
+            // 退出当前群之后不能再回复
+            Ok(_) if current_group == Some(group_code) => return Ok(true),
+            Ok(_) => format!("已退出群 {}", group_code),
+            Err(err) => format!("{}", err),
+        };
+        event
+            .send_message_to_source(reply.parse_message_chain())
+            .await?;
+        Ok(true)
+    }
+}
+
+impl Into<ModuleEventHandler> for LeaveGroupCommand {
+    fn into(self) -> ModuleEventHandler {
+        ModuleEventHandler::new("leave_group", ModuleEventProcess::Message(Box::new(self)))
+    }
+}
+
+/// 退群命令模块
+pub fn leave_group_module() -> Module {
+    module!("leave_group", "退群", LeaveGroupCommand)
+}
@@ -0,0 +1,39 @@
+//! 可选的内置模块, 在build时和其他模块一起注册即可使用
+
+pub use audit::*;
+pub use bridge::*;
+pub use checkin::*;
+pub use command_alias::*;
+pub use content_filter::*;
+pub use dry_run::*;
+pub use join_approval::*;
+pub use leave_group::*;
+pub use poke_reply::*;
+pub use relay::*;
+pub use reminder::*;
+pub use repeater::*;
+#[cfg(feature = "rss")]
+pub use rss::*;
+#[cfg(feature = "scripting")]
+pub use scripting::*;
+pub use translate::*;
+pub use version::*;
+
+mod audit;
+mod bridge;
+mod checkin;
+mod command_alias;
+mod content_filter;
+mod dry_run;
+mod join_approval;
+mod leave_group;
+mod poke_reply;
+mod relay;
+mod reminder;
+mod repeater;
+#[cfg(feature = "rss")]
+mod rss;
+#[cfg(feature = "scripting")]
+mod scripting;
+mod translate;
+mod version;
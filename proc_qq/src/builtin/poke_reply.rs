@@ -0,0 +1,165 @@
+use crate::{
+    module, ClientTrait, FriendMessageEventProcess, FriendPokeEventProcess, KvStore,
+    MessageChainParseTrait, MessageSendToSourceTrait, MessageTarget, Module, ModuleEventHandler,
+    ModuleEventProcess,
+};
+use async_trait::async_trait;
+use rq_engine::msg::MessageChain;
+use rs_qq::client::event::{FriendMessageEvent, FriendPokeEvent};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const MODULE_ID: &str = "poke_reply";
+
+/// 被戳之后的回应, 按顺序全部执行
+#[derive(Debug, Clone)]
+pub enum PokeReply {
+    /// 戳回去
+    PokeBack,
+    Text(String),
+    Image(Vec<u8>),
+}
+
+#[derive(Debug, Clone)]
+pub struct PokeReplyConfig {
+    pub replies: Vec<PokeReply>,
+    // 同一个好友两次回应的最小间隔
+    pub cooldown: Duration,
+    // 好友发送这些命令关闭或开启对自己的回应
+    pub disable_command: String,
+    pub enable_command: String,
+}
+
+impl Default for PokeReplyConfig {
+    fn default() -> Self {
+        Self {
+            replies: vec![PokeReply::PokeBack],
+            cooldown: Duration::from_secs(10),
+            disable_command: "关闭戳一戳".to_owned(),
+            enable_command: "开启戳一戳".to_owned(),
+        }
+    }
+}
+
+fn friend_store(uin: i64) -> KvStore {
+    KvStore::new(MODULE_ID, None, Some(uin))
+}
+
+/// 好友戳机器人时回应
+pub struct PokeResponder {
+    config: PokeReplyConfig,
+    last_reply: Mutex<HashMap<i64, Instant>>,
+}
+
+#[async_trait]
+impl FriendPokeEventProcess for PokeResponder {
+    async fn handle(&self, event: &FriendPokeEvent) -> anyhow::Result<bool> {
+        let uin = event.poke.sender;
+        let bot_uin = event.client.bot_uin().await;
+        if uin == bot_uin || event.poke.receiver != bot_uin {
+            return Ok(false);
+        }
+        if friend_store(uin)
+            .get::<bool>("disabled")
+            .await?
+            .unwrap_or_default()
+        {
+            return Ok(false);
+        }
+        {
+            let mut last_reply = self.last_reply.lock().unwrap();
+            if let Some(time) = last_reply.get(&uin) {
+                if time.elapsed() < self.config.cooldown {
+                    return Ok(true);
+                }
+            }
+            last_reply.retain(|_, time| time.elapsed() < self.config.cooldown);
+            last_reply.insert(uin, Instant::now());
+        }
+        let target = MessageTarget::Private(uin);
+        for reply in &self.config.replies {
+            match reply {
+                PokeReply::PokeBack => {
+                    if !crate::capture_dry_run_action("poke", || uin.to_string()) {
+                        event.client.friend_poke(uin).await?
+                    }
+                }
+                PokeReply::Text(text) => {
+                    event
+                        .client
+                        .send_message_to_target(&target, text.clone().parse_message_chain())
+                        .await?;
+                }
+                PokeReply::Image(data) => {
+                    let image = event
+                        .client
+                        .upload_friend_image(uin, crate::prepare_upload_image(data.clone()).await?)
+                        .await?;
+                    event
+                        .client
+                        .send_message_to_target(&target, MessageChain::new(image))
+                        .await?;
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl Into<ModuleEventHandler> for PokeResponder {
+    fn into(self) -> ModuleEventHandler {
+        ModuleEventHandler::new("poke_reply", ModuleEventProcess::FriendPoke(Box::new(self)))
+    }
+}
+
+/// 好友开启或关闭对自己的回应
+pub struct PokeReplySwitch {
+    config: PokeReplyConfig,
+}
+
+#[async_trait]
+impl FriendMessageEventProcess for PokeReplySwitch {
+    async fn handle(&self, event: &FriendMessageEvent) -> anyhow::Result<bool> {
+        let content = event.message.elements.to_string();
+        let disabled = match content.trim() {
+            command if command == self.config.disable_command => true,
+            command if command == self.config.enable_command => false,
+            _ => return Ok(false),
+        };
+        friend_store(event.message.from_uin)
+            .set("disabled", &disabled)
+            .await?;
+        let reply = if disabled {
+            "已关闭戳一戳回应"
+        } else {
+            "已开启戳一戳回应"
+        };
+        event
+            .send_message_to_source(reply.parse_message_chain())
+            .await?;
+        Ok(true)
+    }
+}
+
+impl Into<ModuleEventHandler> for PokeReplySwitch {
+    fn into(self) -> ModuleEventHandler {
+        ModuleEventHandler::new(
+            "poke_reply_switch",
+            ModuleEventProcess::FriendMessage(Box::new(self)),
+        )
+    }
+}
+
+/// 戳一戳自动回应模块
+pub fn poke_reply_module(config: PokeReplyConfig) -> Module {
+    module!(
+        MODULE_ID,
+        "戳一戳回应",
+        PokeResponder {
+            config: config.clone(),
+            last_reply: Mutex::new(HashMap::new()),
+        },
+        PokeReplySwitch { config },
+    )
+}
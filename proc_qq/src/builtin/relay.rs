@@ -0,0 +1,134 @@
+use crate::{
+    module, transfer_message_chain, ClientTrait, MessageEvent, MessageEventProcess, MessageTarget,
+    Module, ModuleEventHandler, ModuleEventProcess,
+};
+use async_trait::async_trait;
+use rq_engine::msg::elem::{RQElem, Text};
+use rq_engine::msg::MessageChain;
+
+// 转发出去的消息以这个字符开头, 收到带有标记的消息不再转发, 防止两个机器人互相转发
+const RELAY_MARK: char = '\u{200b}';
+
+/// 转发的一端
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelayEndpoint {
+    Group(i64),
+    Friend(i64),
+}
+
+impl RelayEndpoint {
+    fn target(&self) -> MessageTarget {
+        match self {
+            RelayEndpoint::Group(group_code) => MessageTarget::Group(*group_code, 0),
+            RelayEndpoint::Friend(uin) => MessageTarget::Private(*uin),
+        }
+    }
+
+    fn matches(&self, event: &MessageEvent) -> bool {
+        match (self, event) {
+            (RelayEndpoint::Group(group_code), MessageEvent::GroupMessage(event)) => {
+                event.message.group_code == *group_code
+            }
+            (RelayEndpoint::Friend(uin), MessageEvent::FriendMessage(event)) => {
+                event.message.from_uin == *uin
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RelayConfig {
+    // (来源, 目标)
+    pub routes: Vec<(RelayEndpoint, RelayEndpoint)>,
+    // 是否在转发的消息前加上 "[发送者] "
+    pub name_prefix: bool,
+}
+
+impl RelayConfig {
+    /// 两端互相转发
+    pub fn pair(a: RelayEndpoint, b: RelayEndpoint) -> Self {
+        Self {
+            routes: vec![(a, b), (b, a)],
+            name_prefix: true,
+        }
+    }
+}
+
+/// 在群和群 (或群和好友) 之间同步消息, 图片会重新上传
+pub struct Relay {
+    config: RelayConfig,
+}
+
+impl Relay {
+    pub fn new(config: RelayConfig) -> Self {
+        Self { config }
+    }
+}
+
+fn sender_name(event: &MessageEvent) -> String {
+    let name = match event {
+        MessageEvent::GroupMessage(event) => event.message.group_card.clone(),
+        MessageEvent::FriendMessage(event) => event.message.from_nick.clone(),
+        MessageEvent::TempMessage(event) => event.message.from_nick.clone(),
+    };
+    if name.is_empty() {
+        event.from_uin().to_string()
+    } else {
+        name
+    }
+}
+
+fn relayed(chain: &MessageChain) -> bool {
+    chain.clone().into_iter().any(|elem| match elem {
+        RQElem::Text(text) => text.content.starts_with(RELAY_MARK),
+        _ => false,
+    })
+}
+
+#[async_trait]
+impl MessageEventProcess for Relay {
+    async fn handle(&self, event: &MessageEvent) -> anyhow::Result<bool> {
+        let elements = event.elements();
+        if event.from_uin() == event.bot_uin().await || relayed(&elements) {
+            return Ok(false);
+        }
+        let client = event.client();
+        for (from, to) in &self.config.routes {
+            if !from.matches(event) || from == to {
+                continue;
+            }
+            let target = to.target();
+            let mut message = MessageChain::default();
+            let prefix = if self.config.name_prefix {
+                format!("{}[{}] ", RELAY_MARK, sender_name(event))
+            } else {
+                RELAY_MARK.to_string()
+            };
+            message.push(Text::new(prefix));
+            match transfer_message_chain(&client, &elements, &target).await {
+                Ok(transferred) => message.0.extend(transferred.0),
+                Err(err) => {
+                    tracing::warn!(target = "proc_qq", "转发消息失败 : {:?}", err);
+                    continue;
+                }
+            }
+            if let Err(err) = client.send_message_to_target(&target, message).await {
+                tracing::warn!(target = "proc_qq", "转发消息失败 : {:?}", err);
+            }
+        }
+        // 转发不影响其他模块处理这条消息
+        Ok(false)
+    }
+}
+
+impl Into<ModuleEventHandler> for Relay {
+    fn into(self) -> ModuleEventHandler {
+        ModuleEventHandler::new("relay", ModuleEventProcess::Message(Box::new(self)))
+    }
+}
+
+/// 消息转发模块
+pub fn relay_module(config: RelayConfig) -> Module {
+    module!("relay", "转发", Relay::new(config))
+}
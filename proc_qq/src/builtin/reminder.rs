@@ -0,0 +1,496 @@
+use crate::{
+    cancel_scheduled_send, group_profile, module, schedule_send, scheduled_sends, GroupProfile,
+    KvStore, MessageChainParseTrait, MessageContentTrait, MessageEvent, MessageEventProcess,
+    MessageSendToSourceTrait, MessageTargetTrait, Module, ModuleEventHandler, ModuleEventProcess,
+    ScheduledSend, SessionTrait,
+};
+use async_trait::async_trait;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MODULE_ID: &str = "reminder";
+
+#[derive(Debug, Clone)]
+pub struct ReminderConfig {
+    pub command: String,
+    pub list_command: String,
+    pub cancel_command: String,
+    // 每个用户同时存在的提醒数量上限
+    pub max_per_user: usize,
+    // 最远可以设置多久之后的提醒
+    pub max_delay: Duration,
+    // 只发送了时间没有内容时, 等待补充内容的时间
+    pub prompt_timeout: Duration,
+}
+
+impl Default for ReminderConfig {
+    fn default() -> Self {
+        Self {
+            command: "!remind".to_owned(),
+            list_command: "!reminders".to_owned(),
+            cancel_command: "!unremind".to_owned(),
+            max_per_user: 10,
+            max_delay: Duration::from_secs(366 * 24 * 3600),
+            prompt_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// 提醒, 到时间后在设置提醒的地方 (群或私聊) 发送, 使用 schedule_send 保存, 重启后仍然有效
+///
+/// - !remind 10m 休息一下 (s/m/h/d, 秒/分钟/小时/天, 可以组合如 1h30m)
+/// - !remind 明天9点 开会 / !remind tomorrow 9:00 开会 / !remind 2024-05-01 08:00 开会 / !remind 18:30 下班
+/// - !reminders : 查看自己的提醒
+/// - !unremind 编号 : 取消提醒
+///
+/// 日期和时刻按照群设置的时区 (GroupProfile) 计算
+pub struct ReminderModule {
+    config: ReminderConfig,
+}
+
+#[async_trait]
+impl MessageEventProcess for ReminderModule {
+    async fn handle(&self, event: &MessageEvent) -> anyhow::Result<bool> {
+        let content = event.message_content();
+        let (command, rest) = split_word(content.trim());
+        let reply = if command == self.config.command {
+            self.remind(event, rest).await?
+        } else if command == self.config.list_command && rest.is_empty() {
+            self.list(event).await?
+        } else if command == self.config.cancel_command {
+            match rest.parse::<usize>() {
+                Ok(index) => self.cancel(event, index).await?,
+                Err(_) => return Ok(false),
+            }
+        } else {
+            return Ok(false);
+        };
+        if let Some(reply) = reply {
+            event
+                .send_message_to_source(reply.parse_message_chain())
+                .await?;
+        }
+        Ok(true)
+    }
+}
+
+impl ReminderModule {
+    async fn remind(&self, event: &MessageEvent, rest: &str) -> anyhow::Result<Option<String>> {
+        let offset = utc_offset_minutes(event);
+        let now = unix_millis();
+        let (due_at, text) = match parse_when(rest, now, offset) {
+            Some(when) => when,
+            None => {
+                return Ok(Some(format!(
+                    "格式 : {} 10m 内容 / {} 明天9点 内容",
+                    self.config.command, self.config.command
+                )))
+            }
+        };
+        if due_at <= now {
+            return Ok(Some("这个时间已经过去了".to_owned()));
+        }
+        let delay = Duration::from_millis((due_at - now) as u64);
+        if delay > self.config.max_delay {
+            return Ok(Some("提醒的时间太远了".to_owned()));
+        }
+        let mine = self.reminders(event).await?;
+        if mine.len() >= self.config.max_per_user {
+            return Ok(Some(format!(
+                "最多只能设置{}个提醒, 可以使用 {} 取消",
+                self.config.max_per_user, self.config.cancel_command
+            )));
+        }
+        let text = if text.is_empty() {
+            event
+                .send_message_to_source("要提醒什么?".parse_message_chain())
+                .await?;
+            match event.next_message(self.config.prompt_timeout).await {
+                Some(reply) => reply.message_content().trim().to_owned(),
+                None => return Ok(Some("已取消设置提醒".to_owned())),
+            }
+        } else {
+            text.to_owned()
+        };
+        if text.is_empty() {
+            return Ok(Some("已取消设置提醒".to_owned()));
+        }
+        let id = schedule_send(
+            &event.client(),
+            event.target(),
+            format!("[提醒] {}", text),
+            delay,
+        )
+        .await?;
+        let mut ids: Vec<String> = mine.into_iter().map(|send| send.id).collect();
+        ids.push(id.clone());
+        user_store(event).set("ids", &ids).await?;
+        // 和提醒列表使用相同的编号 (按照时间排序)
+        let index = self
+            .reminders(event)
+            .await?
+            .iter()
+            .position(|send| send.id == id)
+            .map(|index| index + 1)
+            .unwrap_or(ids.len());
+        Ok(Some(format!(
+            "好的, 将在 {} 提醒 (编号 {})",
+            format_local(due_at, offset),
+            index
+        )))
+    }
+
+    async fn list(&self, event: &MessageEvent) -> anyhow::Result<Option<String>> {
+        let mine = self.reminders(event).await?;
+        if mine.is_empty() {
+            return Ok(Some("没有设置提醒".to_owned()));
+        }
+        let offset = utc_offset_minutes(event);
+        let lines: Vec<String> = mine
+            .iter()
+            .enumerate()
+            .map(|(index, send)| {
+                format!(
+                    "{}. {} {}",
+                    index + 1,
+                    format_local(send.due_at, offset),
+                    send.message
+                        .strip_prefix("[提醒] ")
+                        .unwrap_or(&send.message)
+                )
+            })
+            .collect();
+        Ok(Some(lines.join("\n")))
+    }
+
+    async fn cancel(&self, event: &MessageEvent, index: usize) -> anyhow::Result<Option<String>> {
+        let mut mine = self.reminders(event).await?;
+        if index == 0 || index > mine.len() {
+            return Ok(Some(format!("没有编号为{}的提醒", index)));
+        }
+        let send = mine.remove(index - 1);
+        cancel_scheduled_send(&send.id).await?;
+        let ids: Vec<String> = mine.into_iter().map(|send| send.id).collect();
+        user_store(event).set("ids", &ids).await?;
+        Ok(Some(format!("已取消提醒 {}", index)))
+    }
+
+    /// 用户还没有发送的提醒 (已经发送的会从列表中去掉), 按照时间排序
+    async fn reminders(&self, event: &MessageEvent) -> anyhow::Result<Vec<ScheduledSend>> {
+        let ids: Vec<String> = user_store(event).get("ids").await?.unwrap_or_default();
+        Ok(scheduled_sends()
+            .await?
+            .into_iter()
+            .filter(|send| ids.contains(&send.id))
+            .collect())
+    }
+}
+
+impl Into<ModuleEventHandler> for ReminderModule {
+    fn into(self) -> ModuleEventHandler {
+        ModuleEventHandler::new(MODULE_ID, ModuleEventProcess::Message(Box::new(self)))
+    }
+}
+
+/// 提醒模块
+pub fn reminder_module(config: ReminderConfig) -> Module {
+    module!(MODULE_ID, "提醒", ReminderModule { config })
+}
+
+// 提醒按照设置的地方区分 (同一个人在不同群的提醒分开)
+fn user_store(event: &MessageEvent) -> KvStore {
+    let group_code = event.as_group_message().ok().map(|e| e.message.group_code);
+    KvStore::new(MODULE_ID, group_code, Some(event.from_uin()))
+}
+
+fn utc_offset_minutes(event: &MessageEvent) -> i64 {
+    let profile = match event.as_group_message() {
+        Ok(group_message) => group_profile(group_message.message.group_code),
+        Err(_) => GroupProfile::default(),
+    };
+    profile.utc_offset_minutes as i64
+}
+
+fn unix_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+fn split_word(s: &str) -> (&str, &str) {
+    let s = s.trim_start();
+    match s.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim()),
+        None => (s, ""),
+    }
+}
+
+/// 解析开头的时间, 返回 (发送时间的毫秒时间戳, 剩下的内容)
+fn parse_when(input: &str, now: i64, offset_minutes: i64) -> Option<(i64, &str)> {
+    let (first, rest) = split_word(input);
+    if first.is_empty() {
+        return None;
+    }
+    // 日期和时刻之间可以有空格 (明天 9:00)
+    let (second, rest_after_second) = split_word(rest);
+    if !second.is_empty() {
+        if let Some(due_at) = parse_point(&format!("{} {}", first, second), now, offset_minutes) {
+            return Some((due_at, rest_after_second));
+        }
+    }
+    if let Some(duration) = parse_duration(first) {
+        return Some((now + duration.as_millis() as i64, rest));
+    }
+    parse_point(first, now, offset_minutes).map(|due_at| (due_at, rest))
+}
+
+/// 10m, 1h30m, 2d, 30秒, 10分钟, 3小时, 2天
+fn parse_duration(input: &str) -> Option<Duration> {
+    let mut total = 0u64;
+    let mut rest = input;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if digits == 0 {
+            return None;
+        }
+        let value: u64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let seconds = match &rest[..unit_len] {
+            "s" | "sec" | "秒" => 1,
+            "m" | "min" | "分" | "分钟" => 60,
+            "h" | "小时" => 3600,
+            "d" | "天" => 86400,
+            _ => return None,
+        };
+        rest = &rest[unit_len..];
+        total = total.checked_add(value.checked_mul(seconds)?)?;
+    }
+    Some(Duration::from_secs(total))
+}
+
+/// 具体的时间点 : 18:30, 9点, 9点半, 明天9点, tomorrow 9:00, 2024-05-01 08:00, 05-01 (默认9点)
+fn parse_point(input: &str, now: i64, offset_minutes: i64) -> Option<i64> {
+    let local_now = now / 1000 + offset_minutes * 60;
+    let today = local_now.div_euclid(86400);
+    let (day, clock) = if let Some((day, clock)) = parse_day_word(input) {
+        (Some(today + day), clock)
+    } else if let Some((date, clock)) = input.split_once(' ') {
+        (Some(parse_date(date, today)?), clock)
+    } else if input.contains('-') {
+        (Some(parse_date(input, today)?), "")
+    } else {
+        (None, input)
+    };
+    let clock = clock.trim();
+    let minute_of_day = if clock.is_empty() {
+        day?;
+        9 * 60
+    } else {
+        parse_clock(clock)?
+    };
+    let day = match day {
+        Some(day) => day,
+        // 只有时刻时为今天, 已经过去则为明天
+        None if (today * 86400 + minute_of_day * 60) <= local_now => today + 1,
+        None => today,
+    };
+    Some((day * 86400 + minute_of_day * 60 - offset_minutes * 60) * 1000)
+}
+
+fn parse_day_word(input: &str) -> Option<(i64, &str)> {
+    for (word, day) in [
+        ("今天", 0),
+        ("明天", 1),
+        ("后天", 2),
+        ("today", 0),
+        ("tomorrow", 1),
+    ] {
+        if let Some(clock) = input.strip_prefix(word) {
+            return Some((day, clock));
+        }
+    }
+    None
+}
+
+/// 2024-05-01 或 05-01 (今年), 返回1970-01-01起的天数
+fn parse_date(input: &str, today: i64) -> Option<i64> {
+    let parts: Vec<i64> = input
+        .split('-')
+        .map(|part| part.parse().ok())
+        .collect::<Option<Vec<i64>>>()?;
+    let (year, month, day) = match parts.as_slice() {
+        [year, month, day] => (*year, *month, *day),
+        [month, day] => (civil_from_days(today).0, *month, *day),
+        _ => return None,
+    };
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    // 不存在的日期 (02-31) 会换算到下个月
+    if civil_from_days(days) != (year, month, day) {
+        return None;
+    }
+    Some(days)
+}
+
+/// 18:30, 9点, 9点30, 9点30分, 9点半, 返回一天中的分钟
+fn parse_clock(input: &str) -> Option<i64> {
+    let (hour, minute) = if let Some((hour, minute)) = input.split_once(':') {
+        (hour, minute)
+    } else if let Some((hour, minute)) = input.split_once('点') {
+        let minute = minute.trim_end_matches('分');
+        (hour, if minute == "半" { "30" } else { minute })
+    } else {
+        return None;
+    };
+    let hour: i64 = hour.parse().ok()?;
+    let minute: i64 = if minute.is_empty() {
+        0
+    } else {
+        minute.parse().ok()?
+    };
+    if !(0..24).contains(&hour) || !(0..60).contains(&minute) {
+        return None;
+    }
+    Some(hour * 60 + minute)
+}
+
+fn format_local(millis: i64, offset_minutes: i64) -> String {
+    let local = millis / 1000 + offset_minutes * 60;
+    let (_, month, day) = civil_from_days(local.div_euclid(86400));
+    let minute_of_day = local.rem_euclid(86400) / 60;
+    format!(
+        "{:02}-{:02} {:02}:{:02}",
+        month,
+        day,
+        minute_of_day / 60,
+        minute_of_day % 60
+    )
+}
+
+// https://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UTC8: i64 = 8 * 60;
+
+    // 2024-05-01 10:00 (UTC+8)
+    fn now() -> i64 {
+        local_millis(2024, 5, 1, 10, 0)
+    }
+
+    fn local_millis(year: i64, month: i64, day: i64, hour: i64, minute: i64) -> i64 {
+        (days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 - UTC8 * 60) * 1000
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("10m"), Some(Duration::from_secs(600)));
+        assert_eq!(parse_duration("1h30m"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("2天"), Some(Duration::from_secs(2 * 86400)));
+        assert_eq!(parse_duration("30秒"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("10分钟"), Some(Duration::from_secs(600)));
+        assert_eq!(parse_duration("10"), None);
+        assert_eq!(parse_duration("m10"), None);
+        assert_eq!(parse_duration("10x"), None);
+        assert_eq!(parse_duration("99999999999999999999d"), None);
+    }
+
+    #[test]
+    fn dates() {
+        let today = days_from_civil(2024, 5, 1);
+        assert_eq!(
+            parse_date("2024-02-29", today),
+            Some(days_from_civil(2024, 2, 29))
+        );
+        assert_eq!(
+            parse_date("06-01", today),
+            Some(days_from_civil(2024, 6, 1))
+        );
+        assert_eq!(parse_date("2023-02-29", today), None);
+        assert_eq!(parse_date("02-31", today), None);
+        assert_eq!(parse_date("04-31", today), None);
+        assert_eq!(parse_date("13-01", today), None);
+        assert_eq!(parse_date("2024-05", today), None);
+        assert_eq!(parse_date("a-b", today), None);
+    }
+
+    #[test]
+    fn relative_times() {
+        assert_eq!(
+            parse_when("10m 休息一下", now(), UTC8),
+            Some((now() + 600_000, "休息一下"))
+        );
+        assert_eq!(parse_when("", now(), UTC8), None);
+        assert_eq!(parse_when("休息一下", now(), UTC8), None);
+    }
+
+    #[test]
+    fn points_in_time() {
+        assert_eq!(
+            parse_when("明天9点 开会", now(), UTC8),
+            Some((local_millis(2024, 5, 2, 9, 0), "开会"))
+        );
+        assert_eq!(
+            parse_when("tomorrow 9:00 开会", now(), UTC8),
+            Some((local_millis(2024, 5, 2, 9, 0), "开会"))
+        );
+        assert_eq!(
+            parse_when("2024-06-01 08:00 开会", now(), UTC8),
+            Some((local_millis(2024, 6, 1, 8, 0), "开会"))
+        );
+        assert_eq!(
+            parse_when("05-03 开会", now(), UTC8),
+            Some((local_millis(2024, 5, 3, 9, 0), "开会"))
+        );
+        // 今天已经过去的时刻为明天
+        assert_eq!(
+            parse_when("9点半 开会", now(), UTC8),
+            Some((local_millis(2024, 5, 2, 9, 30), "开会"))
+        );
+        assert_eq!(
+            parse_when("18:30 下班", now(), UTC8),
+            Some((local_millis(2024, 5, 1, 18, 30), "下班"))
+        );
+        assert_eq!(parse_when("02-31 开会", now(), UTC8), None);
+        assert_eq!(parse_when("25:00 开会", now(), UTC8), None);
+    }
+
+    #[test]
+    fn civil_round_trip() {
+        for days in [-719468, -1, 0, 19844, 2932896] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+        assert_eq!(format_local(now(), UTC8), "05-01 10:00");
+    }
+}
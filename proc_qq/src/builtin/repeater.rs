@@ -0,0 +1,118 @@
+use crate::{
+    module, recent_group_messages, ClientTrait, GroupMessageEventProcess, MessageChainTrait,
+    MessageSendToSourceTrait, Module, ModuleEventHandler, ModuleEventProcess,
+};
+use async_trait::async_trait;
+use rs_qq::client::event::GroupMessageEvent;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+#[derive(Debug, Clone)]
+pub struct RepeaterConfig {
+    // 连续多少条相同的消息后复读
+    pub threshold: usize,
+    // 同一个群两次复读的最小间隔
+    pub cooldown: Duration,
+    // 是否要求连续消息来自不同的人, 防止和其他机器人互相复读
+    pub distinct_senders: bool,
+}
+
+impl Default for RepeaterConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 3,
+            cooldown: Duration::from_secs(60),
+            distinct_senders: true,
+        }
+    }
+}
+
+struct GroupState {
+    last_content: Option<u64>,
+    last_time: Instant,
+}
+
+pub struct Repeater {
+    config: RepeaterConfig,
+    states: Mutex<HashMap<i64, GroupState>>,
+}
+
+impl Repeater {
+    pub fn new(config: RepeaterConfig) -> Self {
+        Self {
+            config,
+            states: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl GroupMessageEventProcess for Repeater {
+    async fn handle(&self, event: &GroupMessageEvent) -> anyhow::Result<bool> {
+        let group_code = event.message.group_code;
+        let threshold = self.config.threshold.max(2);
+        let recent = recent_group_messages(group_code, threshold);
+        if recent.len() < threshold {
+            return Ok(false);
+        }
+        let latest = &recent[recent.len() - 1];
+        let content = latest.elements.content_hash();
+        if let Some(state) = self.states.lock().await.get_mut(&group_code) {
+            // 出现了不同的消息, 上一段复读已经结束
+            if state.last_content != Some(content) {
+                state.last_content = None;
+            }
+        }
+        if latest.content().trim().is_empty()
+            || recent
+                .iter()
+                .any(|m| !m.elements.same_content(&latest.elements))
+        {
+            return Ok(false);
+        }
+        let bot_uin = event.bot_uin().await;
+        if recent.iter().any(|m| m.from_uin == bot_uin) {
+            return Ok(false);
+        }
+        if self.config.distinct_senders {
+            let senders: HashSet<i64> = recent.iter().map(|m| m.from_uin).collect();
+            if senders.len() < recent.len() {
+                return Ok(false);
+            }
+        }
+        {
+            let mut states = self.states.lock().await;
+            if let Some(state) = states.get(&group_code) {
+                // 同一段复读只跟一次
+                if state.last_content == Some(content)
+                    || state.last_time.elapsed() < self.config.cooldown
+                {
+                    return Ok(false);
+                }
+            }
+            states.insert(
+                group_code,
+                GroupState {
+                    last_content: Some(content),
+                    last_time: Instant::now(),
+                },
+            );
+        }
+        event
+            .send_message_to_source(event.message.elements.clone())
+            .await?;
+        Ok(false)
+    }
+}
+
+impl Into<ModuleEventHandler> for Repeater {
+    fn into(self) -> ModuleEventHandler {
+        ModuleEventHandler::new("repeater", ModuleEventProcess::GroupMessage(Box::new(self)))
+    }
+}
+
+/// 复读模块
+pub fn repeater_module(config: RepeaterConfig) -> Module {
+    module!("repeater", "复读", Repeater::new(config))
+}
@@ -0,0 +1,429 @@
+use crate::{
+    module, ClientTrait, GroupMessageEventProcess, GroupTrait, Job, KvStore, MemberTrait,
+    MessageChainParseTrait, MessageSendToSourceTrait, MessageTarget, Module, ModuleEventHandler,
+    ModuleEventProcess,
+};
+use anyhow::Context;
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use reqwest::Url;
+use rq_engine::msg::elem::RichMsg;
+use rq_engine::msg::MessageChain;
+use rs_qq::client::event::GroupMessageEvent;
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+const MODULE_ID: &str = "rss";
+// 每个订阅最多记住的条目数, 用于去重
+const SEEN_LIMIT: usize = 200;
+const MAX_REDIRECTS: usize = 5;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+lazy_static! {
+    // 订阅列表的读取-修改-写回
+    static ref SUBSCRIPTIONS_LOCK: Mutex<()> = Mutex::new(());
+    // 同时只有一次拉取 (多个账号, 重连), 否则会重复推送
+    static ref POLL_LOCK: Mutex<()> = Mutex::new(());
+}
+
+#[derive(Debug, Clone)]
+pub struct RssConfig {
+    // 拉取订阅的间隔
+    pub interval: Duration,
+    // 以卡片形式推送, 否则为文字
+    pub card: bool,
+    // 每次每个订阅最多推送的条目数
+    pub max_entries: usize,
+}
+
+impl Default for RssConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(600),
+            card: false,
+            max_entries: 3,
+        }
+    }
+}
+
+// 订阅保存为 url -> 群号
+fn store() -> KvStore {
+    KvStore::new(MODULE_ID, None, None)
+}
+
+async fn subscriptions() -> anyhow::Result<BTreeMap<String, BTreeSet<i64>>> {
+    Ok(store().get("subscriptions").await?.unwrap_or_default())
+}
+
+/// 群管理员通过命令订阅
+///
+/// - 订阅 url
+/// - 取消订阅 url
+/// - 订阅列表
+struct RssCommands;
+
+#[async_trait]
+impl GroupMessageEventProcess for RssCommands {
+    async fn handle(&self, event: &GroupMessageEvent) -> anyhow::Result<bool> {
+        let group_code = event.message.group_code;
+        let content = event.message.elements.to_string();
+        let words: Vec<&str> = content.split_whitespace().collect();
+        let reply = match words.as_slice() {
+            ["订阅列表"] => {
+                let urls: Vec<String> = subscriptions()
+                    .await?
+                    .into_iter()
+                    .filter(|(_, groups)| groups.contains(&group_code))
+                    .map(|(url, _)| url)
+                    .collect();
+                if urls.is_empty() {
+                    "没有订阅".to_owned()
+                } else {
+                    urls.join("\n")
+                }
+            }
+            ["订阅", url] | ["取消订阅", url] => {
+                let group = event.must_find_group(group_code, true).await?;
+                let member = group.must_find_member(event.message.from_uin).await?;
+                if member.is_member() {
+                    crate::group_framework_messages(group_code).permission_denied()
+                } else if words[0] == "订阅" {
+                    // 地址无效或不允许访问时告诉订阅的人原因
+                    match fetch_feed(url).await {
+                        Ok(_) => update_subscription(url, group_code, true).await?,
+                        Err(err) => format!("订阅失败 : {}", err),
+                    }
+                } else {
+                    update_subscription(url, group_code, false).await?
+                }
+            }
+            _ => return Ok(false),
+        };
+        event
+            .send_message_to_source(reply.parse_message_chain())
+            .await?;
+        Ok(true)
+    }
+}
+
+async fn update_subscription(
+    url: &str,
+    group_code: i64,
+    subscribe: bool,
+) -> anyhow::Result<String> {
+    let _guard = SUBSCRIPTIONS_LOCK.lock().await;
+    let mut subscriptions = subscriptions().await?;
+    let reply = if subscribe {
+        subscriptions
+            .entry(url.to_string())
+            .or_default()
+            .insert(group_code);
+        format!("已订阅 : {}", url)
+    } else {
+        if let Some(groups) = subscriptions.get_mut(url) {
+            groups.remove(&group_code);
+            if groups.is_empty() {
+                subscriptions.remove(url);
+            }
+        }
+        format!("已取消订阅 : {}", url)
+    };
+    store().set("subscriptions", &subscriptions).await?;
+    Ok(reply)
+}
+
+impl Into<ModuleEventHandler> for RssCommands {
+    fn into(self) -> ModuleEventHandler {
+        ModuleEventHandler::new(
+            "rss_commands",
+            ModuleEventProcess::GroupMessage(Box::new(self)),
+        )
+    }
+}
+
+struct FeedEntry {
+    id: String,
+    title: String,
+    link: String,
+}
+
+// 每次请求前检查地址, 重定向也逐个检查, 并且固定使用检查过的IP, 不会被DNS换成内网地址
+async fn fetch_feed(url: &str) -> anyhow::Result<Vec<FeedEntry>> {
+    let mut url = Url::parse(url).with_context(|| format!("订阅地址格式错误 : {}", url))?;
+    let mut redirects = 0;
+    let response = loop {
+        let mut builder = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .timeout(FETCH_TIMEOUT);
+        if let Some(addr) = public_addr(&url).await? {
+            builder = builder.resolve(url.host_str().unwrap_or_default(), addr);
+        }
+        let response = builder.build()?.get(url.clone()).send().await?;
+        if !response.status().is_redirection() {
+            break response;
+        }
+        redirects += 1;
+        if redirects > MAX_REDIRECTS {
+            return Err(anyhow::Error::msg("订阅地址重定向次数过多"));
+        }
+        let location = response
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .context("订阅地址重定向没有目标")?
+            .to_str()?;
+        url = url.join(location)?;
+    };
+    let bytes = response.error_for_status()?.bytes().await?;
+    let feed = feed_rs::parser::parse(&bytes[..])?;
+    Ok(feed
+        .entries
+        .into_iter()
+        .map(|entry| FeedEntry {
+            link: entry
+                .links
+                .first()
+                .map(|link| link.href.clone())
+                .unwrap_or_default(),
+            title: entry.title.map(|title| title.content).unwrap_or_default(),
+            id: entry.id,
+        })
+        .collect())
+}
+
+/// 只允许http和https访问公网地址, 避免通过订阅访问机器人所在的内网
+///
+/// 主机为域名时返回解析到的地址, 请求时使用这个地址; 主机为IP时返回None
+async fn public_addr(url: &Url) -> anyhow::Result<Option<SocketAddr>> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(anyhow::Error::msg(format!(
+            "只支持http和https的订阅地址 : {}",
+            url
+        )));
+    }
+    let port = url.port_or_known_default().unwrap_or(80);
+    let host = url
+        .host_str()
+        .with_context(|| format!("订阅地址没有主机 : {}", url))?;
+    // IPv6的主机带有方括号
+    let ip = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>();
+    let (addrs, domain): (Vec<SocketAddr>, bool) = match ip {
+        Ok(ip) => (vec![SocketAddr::new(ip, port)], false),
+        Err(_) => (tokio::net::lookup_host((host, port)).await?.collect(), true),
+    };
+    if addrs.is_empty() || addrs.iter().any(|addr| !is_public_ip(addr.ip())) {
+        return Err(anyhow::Error::msg(format!("不允许订阅内网地址 : {}", url)));
+    }
+    Ok(if domain { addrs.first().copied() } else { None })
+}
+
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_unspecified()
+                || ip.is_documentation()
+                || a == 0
+                // 100.64.0.0/10 运营商NAT
+                || (a == 100 && (b & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ip(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    // fc00::/7 唯一本地地址, fe80::/10 链路本地地址
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn entry_message(entry: &FeedEntry, card: bool) -> MessageChain {
+    if card {
+        let mut chain = MessageChain::default();
+        chain.push(RichMsg {
+            service_id: 1,
+            template1: format!(
+                r#"<?xml version='1.0' encoding='UTF-8' standalone='yes' ?><msg serviceID="1" templateID="1" action="web" brief="{title}" url="{link}"><item layout="2"><title>{title}</title><summary>{link}</summary></item></msg>"#,
+                title = escape_xml(&entry.title),
+                link = escape_xml(&entry.link),
+            ),
+        });
+        chain
+    } else {
+        format!("{}\n{}", entry.title, entry.link).parse_message_chain()
+    }
+}
+
+struct RssPoller {
+    config: RssConfig,
+}
+
+#[async_trait]
+impl Job for RssPoller {
+    async fn run(&self, client: &Arc<rs_qq::Client>) -> anyhow::Result<()> {
+        let _guard = match POLL_LOCK.try_lock() {
+            Ok(guard) => guard,
+            Err(_) => {
+                tracing::debug!(target = "proc_qq", "上一次拉取订阅还没有结束, 跳过");
+                return Ok(());
+            }
+        };
+        for (url, groups) in subscriptions().await? {
+            let entries = match fetch_feed(&url).await {
+                Ok(entries) => entries,
+                Err(err) => {
+                    tracing::warn!(target = "proc_qq", "拉取订阅 {} 失败 : {:?}", url, err);
+                    continue;
+                }
+            };
+            let seen_key = format!("seen:{}", url);
+            let seen: Option<Vec<String>> = match store().get(&seen_key).await {
+                Ok(seen) => seen,
+                Err(err) => {
+                    tracing::warn!(
+                        target = "proc_qq",
+                        "读取订阅 {} 的记录失败 : {:?}",
+                        url,
+                        err
+                    );
+                    continue;
+                }
+            };
+            // 第一次拉取只记录, 不推送历史条目
+            if let Some(seen) = &seen {
+                let new_entries = entries
+                    .iter()
+                    .filter(|entry| !seen.contains(&entry.id))
+                    .take(self.config.max_entries);
+                for entry in new_entries {
+                    for group_code in &groups {
+                        let target = MessageTarget::Group(*group_code, 0);
+                        if let Err(err) = client
+                            .send_message_to_target(&target, entry_message(entry, self.config.card))
+                            .await
+                        {
+                            tracing::warn!(target = "proc_qq", "推送订阅失败 : {:?}", err);
+                        }
+                    }
+                }
+            }
+            let ids = merge_seen(entries, seen.unwrap_or_default());
+            if let Err(err) = store().set(&seen_key, &ids).await {
+                tracing::warn!(
+                    target = "proc_qq",
+                    "保存订阅 {} 的记录失败 : {:?}",
+                    url,
+                    err
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 当前的条目在前, 之后是以前记录的, 最多保留SEEN_LIMIT个
+fn merge_seen(entries: Vec<FeedEntry>, seen: Vec<String>) -> Vec<String> {
+    let mut ids: Vec<String> = entries.into_iter().map(|entry| entry.id).collect();
+    for id in seen {
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    ids.truncate(SEEN_LIMIT);
+    ids
+}
+
+/// RSS/Atom订阅模块
+pub fn rss_module(config: RssConfig) -> Vec<Module> {
+    vec![
+        module!(MODULE_ID, "订阅", RssCommands),
+        crate::scheduled_module("rss_poller", config.interval, RssPoller { config }),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn public_ips() {
+        for ip in ["1.1.1.1", "8.8.8.8", "2606:4700:4700::1111"] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn private_urls_are_rejected() {
+        for url in [
+            "file:///etc/passwd",
+            "ftp://example.com/feed",
+            "http://127.0.0.1/feed",
+            "http://[::1]:8080/feed",
+            "http://169.254.169.254/latest/meta-data",
+            "http://localhost/feed",
+        ] {
+            let url = Url::parse(url).unwrap();
+            assert!(public_addr(&url).await.is_err(), "{}", url);
+        }
+        let url = Url::parse("https://1.1.1.1/feed").unwrap();
+        assert_eq!(public_addr(&url).await.unwrap(), None);
+    }
+
+    #[test]
+    fn seen_ids_are_capped() {
+        let entry = |id: usize| FeedEntry {
+            id: id.to_string(),
+            title: String::new(),
+            link: String::new(),
+        };
+        let ids = merge_seen(
+            vec![entry(1), entry(2)],
+            vec!["2".to_owned(), "0".to_owned()],
+        );
+        assert_eq!(ids, vec!["1", "2", "0"]);
+        let ids = merge_seen(
+            (0..SEEN_LIMIT + 50).map(entry).collect(),
+            vec!["x".to_owned()],
+        );
+        assert_eq!(ids.len(), SEEN_LIMIT);
+        assert_eq!(ids[0], "0");
+    }
+}
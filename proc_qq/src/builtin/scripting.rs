@@ -0,0 +1,222 @@
+use crate::{
+    module, KvStore, MessageChainParseTrait, MessageContentTrait, MessageEvent,
+    MessageEventProcess, MessageSendToSourceTrait, Module, ModuleEventHandler, ModuleEventProcess,
+};
+use async_trait::async_trait;
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Engine, Scope};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[derive(Debug, Clone)]
+pub struct ScriptingConfig {
+    // 可以添加和删除脚本的QQ号
+    pub admins: Vec<i64>,
+    // 单个脚本单次执行的最大操作数, 防止死循环, 0也按1处理 (rhai中0表示不限制)
+    pub max_operations: u64,
+    // 单条消息最多回复几次
+    pub max_replies: usize,
+}
+
+impl Default for ScriptingConfig {
+    fn default() -> Self {
+        Self {
+            admins: vec![],
+            max_operations: 100_000,
+            max_replies: 3,
+        }
+    }
+}
+
+/// 管理员在运行时添加rhai脚本处理消息, 脚本保存在Storage中
+///
+/// - 脚本 添加 名字 (换行后为脚本内容)
+/// - 脚本 删除 名字
+/// - 脚本 列表
+///
+/// 脚本中可以使用 content (消息文字), sender (发送者), group (群号, 私聊为0), reply(text)
+pub struct Scripting {
+    config: ScriptingConfig,
+}
+
+impl Scripting {
+    pub fn new(config: ScriptingConfig) -> Self {
+        Self { config }
+    }
+
+    fn store(&self) -> KvStore {
+        KvStore::new("scripting", None, None)
+    }
+
+    async fn handle_admin(&self, event: &MessageEvent, content: &str) -> anyhow::Result<bool> {
+        let (command, body) = match content.split_once('\n') {
+            Some((command, body)) => (command, body),
+            None => (content, ""),
+        };
+        let words: Vec<&str> = command.split_whitespace().collect();
+        let reply = match words.as_slice() {
+            ["脚本", "列表"] => {
+                let names = self.store().keys().await?;
+                if names.is_empty() {
+                    "没有脚本".to_owned()
+                } else {
+                    names.join("\n")
+                }
+            }
+            ["脚本", "添加", name] => {
+                // 先检查能否编译, 编译结果不保存
+                let compiled = engine(&self.config).compile(body).map(|_| ());
+                match compiled {
+                    Ok(_) => {
+                        self.store().set(name, body).await?;
+                        format!("已添加脚本 : {}", name)
+                    }
+                    Err(err) => format!("脚本编译失败 : {}", err),
+                }
+            }
+            ["脚本", "删除", name] => {
+                self.store().remove(name).await?;
+                format!("已删除脚本 : {}", name)
+            }
+            _ => return Ok(false),
+        };
+        event
+            .send_message_to_source(reply.parse_message_chain())
+            .await?;
+        Ok(true)
+    }
+}
+
+// 沙箱 : 不能导入模块, 不能打印, 限制操作数和数据大小
+fn engine(config: &ScriptingConfig) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_module_resolver(DummyModuleResolver::new());
+    engine.on_print(|_| {});
+    engine.on_debug(|_, _, _| {});
+    engine.set_max_operations(config.max_operations.max(1));
+    engine.set_max_expr_depths(32, 32);
+    engine.set_max_call_levels(16);
+    engine.set_max_string_size(4096);
+    engine.set_max_array_size(1024);
+    engine.set_max_map_size(1024);
+    engine
+}
+
+// 脚本是同步执行的, 在spawn_blocking中调用, 不能阻塞异步运行时
+fn run_scripts(
+    config: &ScriptingConfig,
+    scripts: &[(String, String)],
+    content: &str,
+    sender: i64,
+    group: i64,
+) -> Vec<String> {
+    let replies = Rc::new(RefCell::new(vec![]));
+    let mut engine = engine(config);
+    let holder = replies.clone();
+    let max_replies = config.max_replies;
+    engine.register_fn("reply", move |text: &str| {
+        let mut replies = holder.borrow_mut();
+        if replies.len() < max_replies {
+            replies.push(text.to_owned());
+        }
+    });
+    for (name, source) in scripts {
+        let mut scope = Scope::new();
+        scope.push_constant("content", content.to_owned());
+        scope.push_constant("sender", sender);
+        scope.push_constant("group", group);
+        if let Err(err) = engine.run_with_scope(&mut scope, source) {
+            tracing::warn!(target = "proc_qq", "脚本 {} 执行失败 : {}", name, err);
+        }
+    }
+    drop(engine);
+    Rc::try_unwrap(replies)
+        .map(RefCell::into_inner)
+        .unwrap_or_default()
+}
+
+#[async_trait]
+impl MessageEventProcess for Scripting {
+    async fn handle(&self, event: &MessageEvent) -> anyhow::Result<bool> {
+        let content = event.message_content();
+        if self.config.admins.contains(&event.from_uin())
+            && content.starts_with("脚本")
+            && self.handle_admin(event, &content).await?
+        {
+            return Ok(true);
+        }
+        let store = self.store();
+        let mut scripts = vec![];
+        for name in store.keys().await? {
+            if let Some(source) = store.get::<String>(&name).await? {
+                scripts.push((name, source));
+            }
+        }
+        if scripts.is_empty() {
+            return Ok(false);
+        }
+        let group = match event {
+            MessageEvent::GroupMessage(event) => event.message.group_code,
+            _ => 0,
+        };
+        let config = self.config.clone();
+        let content = content.into_owned();
+        let sender = event.from_uin();
+        let replies = tokio::task::spawn_blocking(move || {
+            run_scripts(&config, &scripts, &content, sender, group)
+        })
+        .await?;
+        if replies.is_empty() {
+            return Ok(false);
+        }
+        for reply in replies {
+            event
+                .send_message_to_source(reply.parse_message_chain())
+                .await?;
+        }
+        Ok(true)
+    }
+}
+
+impl Into<ModuleEventHandler> for Scripting {
+    fn into(self) -> ModuleEventHandler {
+        ModuleEventHandler::new("scripting", ModuleEventProcess::Message(Box::new(self)))
+    }
+}
+
+/// 脚本模块
+pub fn scripting_module(config: ScriptingConfig) -> Module {
+    module!("scripting", "脚本", Scripting::new(config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(source: &str) -> Vec<(String, String)> {
+        vec![("test".to_owned(), source.to_owned())]
+    }
+
+    #[test]
+    fn replies_are_limited() {
+        let config = ScriptingConfig::default();
+        let replies = run_scripts(
+            &config,
+            &script("for i in 0..10 { reply(content + i) }"),
+            "a",
+            1,
+            0,
+        );
+        assert_eq!(replies, vec!["a0", "a1", "a2"]);
+    }
+
+    #[test]
+    fn endless_script_is_stopped() {
+        let config = ScriptingConfig {
+            max_operations: 0,
+            ..Default::default()
+        };
+        let replies = run_scripts(&config, &script("loop { } reply(\"x\")"), "", 1, 0);
+        assert!(replies.is_empty());
+    }
+}
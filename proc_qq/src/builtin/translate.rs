@@ -0,0 +1,94 @@
+use crate::{
+    module, CachedTranslator, ClientTrait, GroupMessageEventProcess, MessageChainParseTrait,
+    MessageContentTrait, MessageSendToSourceTrait, Module, ModuleEventHandler, ModuleEventProcess,
+    Translator,
+};
+use async_trait::async_trait;
+use rs_qq::client::event::GroupMessageEvent;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct TranslateConfig {
+    pub translator: Arc<dyn Translator>,
+    /// 翻译成的语言
+    pub target_lang: String,
+    /// 需要翻译的语言, 为空时翻译所有和target_lang不同的语言
+    pub source_langs: Vec<String>,
+    /// 只在这些群中翻译, 为空时所有群都翻译
+    pub groups: Vec<i64>,
+    // 太短的消息 (表情, 语气词) 不翻译
+    pub min_length: usize,
+}
+
+impl TranslateConfig {
+    pub fn new<S: Into<String>>(translator: Arc<dyn Translator>, target_lang: S) -> Self {
+        Self {
+            translator,
+            target_lang: target_lang.into(),
+            source_langs: vec![],
+            groups: vec![],
+            min_length: 4,
+        }
+    }
+}
+
+/// 检测群消息的语言, 回复翻译
+pub struct AutoTranslate {
+    config: TranslateConfig,
+    translator: CachedTranslator,
+}
+
+#[async_trait]
+impl GroupMessageEventProcess for AutoTranslate {
+    async fn handle(&self, event: &GroupMessageEvent) -> anyhow::Result<bool> {
+        let config = &self.config;
+        if (!config.groups.is_empty() && !config.groups.contains(&event.message.group_code))
+            || event.message.from_uin == event.bot_uin().await
+        {
+            return Ok(false);
+        }
+        let content = event.message_content();
+        let content = content.trim();
+        if content.chars().count() < config.min_length {
+            return Ok(false);
+        }
+        let lang = match self.translator.detect_language(content) {
+            Some(lang) if lang != config.target_lang => lang,
+            _ => return Ok(false),
+        };
+        if !config.source_langs.is_empty() && !config.source_langs.contains(&lang) {
+            return Ok(false);
+        }
+        let translated = self
+            .translator
+            .translate(content, Some(&lang), &config.target_lang)
+            .await?;
+        if translated.trim() == content {
+            return Ok(false);
+        }
+        event
+            .send_message_to_source(format!("[{}] {}", lang, translated).parse_message_chain())
+            .await?;
+        // 不影响其他模块处理这条消息
+        Ok(false)
+    }
+}
+
+impl Into<ModuleEventHandler> for AutoTranslate {
+    fn into(self) -> ModuleEventHandler {
+        ModuleEventHandler::new(
+            "auto_translate",
+            ModuleEventProcess::GroupMessage(Box::new(self)),
+        )
+    }
+}
+
+/// 自动翻译模块, 缓存重复的翻译
+pub fn translate_module(config: TranslateConfig) -> Module {
+    let translator = CachedTranslator::new(config.translator.clone());
+    module!(
+        "translate",
+        "自动翻译",
+        AutoTranslate { config, translator }
+    )
+}
@@ -0,0 +1,34 @@
+use crate::{
+    build_info, module, MessageChainParseTrait, MessageContentTrait, MessageEvent,
+    MessageEventProcess, MessageSendToSourceTrait, Module, ModuleEventHandler, ModuleEventProcess,
+};
+use async_trait::async_trait;
+
+/// 回复 "version" 或 "版本" 时发送编译信息
+pub struct VersionCommand;
+
+#[async_trait]
+impl MessageEventProcess for VersionCommand {
+    async fn handle(&self, event: &MessageEvent) -> anyhow::Result<bool> {
+        match event.message_content().trim() {
+            "version" | "版本" => {
+                event
+                    .send_message_to_source(build_info().to_string().parse_message_chain())
+                    .await?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+impl Into<ModuleEventHandler> for VersionCommand {
+    fn into(self) -> ModuleEventHandler {
+        ModuleEventHandler::new("version", ModuleEventProcess::Message(Box::new(self)))
+    }
+}
+
+/// 查看版本的模块
+pub fn version_module() -> Module {
+    module!("version", "版本", VersionCommand)
+}
@@ -0,0 +1,78 @@
+use lazy_static::lazy_static;
+use rq_engine::protocol::version::Protocol;
+use serde_derive::Serialize;
+use std::sync::RwLock;
+
+use crate::{Client, ProcClient};
+
+/// 当前协议和登录方式支持的操作, 模块可以据此开关功能, 而不是调用后才得到错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Capabilities {
+    pub send_group_audio: bool,
+    pub send_friend_audio: bool,
+    pub send_temp_audio: bool,
+    pub send_temp_image: bool,
+    /// 戳一戳 (群和好友)
+    pub poke: bool,
+    /// 设置精华消息
+    pub essence: bool,
+    pub qrcode_login: bool,
+}
+
+impl Capabilities {
+    pub fn for_protocol(protocol: Protocol) -> Self {
+        // 手表协议是精简的客户端, 很多操作服务器不会响应
+        let full = protocol != Protocol::AndroidWatch;
+        Self {
+            send_group_audio: full,
+            send_friend_audio: full,
+            send_temp_audio: false,
+            send_temp_image: false,
+            poke: full,
+            essence: full,
+            qrcode_login: protocol == Protocol::AndroidWatch,
+        }
+    }
+
+    /// 不支持的操作的名称, 用于日志
+    pub fn unsupported(&self) -> Vec<&'static str> {
+        [
+            ("send_group_audio", self.send_group_audio),
+            ("send_friend_audio", self.send_friend_audio),
+            ("send_temp_audio", self.send_temp_audio),
+            ("send_temp_image", self.send_temp_image),
+            ("poke", self.poke),
+            ("essence", self.essence),
+            ("qrcode_login", self.qrcode_login),
+        ]
+        .into_iter()
+        .filter(|(_, supported)| !supported)
+        .map(|(name, _)| name)
+        .collect()
+    }
+}
+
+lazy_static! {
+    static ref PROTOCOL: RwLock<Option<Protocol>> = RwLock::new(None);
+}
+
+pub(crate) fn set_protocol(protocol: Protocol) {
+    *PROTOCOL.write().unwrap() = Some(protocol);
+}
+
+/// 正在运行的客户端支持的操作, 客户端启动之前按照默认的安卓手机协议
+pub fn capabilities() -> Capabilities {
+    Capabilities::for_protocol(PROTOCOL.read().unwrap().unwrap_or(Protocol::AndroidPhone))
+}
+
+impl Client {
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities::for_protocol(self.version.protocol)
+    }
+}
+
+impl ProcClient {
+    pub fn capabilities(&self) -> Capabilities {
+        capabilities()
+    }
+}
@@ -0,0 +1,316 @@
+use ab_glyph::{Font, FontArc, PxScale, ScaleFont};
+use image::imageops::FilterType;
+use image::{ImageFormat, Rgba, RgbaImage};
+use lazy_static::lazy_static;
+use std::io::Cursor;
+use std::sync::RwLock;
+
+lazy_static! {
+    static ref CARD_FONT: RwLock<Option<FontArc>> = RwLock::new(None);
+}
+
+/// 设置卡片使用的字体 (ttf/otf), 需要包含中文字符
+pub fn set_card_font(data: Vec<u8>) -> anyhow::Result<()> {
+    let font = FontArc::try_from_vec(data)?;
+    *CARD_FONT.write().unwrap() = Some(font);
+    Ok(())
+}
+
+/// 卡片中的一层, 按照添加的顺序绘制
+#[derive(Debug, Clone)]
+pub enum CardLayer {
+    /// 铺满整个卡片的背景图片
+    BackgroundImage(Vec<u8>),
+    Rect {
+        x: i64,
+        y: i64,
+        width: u32,
+        height: u32,
+        color: [u8; 4],
+    },
+    Avatar {
+        data: Vec<u8>,
+        x: i64,
+        y: i64,
+        size: u32,
+        circle: bool,
+    },
+    Text {
+        text: String,
+        x: i64,
+        y: i64,
+        size: f32,
+        color: [u8; 4],
+    },
+}
+
+/// 由背景, 头像, 文字组合的图片卡片, render得到PNG, 可以直接上传
+#[derive(Debug, Clone)]
+pub struct Card {
+    pub width: u32,
+    pub height: u32,
+    pub background: [u8; 4],
+    pub layers: Vec<CardLayer>,
+}
+
+impl Card {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            background: [255, 255, 255, 255],
+            layers: vec![],
+        }
+    }
+
+    pub fn background(mut self, color: [u8; 4]) -> Self {
+        self.background = color;
+        self
+    }
+
+    pub fn background_image(mut self, data: Vec<u8>) -> Self {
+        self.layers.push(CardLayer::BackgroundImage(data));
+        self
+    }
+
+    pub fn rect(mut self, x: i64, y: i64, width: u32, height: u32, color: [u8; 4]) -> Self {
+        self.layers.push(CardLayer::Rect {
+            x,
+            y,
+            width,
+            height,
+            color,
+        });
+        self
+    }
+
+    /// 圆形头像, size为直径
+    pub fn avatar(mut self, data: Vec<u8>, x: i64, y: i64, size: u32) -> Self {
+        self.layers.push(CardLayer::Avatar {
+            data,
+            x,
+            y,
+            size,
+            circle: true,
+        });
+        self
+    }
+
+    /// (x, y)为文字的左上角
+    pub fn text<S: Into<String>>(
+        mut self,
+        text: S,
+        x: i64,
+        y: i64,
+        size: f32,
+        color: [u8; 4],
+    ) -> Self {
+        self.layers.push(CardLayer::Text {
+            text: text.into(),
+            x,
+            y,
+            size,
+            color,
+        });
+        self
+    }
+
+    /// 绘制为PNG, 有文字时需要先调用 set_card_font
+    pub fn render(&self) -> anyhow::Result<Vec<u8>> {
+        let mut canvas = RgbaImage::from_pixel(self.width, self.height, Rgba(self.background));
+        for layer in &self.layers {
+            match layer {
+                CardLayer::BackgroundImage(data) => {
+                    let img = image::load_from_memory(data)?.resize_to_fill(
+                        self.width,
+                        self.height,
+                        FilterType::Triangle,
+                    );
+                    image::imageops::overlay(&mut canvas, &img.to_rgba8(), 0, 0);
+                }
+                CardLayer::Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                    color,
+                } => {
+                    let rect = RgbaImage::from_pixel(*width, *height, Rgba(*color));
+                    image::imageops::overlay(&mut canvas, &rect, *x, *y);
+                }
+                CardLayer::Avatar {
+                    data,
+                    x,
+                    y,
+                    size,
+                    circle,
+                } => {
+                    let mut img = image::load_from_memory(data)?
+                        .resize_to_fill(*size, *size, FilterType::Triangle)
+                        .to_rgba8();
+                    if *circle {
+                        mask_circle(&mut img);
+                    }
+                    image::imageops::overlay(&mut canvas, &img, *x, *y);
+                }
+                CardLayer::Text {
+                    text,
+                    x,
+                    y,
+                    size,
+                    color,
+                } => {
+                    let font = CARD_FONT.read().unwrap().clone().ok_or_else(|| {
+                        anyhow::Error::msg("没有设置卡片字体, 请调用 set_card_font")
+                    })?;
+                    draw_text(&mut canvas, &font, text, *x, *y, *size, *color);
+                }
+            }
+        }
+        let mut buf = Cursor::new(vec![]);
+        canvas.write_to(&mut buf, ImageFormat::Png)?;
+        Ok(buf.into_inner())
+    }
+}
+
+/// 欢迎新成员的卡片
+pub fn welcome_card(avatar: Vec<u8>, name: &str, group_name: &str) -> Card {
+    Card::new(800, 300)
+        .background([52, 73, 94, 255])
+        .avatar(avatar, 50, 50, 200)
+        .text(
+            format!("欢迎 {}", name),
+            290,
+            90,
+            48.0,
+            [255, 255, 255, 255],
+        )
+        .text(
+            format!("加入 {}", group_name),
+            290,
+            170,
+            32.0,
+            [210, 218, 226, 255],
+        )
+}
+
+/// 排行榜卡片, rows为 (头像, 名字, 分数)
+pub fn rank_card(title: &str, rows: Vec<(Option<Vec<u8>>, String, i64)>) -> Card {
+    const ROW_HEIGHT: u32 = 80;
+    let mut card = Card::new(600, 100 + ROW_HEIGHT * rows.len() as u32)
+        .background([245, 246, 250, 255])
+        .text(title, 30, 25, 40.0, [44, 62, 80, 255]);
+    for (index, (avatar, name, score)) in rows.into_iter().enumerate() {
+        let top = 100 + (ROW_HEIGHT as usize * index) as i64;
+        if index % 2 == 0 {
+            card = card.rect(0, top, 600, ROW_HEIGHT, [236, 240, 241, 255]);
+        }
+        card = card.text(
+            format!("{}", index + 1),
+            30,
+            top + 22,
+            32.0,
+            [127, 140, 141, 255],
+        );
+        if let Some(avatar) = avatar {
+            card = card.avatar(avatar, 90, top + 10, 60);
+        }
+        card = card
+            .text(name, 170, top + 22, 30.0, [44, 62, 80, 255])
+            .text(score.to_string(), 470, top + 22, 30.0, [231, 76, 60, 255]);
+    }
+    card
+}
+
+// 圆形之外的部分设为透明
+fn mask_circle(img: &mut RgbaImage) {
+    let radius = img.width().min(img.height()) as f32 / 2.0;
+    for (x, y, pixel) in img.enumerate_pixels_mut() {
+        let dx = x as f32 + 0.5 - radius;
+        let dy = y as f32 + 0.5 - radius;
+        // 边缘1像素内做抗锯齿
+        let coverage = (radius - (dx * dx + dy * dy).sqrt()).clamp(0.0, 1.0);
+        pixel.0[3] = (pixel.0[3] as f32 * coverage) as u8;
+    }
+}
+
+fn draw_text(
+    canvas: &mut RgbaImage,
+    font: &FontArc,
+    text: &str,
+    x: i64,
+    y: i64,
+    size: f32,
+    color: [u8; 4],
+) {
+    let font = font.as_scaled(PxScale::from(size));
+    let mut caret = ab_glyph::point(x as f32, y as f32 + font.ascent());
+    let mut previous = None;
+    for c in text.chars() {
+        let glyph_id = font.glyph_id(c);
+        if let Some(previous) = previous {
+            caret.x += font.kern(previous, glyph_id);
+        }
+        previous = Some(glyph_id);
+        let glyph = glyph_id.with_scale_and_position(font.scale(), caret);
+        caret.x += font.h_advance(glyph_id);
+        let outlined = match font.outline_glyph(glyph) {
+            Some(outlined) => outlined,
+            None => continue,
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i64 + gx as i64;
+            let py = bounds.min.y as i64 + gy as i64;
+            if px < 0 || py < 0 || px >= canvas.width() as i64 || py >= canvas.height() as i64 {
+                return;
+            }
+            let pixel = canvas.get_pixel_mut(px as u32, py as u32);
+            let alpha = coverage * color[3] as f32 / 255.0;
+            for (channel, value) in pixel.0.iter_mut().zip(color).take(3) {
+                *channel = (value as f32 * alpha + *channel as f32 * (1.0 - alpha)) as u8;
+            }
+            pixel.0[3] = pixel.0[3].max((alpha * 255.0) as u8);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32, color: [u8; 4]) -> Vec<u8> {
+        let mut buf = Cursor::new(vec![]);
+        RgbaImage::from_pixel(width, height, Rgba(color))
+            .write_to(&mut buf, ImageFormat::Png)
+            .unwrap();
+        buf.into_inner()
+    }
+
+    #[test]
+    fn renders_layers_in_order() {
+        let data = Card::new(100, 50)
+            .background([0, 0, 0, 255])
+            .rect(0, 0, 50, 50, [0, 0, 255, 255])
+            .avatar(png(8, 8, [255, 0, 0, 255]), 60, 10, 30)
+            .render()
+            .unwrap();
+        let img = image::load_from_memory(&data).unwrap().to_rgba8();
+        assert_eq!((img.width(), img.height()), (100, 50));
+        assert_eq!(img.get_pixel(10, 10).0, [0, 0, 255, 255]);
+        assert_eq!(img.get_pixel(75, 25).0, [255, 0, 0, 255]);
+        // 头像是圆形的, 角上露出背景
+        assert_eq!(img.get_pixel(60, 10).0, [0, 0, 0, 255]);
+        assert_eq!(img.get_pixel(99, 49).0, [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn text_requires_font() {
+        let card = Card::new(10, 10).text("hi", 0, 0, 12.0, [0, 0, 0, 255]);
+        assert!(card.render().is_err());
+        assert!(Card::new(10, 10)
+            .background_image(b"not an image".to_vec())
+            .render()
+            .is_err());
+    }
+}
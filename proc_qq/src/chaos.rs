@@ -0,0 +1,80 @@
+use lazy_static::lazy_static;
+use rq_engine::{RQError, RQResult};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::SeededRng;
+
+/// 故障注入配置, 用于测试模块的重试和错误处理
+#[derive(Debug, Clone)]
+pub struct ChaosConfig {
+    /// 调用失败的概率 (0.0 - 1.0)
+    pub failure_probability: f64,
+    /// 调用延迟的概率 (0.0 - 1.0)
+    pub delay_probability: f64,
+    /// 最大延迟
+    pub max_delay: Duration,
+    /// 随机种子, 相同的种子得到相同的故障序列
+    pub seed: Option<u64>,
+    /// 只对这些方法注入 (如 "send_message_to_target"), 为空时对所有方法注入
+    pub methods: Vec<String>,
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self {
+            failure_probability: 0.1,
+            delay_probability: 0.1,
+            max_delay: Duration::from_secs(3),
+            seed: None,
+            methods: vec![],
+        }
+    }
+}
+
+lazy_static! {
+    static ref CHAOS: Mutex<Option<(ChaosConfig, SeededRng)>> = Mutex::new(None);
+}
+
+/// 开启或关闭(None)故障注入
+pub fn set_chaos_config(config: Option<ChaosConfig>) {
+    *CHAOS.lock().unwrap() = config.map(|config| {
+        let rng = match config.seed {
+            Some(seed) => SeededRng::new(seed),
+            None => SeededRng::from_time(),
+        };
+        (config, rng)
+    });
+}
+
+pub(crate) async fn inject_fault(method: &str) -> RQResult<()> {
+    let (delay, fail) = {
+        let mut chaos = CHAOS.lock().unwrap();
+        let (config, rng) = match chaos.as_mut() {
+            Some(chaos) => chaos,
+            None => return Ok(()),
+        };
+        if !config.methods.is_empty() && !config.methods.iter().any(|m| m == method) {
+            return Ok(());
+        }
+        let delay = if rng.gen_bool(config.delay_probability) {
+            let max = config.max_delay.as_millis() as i64;
+            Some(Duration::from_millis(rng.gen_range(0..max + 1) as u64))
+        } else {
+            None
+        };
+        (delay, rng.gen_bool(config.failure_probability))
+    };
+    if let Some(delay) = delay {
+        tracing::debug!(target = "proc_qq", "CHAOS : {} 延迟 {:?}", method, delay);
+        tokio::time::sleep(delay).await;
+    }
+    if fail {
+        tracing::warn!(target = "proc_qq", "CHAOS : {} 注入失败", method);
+        return Err(RQError::Other(format!(
+            "chaos: injected failure ({})",
+            method
+        )));
+    }
+    Ok(())
+}
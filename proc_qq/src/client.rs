@@ -1,44 +1,219 @@
+use crate::login_state::{set_login_qrcode, set_login_state};
 use crate::DeviceSource::{JsonFile, JsonString};
-use crate::{Authentication, ClientHandler, DeviceSource, Module};
+use crate::{
+    check_handler_order, order_handlers, Announcement, Authentication, ClientHandler, DeviceSource,
+    DispatchMode, Distributed, EventSink, EventTransport, IntakeConfig, IntakeQueue, LoginState,
+    Module, ModuleRegistry, OwnedEvent, RegistrationReport, RestartPolicy, TicketResolver,
+    TxHelperTicketResolver,
+};
 use anyhow::{Context, Result};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
+use futures::Stream;
+use image::{DynamicImage, GrayImage};
 use rq_engine::binary::{BinaryReader, BinaryWriter};
 use rq_engine::command::wtlogin::{
     LoginDeviceLocked, LoginNeedCaptcha, LoginResponse, LoginSuccess, LoginUnknownStatus,
     QRCodeConfirmed, QRCodeImageFetch, QRCodeState,
 };
 use rq_engine::protocol::device::Device;
-use rq_engine::protocol::version::{Version, ANDROID_PHONE};
+use rq_engine::protocol::version::{Protocol, Version, ANDROID_PHONE};
 use rq_engine::{RQError, RQResult, Token};
 use rs_qq::ext::common::after_login;
+use rs_qq::handler::QEvent;
 use std::path::Path;
 use std::sync::Arc;
-use std::time::Duration;
-use image::{DynamicImage, GrayImage};
+use std::time::{Duration, Instant};
 use tokio::net::TcpStream;
 use tokio::task::JoinHandle;
 use tokio::time::sleep;
 
+// 收到退出信号后等待处理中的事件完成的最长时间
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone)]
 pub struct Client {
     pub rq_client: Arc<rs_qq::Client>,
     pub authentication: Authentication,
     pub priority_session: Option<String>,
-    pub(crate) modules: Arc<Vec<Module>>,
+    pub(crate) modules: Arc<ModuleRegistry>,
+    pub(crate) show_registration_report: bool,
+    pub(crate) ticket_resolver: Arc<dyn TicketResolver>,
+    pub(crate) handler: ClientHandler,
+    pub(crate) version: &'static Version,
+    pub(crate) announcement: Option<Arc<Announcement>>,
 }
 
 impl Client {
     pub fn start(self) -> JoinHandle<Result<()>> {
         tokio::spawn(run_client(self))
     }
+
+    pub fn registration_report(&self) -> RegistrationReport {
+        RegistrationReport::from_modules(self.modules.snapshot().iter())
+    }
+
+    /// 上报好友的在线状态, 用于协议层能够得到好友状态时
+    pub async fn report_friend_presence(&self, uin: i64, online: bool) {
+        crate::handler::EventSender {
+            modules: self.modules.clone(),
+            rq_client: self.rq_client.clone(),
+        }
+        .send_friend_presence(uin, online)
+        .await
+    }
+
+    /// 直接分发事件, 和收到的事件一样经过过滤和模块, 但不经过接收队列
+    ///
+    /// 用于压测或分析模块的性能, 参考 SyntheticEvents
+    pub async fn dispatch_event(&self, e: QEvent) {
+        if crate::deliver_to_session(&e) {
+            return;
+        }
+        self.handler.process(e).await
+    }
+
+    /// 收到的所有事件, 和模块的分发同时进行, 用于在界面或网页中展示事件
+    ///
+    /// 处理太慢时会丢弃最旧的事件, 客户端被释放后结束
+    pub fn event_stream(&self) -> impl Stream<Item = OwnedEvent> {
+        self.handler.events.subscribe()
+    }
+
+    /// 把事件转发到应用提供的 mpsc channel, 可以在运行时添加
+    pub fn add_event_sink(&self, sink: EventSink) {
+        self.handler.events.add_sink(sink)
+    }
+
+    /// 在后台任务中调用时使用, 和事件中的行为一致
+    pub fn proc_client(&self) -> crate::ProcClient {
+        crate::ProcClient(self.rq_client.clone())
+    }
+
+    /// 已注册的模块, 可以在运行时添加或移除
+    pub fn modules(&self) -> Arc<ModuleRegistry> {
+        self.modules.clone()
+    }
+
+    /// 启动并一直运行到收到退出信号 (Ctrl+C / SIGTERM),
+    /// 事件循环崩溃或出错时按照重启策略重新启动
+    pub async fn run_forever(self, policy: RestartPolicy) -> Result<()> {
+        let mut restarts = 0;
+        loop {
+            let started = Instant::now();
+            let mut handle = self.clone().start();
+            let failure = tokio::select! {
+                result = &mut handle => match result {
+                    Ok(Ok(_)) => return Ok(()),
+                    Ok(Err(err)) => {
+                        if !policy.restart_on_error {
+                            return Err(err);
+                        }
+                        tracing::error!(target = "proc_qq", "客户端出现错误 : {:?}", err);
+                        err
+                    }
+                    Err(err) => {
+                        tracing::error!(target = "proc_qq", "事件循环崩溃 : {:?}", err);
+                        anyhow::Error::new(err)
+                    }
+                },
+                _ = shutdown_signal() => {
+                    tracing::info!(target = "proc_qq", "收到退出信号, 正在关闭");
+                    if let Some(announcement) = &self.announcement {
+                        announcement.announce_shutdown(&self).await;
+                    }
+                    self.shutdown(handle).await;
+                    return Ok(());
+                }
+            };
+            // 稳定运行一段时间之后出错, 不计入之前的重启次数
+            if started.elapsed() >= policy.reset_after {
+                restarts = 0;
+            }
+            restarts += 1;
+            if let Some(max_restarts) = policy.max_restarts {
+                if restarts > max_restarts {
+                    return Err(failure.context(format!("重启次数超过限制 ({})", max_restarts)));
+                }
+            }
+            tracing::info!(
+                target = "proc_qq",
+                "{:?}之后重启 (第{}次)",
+                policy.interval,
+                restarts
+            );
+            // 等待重启时客户端已经停止, 收到退出信号直接返回
+            tokio::select! {
+                _ = sleep(policy.interval) => (),
+                _ = shutdown_signal() => {
+                    tracing::info!(target = "proc_qq", "收到退出信号, 不再重启");
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    // 停止接收事件并等待处理中的事件完成, 超时后才强制结束
+    async fn shutdown(&self, mut handle: JoinHandle<Result<()>>) {
+        self.handler.shutdown.stop();
+        self.rq_client.stop();
+        if !self.handler.shutdown.wait_idle(SHUTDOWN_TIMEOUT).await {
+            tracing::warn!(
+                target = "proc_qq",
+                "{:?}内没有处理完所有事件, 强制退出",
+                SHUTDOWN_TIMEOUT
+            );
+        }
+        if tokio::time::timeout(SHUTDOWN_TIMEOUT, &mut handle)
+            .await
+            .is_err()
+        {
+            handle.abort();
+        }
+    }
+}
+
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            return;
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
 }
 
 pub async fn run_client(client: Client) -> Result<()> {
+    crate::handler::set_active_modules(client.modules.clone());
+    crate::set_protocol(client.version.protocol);
+    crate::start_storage_expiry();
+    tracing::info!(target = "proc_qq", "{}", crate::build_info());
+    tracing::debug!(
+        target = "proc_qq",
+        "不支持的操作 : {:?}",
+        client.capabilities().unsupported()
+    );
+    if client.show_registration_report {
+        let report = client.registration_report();
+        tracing::info!(target = "proc_qq", "{}", report.to_banner());
+        for conflict in &report.conflicts {
+            tracing::warn!(target = "proc_qq", "{}", conflict);
+        }
+    }
     let event_sender = crate::handler::EventSender {
         modules: client.modules.clone(),
+        rq_client: client.rq_client.clone(),
     };
+    // 断线重连时不重复发送启动通知
+    let mut announced = false;
     // todo // max try count
     // todo // not retry qr
     loop {
+        set_login_state(LoginState::Connecting);
         // connect to server
         let stream = match TcpStream::connect(client.rq_client.get_address())
             .await
@@ -59,8 +234,16 @@ pub async fn run_client(client: Client) -> Result<()> {
         if !token_login(&client).await {
             // authentication if token login failed or not set
             // The error of login failure is fatal
-            login_authentication(&client).await?;
+            if let Err(err) = login_authentication(&client).await {
+                set_login_state(LoginState::Failed {
+                    message: format!("{:?}", err),
+                });
+                return Err(err);
+            }
         }
+        set_login_state(LoginState::Online {
+            uin: client.rq_client.uin().await,
+        });
         // Reference rs-qq docs, this function must be called after login is completed, maybe it's to register the device.
         after_login(&client.rq_client.clone()).await;
         // save session, IO errors are fatal.
@@ -73,13 +256,28 @@ pub async fn run_client(client: Client) -> Result<()> {
             .with_context(|| "写入session出错")?;
         }
         //
+        if let Some(distributed) = &client.handler.distributed {
+            distributed.start_actions(client.proc_client());
+        }
         let _ = event_sender.send_connected_and_online().await;
+        crate::resume_scheduled_sends(&client.rq_client).await;
+        // 记录好友列表和群列表快照, 重连时对比离线期间的变化
+        event_sender.send_friend_changes().await;
+        event_sender.send_group_changes().await;
+        if let Some(announcement) = client.announcement.as_ref().filter(|_| !announced) {
+            announcement.announce_startup(&client).await;
+            announced = true;
+        }
         // hold handle
         match handle.await {
             Ok(_) => {}
             Err(err) => tracing::info!("{:?}", err),
         };
+        set_login_state(LoginState::Offline);
         let _ = event_sender.send_disconnected_and_offline().await;
+        if client.handler.shutdown.stopping() {
+            return Ok(());
+        }
         tracing::info!("连接已断开, 五秒钟之后重试");
         sleep(Duration::from_secs(5)).await;
     }
@@ -123,20 +321,24 @@ async fn token_login(client: &Client) -> bool {
 
 async fn login_authentication(client: &Client) -> Result<()> {
     let rq_client = client.rq_client.clone();
+    let ticket_resolver = client.ticket_resolver.as_ref();
     match &client.authentication {
-        Authentication::QRCode => qr_login(rq_client).await,
+        Authentication::QRCode => qr_login(rq_client, ticket_resolver).await,
         Authentication::UinPassword(uin, password) => {
             let first = rq_client.password_login(uin.clone(), password).await;
-            loop_login(rq_client, first).await
+            loop_login(rq_client, first, ticket_resolver).await
         }
         Authentication::UinPasswordMd5(uin, password) => {
             let first = rq_client.password_md5_login(uin.clone(), password).await;
-            loop_login(rq_client, first).await
+            loop_login(rq_client, first, ticket_resolver).await
         }
     }
 }
 
-async fn qr_login(rq_client: Arc<rs_qq::Client>) -> Result<()> {
+async fn qr_login(
+    rq_client: Arc<rs_qq::Client>,
+    ticket_resolver: &dyn TicketResolver,
+) -> Result<()> {
     let mut image_sig = Bytes::new();
     let mut resp = rq_client
         .fetch_qrcode()
@@ -152,6 +354,8 @@ async fn qr_login(rq_client: Arc<rs_qq::Client>) -> Result<()> {
                     .await
                     .with_context(|| "failed to write file")?;
                 image_sig = sig.clone();
+                set_login_qrcode(image_data.to_vec());
+                set_login_state(LoginState::WaitingForScan);
                 // todo 桌面环境直接打开, 服务器使用文字渲染
                 let qrcode_acsii = png_to_ascii("qrcode.png").unwrap();
                 tracing::info!("qrcode:\n{}", qrcode_acsii);
@@ -159,12 +363,15 @@ async fn qr_login(rq_client: Arc<rs_qq::Client>) -> Result<()> {
             }
             QRCodeState::WaitingForScan => {
                 // tracing::info!("二维码待扫描")
+                set_login_state(LoginState::WaitingForScan);
             }
             QRCodeState::WaitingForConfirm => {
                 // tracing::info!("二维码待确认")
+                set_login_state(LoginState::WaitingForConfirm);
             }
             QRCodeState::Timeout => {
                 tracing::info!("二维码已超时，重新获取");
+                set_login_state(LoginState::QRCodeExpired);
                 resp = rq_client
                     .fetch_qrcode()
                     .await
@@ -181,9 +388,12 @@ async fn qr_login(rq_client: Arc<rs_qq::Client>) -> Result<()> {
                 let first = rq_client
                     .qrcode_login(tmp_pwd, tmp_no_pic_sig, tgt_qr)
                     .await;
-                return loop_login(rq_client, first).await;
+                return loop_login(rq_client, first, ticket_resolver).await;
             }
             QRCodeState::Canceled => {
+                set_login_state(LoginState::Failed {
+                    message: "二维码已取消".to_owned(),
+                });
                 return Err(anyhow::Error::msg("二维码已取消"));
             }
         }
@@ -195,7 +405,11 @@ async fn qr_login(rq_client: Arc<rs_qq::Client>) -> Result<()> {
     }
 }
 
-async fn loop_login(client: Arc<rs_qq::Client>, first: RQResult<LoginResponse>) -> Result<()> {
+async fn loop_login(
+    client: Arc<rs_qq::Client>,
+    first: RQResult<LoginResponse>,
+    ticket_resolver: &dyn TicketResolver,
+) -> Result<()> {
     let mut resp = first.unwrap();
     loop {
         match resp {
@@ -226,27 +440,19 @@ async fn loop_login(client: Arc<rs_qq::Client>, first: RQResult<LoginResponse>)
                 ..
             }) => {
                 tracing::info!("滑动条 (原URL) : {:?}", verify_url);
-                let helper_url = verify_url
-                    .clone()
-                    .unwrap()
-                    .replace("ssl.captcha.qq.com", "txhelper.glitch.me");
-                tracing::info!("滑动条 (改URL) : {:?}", helper_url);
-                let mut txt = http_get(&helper_url)
+                let verify_url = verify_url.clone().with_context(|| "滑动条缺少验证地址")?;
+                set_login_state(LoginState::Captcha {
+                    verify_url: verify_url.clone(),
+                });
+                let ticket = ticket_resolver
+                    .resolve(&verify_url)
                     .await
-                    .with_context(|| "http请求失败")?;
-                tracing::info!("您需要使用该仓库 提供的APP进行滑动 , 滑动后请等待, https://github.com/mzdluo123/TxCaptchaHelper : {}", txt);
-                loop {
-                    sleep(Duration::from_secs(5)).await;
-                    let rsp = http_get(&helper_url)
-                        .await
-                        .with_context(|| "http请求失败")?;
-                    if !rsp.eq(&txt) {
-                        txt = rsp;
-                        break;
-                    }
-                }
-                tracing::info!("获取到ticket : {}", txt);
-                resp = client.submit_ticket(&txt).await.expect("发送ticket失败");
+                    .with_context(|| "获取ticket失败")?;
+                tracing::info!("获取到ticket : {}", ticket);
+                resp = client
+                    .submit_ticket(&ticket)
+                    .await
+                    .with_context(|| "发送ticket失败")?;
             }
             LoginResponse::DeviceLockLogin { .. } => {
                 resp = client
@@ -275,14 +481,6 @@ async fn loop_login(client: Arc<rs_qq::Client>, first: RQResult<LoginResponse>)
     }
 }
 
-async fn http_get(url: &str) -> Result<String> {
-    Ok(reqwest::ClientBuilder::new().build().unwrap().get(url).header(
-        "user-agent", "Mozilla/5.0 (Linux; Android 6.0; Nexus 5 Build/MRA58N) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/98.0.4758.80 Mobile Safari/537.36",
-    ).send().await?
-        .text()
-        .await?)
-}
-
 pub fn token_to_bytes(t: &Token) -> Bytes {
     let mut token = BytesMut::with_capacity(1024);
     token.put_i64(t.uin);
@@ -320,6 +518,13 @@ pub struct ClientBuilder {
     version: &'static Version,
     authentication: Option<Authentication>,
     priority_session: Option<String>,
+    show_registration_report: bool,
+    dispatch_mode: DispatchMode,
+    intake: Option<IntakeConfig>,
+    ticket_resolver: Arc<dyn TicketResolver>,
+    transport: Option<Arc<dyn EventTransport>>,
+    announcement: Option<Arc<Announcement>>,
+    config_file: Option<String>,
 }
 
 impl ClientBuilder {
@@ -329,45 +534,117 @@ impl ClientBuilder {
             version: ANDROID_PHONE,
             authentication: None,
             priority_session: None,
+            show_registration_report: true,
+            dispatch_mode: DispatchMode::default(),
+            intake: None,
+            ticket_resolver: Arc::new(TxHelperTicketResolver),
+            transport: None,
+            announcement: None,
+            config_file: None,
         }
     }
 
-    pub async fn build<S: Into<Arc<Vec<Module>>>>(&self, h: S) -> Result<Client, anyhow::Error> {
-        let modules = h.into();
-        Ok(Client {
-            rq_client: Arc::new(rs_qq::Client::new(
-                match &self.device_source {
-                    JsonFile(file_name) => {
-                        if Path::new(file_name).exists() {
-                            parse_device_json(
-                                &tokio::fs::read_to_string(file_name)
-                                    .await
-                                    .with_context(|| format!("读取文件失败 : {}", file_name))?,
-                            )?
-                        } else {
-                            let device = Device::random();
-                            tokio::fs::write(file_name, serde_json::to_string(&device).unwrap())
-                                .await
-                                .with_context(|| format!("写入文件失败 : {}", file_name))?;
-                            device
-                        }
-                    }
-                    JsonString(json_string) => parse_device_json(json_string)?,
-                },
-                self.version,
-                ClientHandler {
-                    modules: modules.clone(),
-                },
-            )),
-            authentication: self
-                .authentication
+    pub async fn build<S: Into<Arc<Vec<Module>>>>(&self, h: S) -> Result<Client, BuildError> {
+        let mut modules = h.into();
+        let authentication = self.validate(&modules)?;
+        if let Some(config_file) = &self.config_file {
+            let json = tokio::fs::read_to_string(config_file)
+                .await
+                .map_err(|err| BuildError::Io(config_file.clone(), err))?;
+            let config = serde_json::from_str(&json)
+                .map_err(|err| BuildError::Config(format!("{} : {}", config_file, err)))?;
+            crate::set_config(config);
+        }
+        for module in modules.iter() {
+            check_config_sections(module)?;
+        }
+        // 模块列表没有被共享时按照 after / before 重新排序, 否则只检查顺序
+        match Arc::get_mut(&mut modules) {
+            Some(modules) => {
+                for module in modules.iter_mut() {
+                    order_handlers(module).map_err(BuildError::HandlerOrder)?;
+                }
+            }
+            None => {
+                for module in modules.iter() {
+                    check_handler_order(module).map_err(BuildError::HandlerOrder)?;
+                }
+            }
+        }
+        let modules = Arc::new(ModuleRegistry::new(modules));
+        let device = match &self.device_source {
+            JsonFile(file_name) => {
+                if Path::new(file_name).exists() {
+                    parse_device_json(
+                        &tokio::fs::read_to_string(file_name)
+                            .await
+                            .map_err(|err| BuildError::Io(file_name.clone(), err))?,
+                    )?
+                } else {
+                    let device = Device::random();
+                    tokio::fs::write(file_name, serde_json::to_string(&device).unwrap())
+                        .await
+                        .map_err(|err| BuildError::Io(file_name.clone(), err))?;
+                    device
+                }
+            }
+            JsonString(json_string) => parse_device_json(json_string)?,
+        };
+        let handler = ClientHandler {
+            modules: modules.clone(),
+            dispatch_mode: self.dispatch_mode,
+            group_locks: Default::default(),
+            intake: self
+                .intake
                 .clone()
-                .with_context(|| "您必须设置验证方式 (调用authentication)")?,
+                .map(|config| Arc::new(IntakeQueue::new(config))),
+            distributed: self
+                .transport
+                .clone()
+                .map(|transport| Arc::new(Distributed::new(transport))),
+            events: Default::default(),
+            shutdown: Default::default(),
+        };
+        Ok(Client {
+            rq_client: Arc::new(rs_qq::Client::new(device, self.version, handler.clone())),
+            authentication,
             priority_session: self.priority_session.clone(),
             modules,
+            show_registration_report: self.show_registration_report,
+            ticket_resolver: self.ticket_resolver.clone(),
+            handler,
+            version: self.version,
+            announcement: self.announcement.clone(),
         })
     }
 
+    /// 检查配置组合, 在登录前给出错误
+    fn validate(&self, modules: &[Module]) -> Result<Authentication, BuildError> {
+        let authentication = self
+            .authentication
+            .clone()
+            .ok_or(BuildError::MissingAuthentication)?;
+        if let Authentication::QRCode = authentication {
+            if self.version.protocol != Protocol::AndroidWatch {
+                return Err(BuildError::QRCodeNotSupported(self.version.protocol));
+            }
+        }
+        if let JsonFile(file_name) = &self.device_source {
+            if file_name.trim().is_empty() {
+                return Err(BuildError::EmptyDevicePath);
+            }
+            if let Some(parent) = Path::new(file_name).parent() {
+                if !parent.as_os_str().is_empty() && !parent.exists() {
+                    return Err(BuildError::DeviceDirNotFound(file_name.clone()));
+                }
+            }
+        }
+        if modules.is_empty() {
+            return Err(BuildError::EmptyModules);
+        }
+        Ok(authentication)
+    }
+
     pub fn device(mut self, device_source: DeviceSource) -> Self {
         self.device_source = device_source;
         self
@@ -387,10 +664,125 @@ impl ClientBuilder {
         self.authentication = Some(authentication);
         self
     }
+
+    /// 启动时是否打印模块注册报告 (默认打印)
+    pub fn show_registration_report(mut self, show: bool) -> Self {
+        self.show_registration_report = show;
+        self
+    }
+
+    /// 密码登录需要滑动验证码时获取ticket的方式 (默认使用TxCaptchaHelper)
+    pub fn ticket_resolver<R: TicketResolver + 'static>(mut self, ticket_resolver: R) -> Self {
+        self.ticket_resolver = Arc::new(ticket_resolver);
+        self
+    }
+
+    /// 事件分发方式, 有状态的游戏模块可以使用SerialPerGroup保证同群事件按顺序处理
+    pub fn dispatch_mode(mut self, dispatch_mode: DispatchMode) -> Self {
+        self.dispatch_mode = dispatch_mode;
+        self
+    }
+
+    /// 使用有界的事件接收队列, 突发大量事件时按照溢出策略丢弃或等待, 避免内存无限增长
+    pub fn intake_queue(mut self, config: IntakeConfig) -> Self {
+        self.intake = Some(config);
+        self
+    }
+
+    /// 消息事件不在本地处理, 而是通过transport发送给worker (run_worker),
+    /// 再执行worker返回的操作, 其他事件仍然在本地处理
+    pub fn distributed(mut self, transport: Arc<dyn EventTransport>) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// 模块的配置文件 (JSON), 处理器通过 #[config(section = "...")] 或 config_section 读取,
+    /// build时检查所有处理器使用的配置节
+    pub fn config_file<S: Into<String>>(mut self, config_file: S) -> Self {
+        self.config_file = Some(config_file.into());
+        self
+    }
+
+    /// 启动 (首次登录成功) 和收到退出信号时通知管理员和指定的群
+    pub fn announcement(mut self, announcement: Announcement) -> Self {
+        self.announcement = Some(Arc::new(announcement));
+        self
+    }
+}
+
+fn parse_device_json(json: &str) -> Result<Device, BuildError> {
+    serde_json::from_str(json).map_err(BuildError::DeviceJson)
+}
+
+/// ClientBuilder::build 的错误
+#[derive(Debug)]
+pub enum BuildError {
+    /// 未设置验证方式
+    MissingAuthentication,
+    /// 协议不支持二维码登录
+    QRCodeNotSupported(Protocol),
+    /// 设备文件路径为空
+    EmptyDevicePath,
+    /// 设备文件所在的目录不存在
+    DeviceDirNotFound(String),
+    /// 设备文件解析失败
+    DeviceJson(serde_json::Error),
+    /// 设备文件读写失败
+    Io(String, std::io::Error),
+    /// 没有注册任何模块
+    EmptyModules,
+    /// 处理器的 after / before 引用了不存在的处理器或者有循环
+    HandlerOrder(String),
+    /// 配置文件解析失败, 或者处理器使用的配置节格式错误
+    Config(String),
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::MissingAuthentication => {
+                write!(f, "您必须设置验证方式 (调用authentication)")
+            }
+            BuildError::QRCodeNotSupported(protocol) => write!(
+                f,
+                "{:?}协议不支持二维码登录, 请使用 .version(&ANDROID_WATCH) 或改用密码登录",
+                protocol
+            ),
+            BuildError::EmptyDevicePath => write!(f, "设备文件路径不能为空"),
+            BuildError::DeviceDirNotFound(file_name) => {
+                write!(f, "设备文件所在的目录不存在, 请先创建 : {}", file_name)
+            }
+            BuildError::DeviceJson(err) => write!(f, "DeviceJson解析失败 : {}", err),
+            BuildError::Io(file_name, err) => write!(f, "读写文件失败 : {} : {}", file_name, err),
+            BuildError::EmptyModules => {
+                write!(f, "没有注册任何模块, 请在build时传入至少一个模块")
+            }
+            BuildError::HandlerOrder(message) => write!(f, "处理器顺序错误 : {}", message),
+            BuildError::Config(message) => write!(f, "配置错误 : {}", message),
+        }
+    }
+}
+
+/// 检查模块的处理器使用的配置节, build和运行时添加模块 (ModuleRegistry::add) 都会检查
+pub(crate) fn check_config_sections(module: &Module) -> Result<(), BuildError> {
+    for handle in &module.handles {
+        for section in &handle.config_sections {
+            section.check().map_err(|err| {
+                BuildError::Config(format!("{} ({}) : {}", module.id, handle.name, err))
+            })?;
+        }
+    }
+    Ok(())
 }
 
-fn parse_device_json(json: &str) -> Result<Device, anyhow::Error> {
-    Ok(serde_json::from_str(json).with_context(|| format!("DeviceJson解析失败"))?)
+impl std::error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BuildError::DeviceJson(err) => Some(err),
+            BuildError::Io(_, err) => Some(err),
+            _ => None,
+        }
+    }
 }
 
 fn png_to_ascii(img_path: impl AsRef<Path>) -> Result<String> {
@@ -416,4 +808,4 @@ fn png_to_ascii(img_path: impl AsRef<Path>) -> Result<String> {
     }
 
     Ok(result)
-}
\ No newline at end of file
+}
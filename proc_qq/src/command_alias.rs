@@ -0,0 +1,145 @@
+use lazy_static::lazy_static;
+use rq_engine::msg::MessageChain;
+use rq_engine::pb::msg::elem::Elem;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+lazy_static! {
+    static ref GLOBAL_ALIASES: RwLock<HashMap<String, String>> = RwLock::new(HashMap::new());
+    static ref GROUP_ALIASES: RwLock<HashMap<i64, HashMap<String, String>>> =
+        RwLock::new(HashMap::new());
+}
+
+/// 设置全局命令别名, 例如 "签到" -> "checkin"
+pub fn set_command_alias<A: Into<String>, C: Into<String>>(alias: A, command: C) {
+    GLOBAL_ALIASES
+        .write()
+        .unwrap()
+        .insert(alias.into(), command.into());
+}
+
+/// 删除全局命令别名, 返回原来指向的命令
+pub fn remove_command_alias(alias: &str) -> Option<String> {
+    GLOBAL_ALIASES.write().unwrap().remove(alias)
+}
+
+/// 设置群命令别名, 优先于全局别名
+pub fn set_group_command_alias<A: Into<String>, C: Into<String>>(
+    group_code: i64,
+    alias: A,
+    command: C,
+) {
+    GROUP_ALIASES
+        .write()
+        .unwrap()
+        .entry(group_code)
+        .or_default()
+        .insert(alias.into(), command.into());
+}
+
+/// 删除群命令别名, 返回原来指向的命令
+pub fn remove_group_command_alias(group_code: i64, alias: &str) -> Option<String> {
+    let mut lock = GROUP_ALIASES.write().unwrap();
+    let aliases = lock.get_mut(&group_code)?;
+    let command = aliases.remove(alias);
+    if aliases.is_empty() {
+        lock.remove(&group_code);
+    }
+    command
+}
+
+/// 当前生效的别名 (群别名覆盖全局别名)
+pub fn command_aliases(group_code: Option<i64>) -> HashMap<String, String> {
+    let mut aliases = GLOBAL_ALIASES.read().unwrap().clone();
+    if let Some(group_code) = group_code {
+        if let Some(group) = GROUP_ALIASES.read().unwrap().get(&group_code) {
+            aliases.extend(group.clone());
+        }
+    }
+    aliases
+}
+
+/// 查询别名对应的命令, 先查群别名再查全局别名
+pub fn resolve_command_alias(group_code: Option<i64>, word: &str) -> Option<String> {
+    if let Some(group_code) = group_code {
+        if let Some(command) = GROUP_ALIASES
+            .read()
+            .unwrap()
+            .get(&group_code)
+            .and_then(|aliases| aliases.get(word))
+        {
+            return Some(command.clone());
+        }
+    }
+    GLOBAL_ALIASES.read().unwrap().get(word).cloned()
+}
+
+/// 把消息中第一段文字的第一个词替换成别名指向的命令, 替换过返回true
+pub(crate) fn apply_command_alias(group_code: Option<i64>, chain: &mut MessageChain) -> bool {
+    for elem in chain.0.iter_mut() {
+        if let Elem::Text(text) = elem {
+            // at 也是以 Text 的形式存在, 跳过
+            if text.attr6_buf.is_some() {
+                continue;
+            }
+            let content = match text.str.as_mut() {
+                Some(content) => content,
+                None => continue,
+            };
+            let trimmed = content.trim_start();
+            if trimmed.is_empty() {
+                continue;
+            }
+            let offset = content.len() - trimmed.len();
+            let end = trimmed
+                .find(char::is_whitespace)
+                .map(|i| offset + i)
+                .unwrap_or(content.len());
+            return match resolve_command_alias(group_code, &content[offset..end]) {
+                Some(command) => {
+                    content.replace_range(offset..end, &command);
+                    true
+                }
+                None => false,
+            };
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_aliases_override_global() {
+        set_command_alias("alias_test_qd", "签到");
+        set_group_command_alias(1, "alias_test_qd", "打卡");
+        assert_eq!(
+            resolve_command_alias(Some(1), "alias_test_qd").as_deref(),
+            Some("打卡")
+        );
+        assert_eq!(
+            resolve_command_alias(Some(2), "alias_test_qd").as_deref(),
+            Some("签到")
+        );
+        assert_eq!(
+            resolve_command_alias(None, "alias_test_qd").as_deref(),
+            Some("签到")
+        );
+        assert_eq!(command_aliases(Some(1))["alias_test_qd"], "打卡");
+        assert_eq!(
+            remove_group_command_alias(1, "alias_test_qd").as_deref(),
+            Some("打卡")
+        );
+        assert_eq!(
+            resolve_command_alias(Some(1), "alias_test_qd").as_deref(),
+            Some("签到")
+        );
+        assert_eq!(
+            remove_command_alias("alias_test_qd").as_deref(),
+            Some("签到")
+        );
+        assert_eq!(resolve_command_alias(Some(1), "alias_test_qd"), None);
+    }
+}
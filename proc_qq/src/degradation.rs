@@ -0,0 +1,80 @@
+use lazy_static::lazy_static;
+use rq_engine::msg::elem::{FlashImage, RQElem, Text};
+use rq_engine::msg::MessageChain;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use crate::current_module_id;
+
+/// 当前场景不支持的操作如何降级, 默认不降级, 直接返回错误
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Degradation {
+    /// 临时会话中的图片改为发送图片链接
+    pub image_as_url: bool,
+    /// 不能发送语音时改为发送这段文字
+    pub audio_as_text: Option<String>,
+}
+
+impl Degradation {
+    /// 图片改为链接, 语音改为 "[语音]"
+    pub fn fallback() -> Self {
+        Self {
+            image_as_url: true,
+            audio_as_text: Some("[语音]".to_owned()),
+        }
+    }
+}
+
+lazy_static! {
+    static ref DEFAULT_DEGRADATION: RwLock<Degradation> = RwLock::new(Degradation::default());
+    static ref MODULE_DEGRADATION: RwLock<HashMap<String, Degradation>> =
+        RwLock::new(HashMap::new());
+}
+
+/// 所有模块默认的降级方式
+pub fn set_degradation(degradation: Degradation) {
+    *DEFAULT_DEGRADATION.write().unwrap() = degradation;
+}
+
+/// 单独设置一个模块的降级方式, 为None时使用默认的
+pub fn set_module_degradation<S: Into<String>>(module_id: S, degradation: Option<Degradation>) {
+    let mut lock = MODULE_DEGRADATION.write().unwrap();
+    match degradation {
+        Some(degradation) => lock.insert(module_id.into(), degradation),
+        None => lock.remove(&module_id.into()),
+    };
+}
+
+/// 当前模块的降级方式, 在模块之外调用时为默认的
+pub fn degradation() -> Degradation {
+    if let Some(module_id) = current_module_id() {
+        if let Some(degradation) = MODULE_DEGRADATION.read().unwrap().get(&module_id) {
+            return degradation.clone();
+        }
+    }
+    DEFAULT_DEGRADATION.read().unwrap().clone()
+}
+
+/// 临时会话不能发送图片, 按照降级方式把图片替换为链接
+pub(crate) fn degrade_temp_message(message: MessageChain) -> MessageChain {
+    if !degradation().image_as_url {
+        return message;
+    }
+    let mut chain = MessageChain::default();
+    for elem in message.into_iter() {
+        let url = match &elem {
+            RQElem::FriendImage(image) | RQElem::FlashImage(FlashImage::FriendImage(image)) => {
+                image.url()
+            }
+            RQElem::GroupImage(image) | RQElem::FlashImage(FlashImage::GroupImage(image)) => {
+                image.url()
+            }
+            _ => {
+                chain.push(elem);
+                continue;
+            }
+        };
+        chain.push(Text::new(url));
+    }
+    chain
+}
@@ -0,0 +1,286 @@
+use async_trait::async_trait;
+use rs_qq::handler::QEvent;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+
+use crate::{ClientTrait, MessageChainParseTrait, MessageEvent, MessageTarget, ProcClient};
+
+/// 连接进程和worker之间传递数据的方式, 内容为JSON
+///
+/// 进程内使用 InProcessTransport, 跨进程时可以用Redis的列表或NATS的subject实现
+#[async_trait]
+pub trait EventTransport: Send + Sync + std::fmt::Debug {
+    async fn publish_event(&self, event: Vec<u8>) -> anyhow::Result<()>;
+    async fn next_event(&self) -> anyhow::Result<Vec<u8>>;
+    async fn publish_action(&self, action: Vec<u8>) -> anyhow::Result<()>;
+    async fn next_action(&self) -> anyhow::Result<Vec<u8>>;
+}
+
+/// 同一进程内的传输, 用于测试或在同一进程内运行多个worker
+#[derive(Debug)]
+pub struct InProcessTransport {
+    events: (
+        mpsc::UnboundedSender<Vec<u8>>,
+        Mutex<mpsc::UnboundedReceiver<Vec<u8>>>,
+    ),
+    actions: (
+        mpsc::UnboundedSender<Vec<u8>>,
+        Mutex<mpsc::UnboundedReceiver<Vec<u8>>>,
+    ),
+}
+
+impl InProcessTransport {
+    pub fn new() -> Self {
+        let (event_tx, event_rx) = mpsc::unbounded_channel();
+        let (action_tx, action_rx) = mpsc::unbounded_channel();
+        Self {
+            events: (event_tx, Mutex::new(event_rx)),
+            actions: (action_tx, Mutex::new(action_rx)),
+        }
+    }
+}
+
+impl Default for InProcessTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl EventTransport for InProcessTransport {
+    async fn publish_event(&self, event: Vec<u8>) -> anyhow::Result<()> {
+        Ok(self.events.0.send(event)?)
+    }
+
+    async fn next_event(&self) -> anyhow::Result<Vec<u8>> {
+        self.events
+            .1
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or_else(|| anyhow::Error::msg("事件通道已关闭"))
+    }
+
+    async fn publish_action(&self, action: Vec<u8>) -> anyhow::Result<()> {
+        Ok(self.actions.0.send(action)?)
+    }
+
+    async fn next_action(&self) -> anyhow::Result<Vec<u8>> {
+        self.actions
+            .1
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or_else(|| anyhow::Error::msg("结果通道已关闭"))
+    }
+}
+
+/// 发送给worker的消息事件, event为 MessageEvent::to_json 的格式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerEvent {
+    pub id: u64,
+    pub event: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkerTarget {
+    Group { group_code: i64 },
+    Private { uin: i64 },
+    Temp { group_code: Option<i64>, uin: i64 },
+}
+
+impl From<WorkerTarget> for MessageTarget {
+    fn from(target: WorkerTarget) -> Self {
+        match target {
+            WorkerTarget::Group { group_code } => MessageTarget::Group(group_code, 0),
+            WorkerTarget::Private { uin } => MessageTarget::Private(uin),
+            WorkerTarget::Temp { group_code, uin } => MessageTarget::Temp(group_code, uin),
+        }
+    }
+}
+
+/// worker处理事件的结果, 由连接进程执行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkerAction {
+    SendText {
+        event_id: u64,
+        target: WorkerTarget,
+        text: String,
+    },
+}
+
+impl WorkerEvent {
+    pub fn content(&self) -> &str {
+        self.event["content"].as_str().unwrap_or_default()
+    }
+
+    pub fn from_uin(&self) -> i64 {
+        self.event["sender"]["uin"].as_i64().unwrap_or_default()
+    }
+
+    pub fn group_code(&self) -> Option<i64> {
+        self.event["target"]["group_code"].as_i64()
+    }
+
+    /// 消息来源, 回复时发送到这里
+    pub fn source(&self) -> Option<WorkerTarget> {
+        match self.event["type"].as_str()? {
+            "group" => Some(WorkerTarget::Group {
+                group_code: self.group_code()?,
+            }),
+            "friend" => Some(WorkerTarget::Private {
+                uin: self.from_uin(),
+            }),
+            "temp" => Some(WorkerTarget::Temp {
+                group_code: self.group_code(),
+                uin: self.from_uin(),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn reply<S: Into<String>>(&self, text: S) -> Option<WorkerAction> {
+        Some(WorkerAction::SendText {
+            event_id: self.id,
+            target: self.source()?,
+            text: text.into(),
+        })
+    }
+}
+
+/// worker中处理事件, 返回需要执行的操作, 返回空时交给下一个处理器
+#[async_trait]
+pub trait WorkerHandler: Send + Sync {
+    async fn handle(&self, event: &WorkerEvent) -> anyhow::Result<Vec<WorkerAction>>;
+}
+
+/// 运行worker, 从transport接收事件, 处理后把结果发送回连接进程
+pub async fn run_worker(
+    transport: Arc<dyn EventTransport>,
+    handlers: Vec<Box<dyn WorkerHandler>>,
+) -> anyhow::Result<()> {
+    loop {
+        let data = transport.next_event().await?;
+        let event: WorkerEvent = match serde_json::from_slice(&data) {
+            Ok(event) => event,
+            Err(err) => {
+                tracing::warn!(target = "proc_qq", "无法解析worker事件 : {:?}", err);
+                continue;
+            }
+        };
+        for handler in &handlers {
+            match handler.handle(&event).await {
+                Ok(actions) if actions.is_empty() => continue,
+                Ok(actions) => {
+                    for action in actions {
+                        transport
+                            .publish_action(serde_json::to_vec(&action)?)
+                            .await?;
+                    }
+                    break;
+                }
+                Err(err) => {
+                    tracing::error!(
+                        target = "proc_qq",
+                        "worker处理事件 {} 出现错误 : {:?}",
+                        event.id,
+                        err
+                    );
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// 连接进程中的分布式状态
+pub(crate) struct Distributed {
+    transport: Arc<dyn EventTransport>,
+    next_id: AtomicU64,
+    started: AtomicBool,
+}
+
+impl Distributed {
+    pub(crate) fn new(transport: Arc<dyn EventTransport>) -> Self {
+        Self {
+            transport,
+            next_id: AtomicU64::new(1),
+            started: AtomicBool::new(false),
+        }
+    }
+
+    /// 消息事件发送给worker, 其他事件返回给调用者在本地处理
+    pub(crate) async fn publish(&self, e: QEvent) -> Option<QEvent> {
+        let event = match e {
+            QEvent::GroupMessage(event) => MessageEvent::GroupMessage(event),
+            QEvent::FriendMessage(event) => MessageEvent::FriendMessage(event),
+            QEvent::TempMessage(event) => MessageEvent::TempMessage(event),
+            e => return Some(e),
+        };
+        let event = WorkerEvent {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            event: event.to_json(),
+        };
+        let result = match serde_json::to_vec(&event) {
+            Ok(data) => self.transport.publish_event(data).await,
+            Err(err) => Err(err.into()),
+        };
+        if let Err(err) = result {
+            tracing::error!(target = "proc_qq", "发送事件到worker失败 : {:?}", err);
+        }
+        None
+    }
+
+    /// 执行worker返回的操作, 重启客户端时不会重复启动
+    pub(crate) fn start_actions(&self, client: ProcClient) {
+        if self.started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let transport = self.transport.clone();
+        tokio::spawn(async move {
+            loop {
+                let data = match transport.next_action().await {
+                    Ok(data) => data,
+                    Err(err) => {
+                        tracing::error!(target = "proc_qq", "接收worker结果失败 : {:?}", err);
+                        return;
+                    }
+                };
+                let action: WorkerAction = match serde_json::from_slice(&data) {
+                    Ok(action) => action,
+                    Err(err) => {
+                        tracing::warn!(target = "proc_qq", "无法解析worker结果 : {:?}", err);
+                        continue;
+                    }
+                };
+                match action {
+                    WorkerAction::SendText {
+                        event_id,
+                        target,
+                        text,
+                    } => {
+                        let target: MessageTarget = target.into();
+                        if let Err(err) = client
+                            .send_message_to_target(&target, text.parse_message_chain())
+                            .await
+                        {
+                            tracing::warn!(
+                                target = "proc_qq",
+                                "执行worker结果失败 (事件 {}) : {:?}",
+                                event_id,
+                                err
+                            );
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,211 @@
+use lazy_static::lazy_static;
+use rq_engine::msg::MessageChain;
+use rq_engine::structs::MessageReceipt;
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Mutex, RwLock};
+use std::time::SystemTime;
+
+use crate::{current_actor, Actor, MessageTarget};
+
+// 最多保留的试运行消息数量
+const DRY_RUN_LOG_LIMIT: usize = 100;
+
+lazy_static! {
+    static ref DRY_RUN_MODULES: RwLock<HashSet<String>> = RwLock::new(HashSet::new());
+    static ref DRY_RUN_SENDS: Mutex<VecDeque<DryRunSend>> = Mutex::new(VecDeque::new());
+    static ref DRY_RUN_ACTIONS: Mutex<VecDeque<DryRunAction>> = Mutex::new(VecDeque::new());
+}
+
+/// 试运行中的处理器发送的消息, 没有真正发出
+#[derive(Debug, Clone)]
+pub struct DryRunSend {
+    pub actor: Actor,
+    pub target: MessageTarget,
+    pub chain: MessageChain,
+    pub time: SystemTime,
+}
+
+/// 试运行中的处理器执行的其他操作 (撤回, 禁言, 踢人等), 没有真正执行
+#[derive(Debug, Clone)]
+pub struct DryRunAction {
+    pub actor: Actor,
+    pub action: String,
+    pub detail: String,
+    pub time: SystemTime,
+}
+
+/// 设置模块是否试运行, 试运行的模块照常处理线上的事件, 但发送的消息和其他操作只记录不执行
+pub fn set_dry_run(module_id: &str, dry_run: bool) {
+    let mut modules = DRY_RUN_MODULES.write().unwrap();
+    if dry_run {
+        modules.insert(module_id.to_owned());
+    } else {
+        modules.remove(module_id);
+    }
+}
+
+pub fn dry_run_modules() -> Vec<String> {
+    let mut modules: Vec<String> = DRY_RUN_MODULES.read().unwrap().iter().cloned().collect();
+    modules.sort();
+    modules
+}
+
+pub(crate) fn module_dry_run(module_id: &str) -> bool {
+    DRY_RUN_MODULES.read().unwrap().contains(module_id)
+}
+
+/// 当前的处理器是否在试运行, 不在处理器中时返回false
+pub fn is_dry_run() -> bool {
+    current_actor().map(|actor| actor.dry_run).unwrap_or(false)
+}
+
+/// 最近的试运行消息, 从旧到新
+pub fn dry_run_sends() -> Vec<DryRunSend> {
+    DRY_RUN_SENDS.lock().unwrap().iter().cloned().collect()
+}
+
+/// 最近的试运行操作, 从旧到新
+pub fn dry_run_actions() -> Vec<DryRunAction> {
+    DRY_RUN_ACTIONS.lock().unwrap().iter().cloned().collect()
+}
+
+fn push_limited<T>(records: &Mutex<VecDeque<T>>, record: T) {
+    let mut records = records.lock().unwrap();
+    if records.len() >= DRY_RUN_LOG_LIMIT {
+        records.pop_front();
+    }
+    records.push_back(record);
+}
+
+/// 试运行时记录消息并返回回执, 否则返回None
+pub(crate) fn capture_dry_run(
+    target: &MessageTarget,
+    chain: &MessageChain,
+) -> Option<MessageReceipt> {
+    let actor = current_actor().filter(|actor| actor.dry_run)?;
+    tracing::info!(
+        target = "proc_qq",
+        "DRY RUN {} ({}) : {}",
+        crate::log_target(target),
+        actor.module_id,
+        crate::log_chain(chain)
+    );
+    push_limited(
+        &DRY_RUN_SENDS,
+        DryRunSend {
+            actor,
+            target: *target,
+            chain: chain.clone(),
+            time: SystemTime::now(),
+        },
+    );
+    Some(MessageReceipt::default())
+}
+
+/// 试运行时记录操作并返回true, 调用者不再执行这个操作
+pub(crate) fn capture_dry_run_action<D: FnOnce() -> String>(action: &str, detail: D) -> bool {
+    let actor = match current_actor().filter(|actor| actor.dry_run) {
+        Some(actor) => actor,
+        None => return false,
+    };
+    let detail = detail();
+    tracing::info!(
+        target = "proc_qq",
+        "DRY RUN {} ({}) : {}",
+        action,
+        actor.module_id,
+        detail
+    );
+    push_limited(
+        &DRY_RUN_ACTIONS,
+        DryRunAction {
+            actor,
+            action: action.to_owned(),
+            detail,
+            time: SystemTime::now(),
+        },
+    );
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handler::with_actor;
+    use crate::{spawn_with_actor, ClientTrait, MessageChainParseTrait};
+    use std::time::Duration;
+
+    // 试运行时不应该调用协议, 调用即测试失败
+    struct NoProtocol;
+
+    impl ClientTrait for NoProtocol {
+        fn protocol_client(&self) -> &rs_qq::Client {
+            panic!("试运行的操作调用了协议")
+        }
+    }
+
+    fn actor(module_id: &str) -> Actor {
+        Actor {
+            module_id: module_id.to_owned(),
+            handler: "handler".to_owned(),
+            event: "GroupMessageEvent".to_owned(),
+            group_code: Some(1),
+            from_uin: Some(2),
+            dry_run: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn send_is_recorded_and_not_sent() {
+        let target = MessageTarget::Group(100, 0);
+        with_actor(actor("dry_run_send"), async {
+            NoProtocol
+                .send_message_to_target(&target, "hello".parse_message_chain())
+                .await
+                .unwrap();
+        })
+        .await;
+        let sends: Vec<DryRunSend> = dry_run_sends()
+            .into_iter()
+            .filter(|send| send.actor.module_id == "dry_run_send")
+            .collect();
+        assert_eq!(sends.len(), 1);
+        assert_eq!(sends[0].target, target);
+    }
+
+    #[tokio::test]
+    async fn actions_are_recorded_and_not_executed() {
+        with_actor(actor("dry_run_action"), async {
+            NoProtocol
+                .mute_member(100, 200, Duration::from_secs(60))
+                .await
+                .unwrap();
+            NoProtocol.kick_member(100, 200, "", false).await.unwrap();
+        })
+        .await;
+        let actions: Vec<String> = dry_run_actions()
+            .into_iter()
+            .filter(|action| action.actor.module_id == "dry_run_action")
+            .map(|action| action.action)
+            .collect();
+        assert_eq!(actions, vec!["mute", "kick"]);
+    }
+
+    #[tokio::test]
+    async fn spawned_tasks_keep_the_actor() {
+        with_actor(actor("dry_run_spawn"), async {
+            spawn_with_actor(async {
+                NoProtocol
+                    .send_message_to_target(&MessageTarget::Private(3), "hi".parse_message_chain())
+                    .await
+                    .unwrap();
+            })
+            .await
+            .unwrap();
+        })
+        .await;
+        assert!(dry_run_sends()
+            .iter()
+            .any(|send| send.actor.module_id == "dry_run_spawn"));
+    }
+}
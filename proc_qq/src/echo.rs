@@ -0,0 +1,14 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ECHO_SUPPRESSION: AtomicBool = AtomicBool::new(true);
+
+/// 是否跳过机器人自己发送的消息 (包括在其他设备上发送的), 默认跳过, 避免处理器回复自己形成循环
+///
+/// 关闭后所有处理器都会收到自己的消息, 只需要个别处理器接收时使用 #[own_messages]
+pub fn set_echo_suppression(enabled: bool) {
+    ECHO_SUPPRESSION.store(enabled, Ordering::Relaxed);
+}
+
+pub fn echo_suppression() -> bool {
+    ECHO_SUPPRESSION.load(Ordering::Relaxed)
+}
@@ -18,3 +18,43 @@ pub enum Authentication {
     UinPassword(i64, String),
     UinPasswordMd5(i64, [u8; 16]),
 }
+
+/// 发送失败或未得到服务器确认时的重发策略
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub interval: std::time::Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            interval: std::time::Duration::from_secs(1),
+        }
+    }
+}
+
+/// run_forever 的重启策略
+#[derive(Debug, Clone)]
+pub struct RestartPolicy {
+    /// 最多重启次数, None为不限制
+    pub max_restarts: Option<u32>,
+    /// 重启前等待的时间
+    pub interval: std::time::Duration,
+    /// 出现致命错误 (如登录失败) 时是否也重启, 否则直接返回错误
+    pub restart_on_error: bool,
+    /// 运行超过这个时间之后出错, 重启次数重新计算
+    pub reset_after: std::time::Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: Some(5),
+            interval: std::time::Duration::from_secs(5),
+            restart_on_error: false,
+            reset_after: std::time::Duration::from_secs(600),
+        }
+    }
+}
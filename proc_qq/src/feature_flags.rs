@@ -0,0 +1,93 @@
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use crate::EventInfo;
+
+/// 功能开关的来源, 配合 #[feature("name")] 使用
+pub trait FeatureFlags: Send + Sync {
+    /// 未知的开关视为关闭
+    fn is_enabled(&self, flag: &str, info: &EventInfo) -> bool;
+}
+
+/// 固定配置的功能开关
+#[derive(Debug, Clone, Default)]
+pub struct StaticFeatureFlags {
+    flags: HashMap<String, bool>,
+}
+
+impl StaticFeatureFlags {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set<S: Into<String>>(mut self, flag: S, enabled: bool) -> Self {
+        self.flags.insert(flag.into(), enabled);
+        self
+    }
+}
+
+impl FeatureFlags for StaticFeatureFlags {
+    fn is_enabled(&self, flag: &str, _info: &EventInfo) -> bool {
+        self.flags.get(flag).cloned().unwrap_or(false)
+    }
+}
+
+/// 定时从HTTP地址拉取开关, 返回内容为 {"new_search": true}
+/// 拉取失败时保留上一次的结果
+pub struct HttpFeatureFlags {
+    flags: Arc<RwLock<HashMap<String, bool>>>,
+}
+
+impl HttpFeatureFlags {
+    /// 需要在tokio运行时中调用
+    pub fn new<S: Into<String>>(url: S, interval: Duration) -> Self {
+        let url = url.into();
+        let flags = Arc::new(RwLock::new(HashMap::new()));
+        let holder = flags.clone();
+        tokio::spawn(async move {
+            loop {
+                match fetch_flags(&url).await {
+                    Ok(fetched) => *holder.write().unwrap() = fetched,
+                    Err(err) => {
+                        tracing::warn!(target = "proc_qq", "拉取功能开关失败 : {:?}", err)
+                    }
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+        Self { flags }
+    }
+}
+
+async fn fetch_flags(url: &str) -> anyhow::Result<HashMap<String, bool>> {
+    let text = reqwest::get(url).await?.error_for_status()?.text().await?;
+    Ok(serde_json::from_str(&text)?)
+}
+
+impl FeatureFlags for HttpFeatureFlags {
+    fn is_enabled(&self, flag: &str, _info: &EventInfo) -> bool {
+        self.flags
+            .read()
+            .unwrap()
+            .get(flag)
+            .cloned()
+            .unwrap_or(false)
+    }
+}
+
+lazy_static! {
+    static ref FEATURE_FLAGS: RwLock<Arc<dyn FeatureFlags>> =
+        RwLock::new(Arc::new(StaticFeatureFlags::new()));
+}
+
+/// 设置功能开关的来源, 默认所有开关关闭
+pub fn set_feature_flags<F: FeatureFlags + 'static>(flags: F) {
+    *FEATURE_FLAGS.write().unwrap() = Arc::new(flags);
+}
+
+pub fn feature_enabled(flag: &str, info: &EventInfo) -> bool {
+    let flags = FEATURE_FLAGS.read().unwrap().clone();
+    flags.is_enabled(flag, info)
+}
@@ -0,0 +1,111 @@
+use lazy_static::lazy_static;
+use rq_engine::msg::elem::{RQElem, Text};
+use rq_engine::msg::MessageChain;
+use rq_engine::structs::{ForwardMessage, MessageNode};
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 群消息超出行数或字数时, 自动打包为合并转发发送
+#[derive(Debug, Clone)]
+pub struct ForwardThreshold {
+    /// 超过此行数时转为合并转发
+    pub max_lines: usize,
+    /// 超过此字数时转为合并转发
+    pub max_chars: usize,
+    /// 合并转发中每条消息的最大行数
+    pub node_lines: usize,
+    /// 合并转发中显示的发送者名称, 为空时使用机器人QQ号
+    pub sender_name: String,
+}
+
+impl Default for ForwardThreshold {
+    fn default() -> Self {
+        Self {
+            max_lines: 15,
+            max_chars: 500,
+            node_lines: 15,
+            sender_name: String::new(),
+        }
+    }
+}
+
+lazy_static! {
+    static ref FORWARD_THRESHOLD: RwLock<Option<ForwardThreshold>> = RwLock::new(None);
+}
+
+/// 设置合并转发的阈值, None为关闭 (默认关闭)
+pub fn set_forward_threshold(threshold: Option<ForwardThreshold>) {
+    *FORWARD_THRESHOLD.write().unwrap() = threshold;
+}
+
+pub fn forward_threshold() -> Option<ForwardThreshold> {
+    FORWARD_THRESHOLD.read().unwrap().clone()
+}
+
+impl ForwardThreshold {
+    pub fn exceeds(&self, content: &str) -> bool {
+        content.lines().count() > self.max_lines || content.chars().count() > self.max_chars
+    }
+
+    /// 纯文字消息按行拆分为多条, 其他消息整条放入合并转发
+    pub fn forward_nodes(&self, message: MessageChain, bot_uin: i64) -> Vec<ForwardMessage> {
+        let sender_name = if self.sender_name.is_empty() {
+            bot_uin.to_string()
+        } else {
+            self.sender_name.clone()
+        };
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i32;
+        let node = |elements: MessageChain| -> ForwardMessage {
+            MessageNode {
+                sender_id: bot_uin,
+                time,
+                sender_name: sender_name.clone(),
+                elements,
+            }
+            .into()
+        };
+        let mut text = String::new();
+        for elem in message.clone().into_iter() {
+            match elem {
+                RQElem::Text(t) => text.push_str(&t.content),
+                _ => return vec![node(message)],
+            }
+        }
+        self.split_text(&text)
+            .into_iter()
+            .map(|chunk| {
+                let mut chain = MessageChain::default();
+                chain.push(Text::new(chunk));
+                node(chain)
+            })
+            .collect()
+    }
+
+    fn split_text(&self, text: &str) -> Vec<String> {
+        let node_lines = self.node_lines.max(1);
+        let mut chunks = vec![];
+        let mut chunk = String::new();
+        let mut lines = 0;
+        for line in text.lines() {
+            let overflow = !chunk.is_empty()
+                && (lines >= node_lines
+                    || chunk.chars().count() + line.chars().count() > self.max_chars);
+            if overflow {
+                chunks.push(std::mem::take(&mut chunk));
+                lines = 0;
+            }
+            if !chunk.is_empty() {
+                chunk.push('\n');
+            }
+            chunk.push_str(line);
+            lines += 1;
+        }
+        if !chunk.is_empty() {
+            chunks.push(chunk);
+        }
+        chunks
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{group_profile, Language};
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// 框架发出的面向用户的文字, 可替换为其他语言或自定义措辞
+pub trait FrameworkMessages: Send + Sync {
+    fn only_in_group(&self) -> String;
+    fn permission_denied(&self) -> String;
+    fn bot_permission_denied(&self) -> String;
+    fn rate_limited(&self, retry_after: Duration) -> String;
+    fn handler_error(&self) -> String;
+    fn unsupported(&self, operation: &str) -> String;
+}
+
+pub struct ChineseMessages;
+
+impl FrameworkMessages for ChineseMessages {
+    fn only_in_group(&self) -> String {
+        "只能在群中使用".to_owned()
+    }
+
+    fn permission_denied(&self) -> String {
+        "您必须是群主或管理员才能使用".to_owned()
+    }
+
+    fn bot_permission_denied(&self) -> String {
+        "机器人必须是群主或管理员才能使用".to_owned()
+    }
+
+    fn rate_limited(&self, retry_after: Duration) -> String {
+        format!("操作太频繁了, 请{}秒后再试", retry_after.as_secs().max(1))
+    }
+
+    fn handler_error(&self) -> String {
+        "处理出错了, 请稍后再试".to_owned()
+    }
+
+    fn unsupported(&self, operation: &str) -> String {
+        format!("不支持的操作 : {}", operation)
+    }
+}
+
+pub struct EnglishMessages;
+
+impl FrameworkMessages for EnglishMessages {
+    fn only_in_group(&self) -> String {
+        "This command can only be used in groups".to_owned()
+    }
+
+    fn permission_denied(&self) -> String {
+        "You must be the group owner or an admin to use this".to_owned()
+    }
+
+    fn bot_permission_denied(&self) -> String {
+        "The bot must be the group owner or an admin to do this".to_owned()
+    }
+
+    fn rate_limited(&self, retry_after: Duration) -> String {
+        format!(
+            "Too many requests, please retry in {} seconds",
+            retry_after.as_secs().max(1)
+        )
+    }
+
+    fn handler_error(&self) -> String {
+        "Something went wrong, please try again later".to_owned()
+    }
+
+    fn unsupported(&self, operation: &str) -> String {
+        format!("Unsupported operation : {}", operation)
+    }
+}
+
+lazy_static! {
+    static ref FRAMEWORK_MESSAGES: RwLock<Arc<dyn FrameworkMessages>> =
+        RwLock::new(Arc::new(ChineseMessages));
+    static ref LANGUAGE_MESSAGES: RwLock<HashMap<Language, Arc<dyn FrameworkMessages>>> = {
+        let mut map: HashMap<Language, Arc<dyn FrameworkMessages>> = HashMap::new();
+        map.insert(Language::Chinese, Arc::new(ChineseMessages));
+        map.insert(Language::English, Arc::new(EnglishMessages));
+        RwLock::new(map)
+    };
+}
+
+/// 当前使用的框架文字, 默认为中文
+pub fn framework_messages() -> Arc<dyn FrameworkMessages> {
+    FRAMEWORK_MESSAGES.read().unwrap().clone()
+}
+
+pub fn set_framework_messages<M: FrameworkMessages + 'static>(messages: M) {
+    *FRAMEWORK_MESSAGES.write().unwrap() = Arc::new(messages);
+}
+
+/// 注册某种语言的框架文字, 供设置了该语言的群使用
+pub fn set_language_messages<M: FrameworkMessages + 'static>(language: Language, messages: M) {
+    LANGUAGE_MESSAGES
+        .write()
+        .unwrap()
+        .insert(language, Arc::new(messages));
+}
+
+/// 按照群设置的语言取框架文字, 该语言未注册时使用默认
+pub fn group_framework_messages(group_code: i64) -> Arc<dyn FrameworkMessages> {
+    let language = group_profile(group_code).language;
+    match LANGUAGE_MESSAGES.read().unwrap().get(&language) {
+        Some(messages) => messages.clone(),
+        None => framework_messages(),
+    }
+}
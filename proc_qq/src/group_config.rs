@@ -0,0 +1,100 @@
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Language {
+    Chinese,
+    English,
+    Custom(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Formality {
+    Casual,
+    Formal,
+}
+
+/// 群的语言/时区/语气设置, 框架文字和模版会根据它自动选择
+#[derive(Debug, Clone)]
+pub struct GroupProfile {
+    pub language: Language,
+    // 相对UTC的偏移 (分钟), 默认为东八区
+    pub utc_offset_minutes: i32,
+    pub formality: Formality,
+}
+
+impl Default for GroupProfile {
+    fn default() -> Self {
+        Self {
+            language: Language::Chinese,
+            utc_offset_minutes: 8 * 60,
+            formality: Formality::Casual,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GroupConfig {
+    pub profile: GroupProfile,
+}
+
+lazy_static! {
+    static ref GROUP_CONFIGS: RwLock<HashMap<i64, GroupConfig>> = RwLock::new(HashMap::new());
+}
+
+pub fn group_config(group_code: i64) -> Option<GroupConfig> {
+    GROUP_CONFIGS.read().unwrap().get(&group_code).cloned()
+}
+
+pub fn set_group_config(group_code: i64, config: GroupConfig) {
+    GROUP_CONFIGS.write().unwrap().insert(group_code, config);
+}
+
+/// 修改群设置, 不存在时从默认值开始修改
+pub fn update_group_config<F: FnOnce(&mut GroupConfig)>(group_code: i64, f: F) {
+    f(GROUP_CONFIGS
+        .write()
+        .unwrap()
+        .entry(group_code)
+        .or_default());
+}
+
+/// 群的设置, 未设置时为默认值
+pub fn group_profile(group_code: i64) -> GroupProfile {
+    group_config(group_code)
+        .map(|config| config.profile)
+        .unwrap_or_default()
+}
+
+/// 按群设置的时区计算的日期 (1970-01-01 为 0)
+pub fn group_today(group_code: i64) -> i64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    let utc_offset = group_profile(group_code).utc_offset_minutes as i64 * 60;
+    (now + utc_offset).div_euclid(86400)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn today_follows_group_offset() {
+        update_group_config(7_000_648, |config| {
+            config.profile.utc_offset_minutes = 14 * 60
+        });
+        update_group_config(7_000_649, |config| {
+            config.profile.utc_offset_minutes = -12 * 60
+        });
+        assert_eq!(group_profile(7_000_648).formality, Formality::Casual);
+        let ahead = group_today(7_000_648);
+        let behind = group_today(7_000_649);
+        // 两个时区相差26小时, 日期相差1或2天
+        assert!((1..=2).contains(&(ahead - behind)));
+        assert_eq!(group_profile(7_000_650).utc_offset_minutes, 8 * 60);
+    }
+}
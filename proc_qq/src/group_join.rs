@@ -0,0 +1,50 @@
+use lazy_static::lazy_static;
+use std::collections::HashSet;
+use std::sync::RwLock;
+
+use crate::{admin_targets, MessageTarget};
+
+/// 机器人被邀请入群时, 自动同意这些人的邀请, 用于自动部署新的群
+#[derive(Debug, Clone, Default)]
+pub struct InvitePolicy {
+    pub inviters: HashSet<i64>,
+    /// 同时同意管理员 (set_admin_targets 中的QQ号) 的邀请
+    pub admins: bool,
+}
+
+impl InvitePolicy {
+    pub fn new<I: IntoIterator<Item = i64>>(inviters: I) -> Self {
+        Self {
+            inviters: inviters.into_iter().collect(),
+            admins: false,
+        }
+    }
+
+    /// 只同意管理员的邀请
+    pub fn admins() -> Self {
+        Self {
+            inviters: HashSet::new(),
+            admins: true,
+        }
+    }
+
+    pub fn allows(&self, inviter_uin: i64) -> bool {
+        self.inviters.contains(&inviter_uin)
+            || (self.admins && admin_targets().contains(&MessageTarget::Private(inviter_uin)))
+    }
+}
+
+lazy_static! {
+    static ref INVITE_POLICY: RwLock<Option<InvitePolicy>> = RwLock::new(None);
+}
+
+/// 设置自动同意入群邀请的策略, 为None时不自动同意 (默认)
+///
+/// 模块处理了SelfInvitedEvent (处理器返回true或出错) 时不会再自动同意
+pub fn set_invite_policy(policy: Option<InvitePolicy>) {
+    *INVITE_POLICY.write().unwrap() = policy;
+}
+
+pub fn invite_policy() -> Option<InvitePolicy> {
+    INVITE_POLICY.read().unwrap().clone()
+}
@@ -0,0 +1,122 @@
+use rq_engine::msg::MessageChain;
+use serde_derive::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+use super::filters::EventInfo;
+use crate::MessageContent;
+
+tokio::task_local! {
+    static CURRENT_ACTOR: Actor;
+    static MESSAGE_CONTENT: (ContentKey, Mutex<Option<Arc<str>>>);
+}
+
+// 发送者和消息的序号, 用于确认缓存的内容属于同一条消息
+pub(crate) type ContentKey = (i64, i32, i32);
+
+/// 正在执行的处理器, 以及触发它的事件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Actor {
+    pub module_id: String,
+    pub handler: String,
+    pub event: String,
+    pub group_code: Option<i64>,
+    pub from_uin: Option<i64>,
+    /// 模块在试运行 (set_dry_run), 发送的消息只记录不发出
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// 当前正在执行的处理器所属的模块id, 不在处理器中时返回None
+pub fn current_module_id() -> Option<String> {
+    CURRENT_ACTOR.try_with(|actor| actor.module_id.clone()).ok()
+}
+
+/// 当前正在执行的处理器, 不在处理器中时返回None
+pub fn current_actor() -> Option<Actor> {
+    CURRENT_ACTOR.try_with(|actor| actor.clone()).ok()
+}
+
+pub(crate) async fn in_handler<F: std::future::Future>(
+    module_id: &str,
+    handler: &str,
+    event: &str,
+    info: &EventInfo,
+    fut: F,
+) -> F::Output {
+    let actor = Actor {
+        module_id: module_id.to_owned(),
+        handler: handler.to_owned(),
+        event: event.to_owned(),
+        group_code: info.group_code,
+        from_uin: info.from_uin,
+        dry_run: crate::module_dry_run(module_id),
+    };
+    with_actor(actor, fut).await
+}
+
+pub(crate) async fn with_actor<F: std::future::Future>(actor: Actor, fut: F) -> F::Output {
+    CURRENT_ACTOR.scope(actor, fut).await
+}
+
+/// 启动后台任务, 在处理器中调用时任务仍然属于这个处理器 (current_actor, 试运行, 审计日志)
+///
+/// 直接使用tokio::spawn的任务不会继承当前的处理器
+pub fn spawn_with_actor<F>(fut: F) -> JoinHandle<F::Output>
+where
+    F: std::future::Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    match current_actor() {
+        Some(actor) => tokio::spawn(CURRENT_ACTOR.scope(actor, fut)),
+        None => tokio::spawn(fut),
+    }
+}
+
+pub(crate) fn content_key(from_uin: i64, seqs: &[i32], rands: &[i32]) -> ContentKey {
+    (
+        from_uin,
+        seqs.first().copied().unwrap_or_default(),
+        rands.first().copied().unwrap_or_default(),
+    )
+}
+
+/// 分发一条消息时最多转换一次文字 (第一次用到时), 所有过滤器和处理器共用
+pub(crate) async fn with_message_content<F: std::future::Future>(
+    key: ContentKey,
+    fut: F,
+) -> F::Output {
+    MESSAGE_CONTENT.scope((key, Mutex::new(None)), fut).await
+}
+
+pub(crate) fn dispatch_content(key: ContentKey, elements: &MessageChain) -> MessageContent {
+    MESSAGE_CONTENT
+        .try_with(|(cached_key, content)| {
+            if *cached_key != key {
+                return None;
+            }
+            let mut content = content.lock().unwrap();
+            let content = content.get_or_insert_with(|| Arc::from(elements.to_string()));
+            Some(MessageContent::from(content.clone()))
+        })
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| MessageContent::from(elements.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn dispatch_content_is_rendered_once() {
+        let elements = MessageChain::default();
+        let key = (1, 2, 3);
+        with_message_content(key, async {
+            let first = dispatch_content(key, &elements);
+            let second = dispatch_content(key, &elements);
+            assert_eq!(first.as_ptr(), second.as_ptr());
+        })
+        .await;
+    }
+}
@@ -0,0 +1,220 @@
+use futures::Stream;
+use rs_qq::handler::QEvent;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::sync::{broadcast, mpsc};
+
+// 消费者跟不上时最多缓存的事件数量, 超过后最旧的事件被丢弃
+const EVENT_STREAM_CAPACITY: usize = 1024;
+
+/// 每个 EventSink 在转发任务中排队的事件数量上限, 超过后丢弃新的事件
+pub const EVENT_SINK_BUFFER: usize = 256;
+
+/// event_stream 中的事件, 和分发给模块的是同一个事件
+#[derive(Clone, Debug)]
+pub struct OwnedEvent {
+    pub event: QEvent,
+    pub received_at: SystemTime,
+}
+
+/// channel已满时如何处理新的事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SinkOverflow {
+    /// 丢弃新的事件
+    DropNew,
+    /// 等待channel有空位, 期间的事件在这个接收端的转发队列 (EVENT_SINK_BUFFER) 中等待
+    Block,
+    /// 最多等待一段时间, 超时后丢弃
+    Timeout(Duration),
+}
+
+type SinkFilter = Box<dyn Fn(&QEvent) -> bool + Send + Sync>;
+
+/// 把事件转发到应用提供的channel, 接收端被释放后自动移除
+pub struct EventSink {
+    name: String,
+    sender: mpsc::Sender<OwnedEvent>,
+    filter: Option<SinkFilter>,
+    overflow: SinkOverflow,
+}
+
+impl EventSink {
+    /// 默认转发所有事件, channel已满时丢弃新的事件
+    pub fn new<S: Into<String>>(name: S, sender: mpsc::Sender<OwnedEvent>) -> Self {
+        Self {
+            name: name.into(),
+            sender,
+            filter: None,
+            overflow: SinkOverflow::DropNew,
+        }
+    }
+
+    /// 只转发filter返回true的事件
+    pub fn filter<F: Fn(&QEvent) -> bool + Send + Sync + 'static>(mut self, filter: F) -> Self {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    pub fn overflow(mut self, overflow: SinkOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    // 每个接收端一个转发任务, 慢的接收端只会让自己的队列积压, 不影响事件分发和其他接收端
+    async fn forward(self, mut queue: mpsc::Receiver<OwnedEvent>) {
+        while let Some(event) = queue.recv().await {
+            if self.sender.is_closed() {
+                break;
+            }
+            self.deliver(event).await;
+        }
+    }
+
+    async fn deliver(&self, event: OwnedEvent) {
+        if let Some(filter) = &self.filter {
+            if !filter(&event.event) {
+                return;
+            }
+        }
+        let delivered = match self.overflow {
+            SinkOverflow::DropNew => self.sender.try_send(event).is_ok(),
+            SinkOverflow::Block => self.sender.send(event).await.is_ok(),
+            SinkOverflow::Timeout(timeout) => {
+                self.sender.send_timeout(event, timeout).await.is_ok()
+            }
+        };
+        if !delivered && !self.sender.is_closed() {
+            tracing::debug!(
+                target = "proc_qq",
+                "事件接收端 {} 已满, 丢弃事件",
+                self.name
+            );
+        }
+    }
+}
+
+// 转发任务的队列
+struct SinkQueue {
+    name: String,
+    sender: mpsc::Sender<OwnedEvent>,
+}
+
+/// 把收到的所有事件广播给 event_stream 的订阅者和注册的 EventSink
+#[derive(Clone)]
+pub(crate) struct EventBroadcast {
+    sender: broadcast::Sender<OwnedEvent>,
+    sinks: Arc<RwLock<Vec<SinkQueue>>>,
+}
+
+impl Default for EventBroadcast {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(EVENT_STREAM_CAPACITY).0,
+            sinks: Default::default(),
+        }
+    }
+}
+
+impl EventBroadcast {
+    /// 没有订阅者时不复制事件, 不会等待接收端
+    pub(crate) fn publish(&self, e: &QEvent) {
+        let mut closed = false;
+        {
+            let sinks = self.sinks.read().unwrap();
+            if self.sender.receiver_count() == 0 && sinks.is_empty() {
+                return;
+            }
+            let event = OwnedEvent {
+                event: e.clone(),
+                received_at: SystemTime::now(),
+            };
+            for sink in sinks.iter() {
+                match sink.sender.try_send(event.clone()) {
+                    Ok(_) => {}
+                    Err(mpsc::error::TrySendError::Full(_)) => tracing::debug!(
+                        target = "proc_qq",
+                        "事件接收端 {} 的转发队列已满, 丢弃事件",
+                        sink.name
+                    ),
+                    Err(mpsc::error::TrySendError::Closed(_)) => closed = true,
+                }
+            }
+            let _ = self.sender.send(event);
+        }
+        if closed {
+            self.sinks
+                .write()
+                .unwrap()
+                .retain(|sink| !sink.sender.is_closed());
+        }
+    }
+
+    /// 需要在tokio运行时中调用
+    pub(crate) fn add_sink(&self, sink: EventSink) {
+        let (sender, queue) = mpsc::channel(EVENT_SINK_BUFFER);
+        let name = sink.name.clone();
+        tokio::spawn(sink.forward(queue));
+        self.sinks.write().unwrap().push(SinkQueue { name, sender });
+    }
+
+    pub(crate) fn subscribe(&self) -> impl Stream<Item = OwnedEvent> {
+        futures::stream::unfold(self.sender.subscribe(), |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => return Some((event, receiver)),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!(
+                            target = "proc_qq",
+                            "event_stream 的消费者处理太慢, 丢弃了 {} 个事件",
+                            skipped
+                        );
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn slow_sink_does_not_block_others() {
+        let events = EventBroadcast::default();
+        // 从不读取的接收端
+        let (slow, _slow_receiver) = mpsc::channel(1);
+        events.add_sink(EventSink::new("slow", slow).overflow(SinkOverflow::Block));
+        let (fast, mut fast_receiver) = mpsc::channel(16);
+        events.add_sink(EventSink::new("fast", fast));
+        for _ in 0..10 {
+            events.publish(&QEvent::TcpConnect);
+        }
+        for _ in 0..10 {
+            let event = tokio::time::timeout(Duration::from_secs(1), fast_receiver.recv())
+                .await
+                .unwrap();
+            assert!(matches!(event.unwrap().event, QEvent::TcpConnect));
+        }
+    }
+
+    #[tokio::test]
+    async fn filter_and_removal() {
+        let events = EventBroadcast::default();
+        let (sender, mut receiver) = mpsc::channel(16);
+        events.add_sink(EventSink::new("logins", sender).filter(|e| matches!(e, QEvent::Login(_))));
+        events.publish(&QEvent::TcpConnect);
+        events.publish(&QEvent::Login(1));
+        let event = tokio::time::timeout(Duration::from_secs(1), receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(matches!(event.event, QEvent::Login(1)));
+        drop(receiver);
+        events.publish(&QEvent::TcpConnect);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        events.publish(&QEvent::TcpConnect);
+        assert!(events.sinks.read().unwrap().is_empty());
+    }
+}
@@ -1,4 +1,5 @@
 use rq_engine::msg::MessageChain;
+use rq_engine::structs::{FriendInfo, GroupMemberPermission};
 use rq_engine::{RQError, RQResult};
 pub use rs_qq::client::event::{
     DeleteFriendEvent, FriendMessageEvent, FriendMessageRecallEvent, FriendPokeEvent,
@@ -12,6 +13,7 @@ pub struct LoginEvent {
     pub uin: i64,
 }
 
+#[derive(Clone)]
 pub enum MessageEvent {
     GroupMessage(GroupMessageEvent),
     FriendMessage(FriendMessageEvent),
@@ -26,6 +28,9 @@ impl MessageEvent {
             MessageEvent::TempMessage(e) => e.client.clone(),
         }
     }
+    pub fn proc_client(&self) -> crate::ProcClient {
+        crate::ProcClient(self.client())
+    }
     pub fn is_group_message(&self) -> bool {
         match self {
             MessageEvent::GroupMessage(_) => true,
@@ -79,6 +84,82 @@ impl MessageEvent {
     }
 }
 
-pub struct ConnectedAndOnlineEvent {}
+pub struct ConnectedAndOnlineEvent {
+    pub client: Arc<rs_qq::Client>,
+}
 
 pub struct DisconnectedAndOfflineEvent {}
+
+/// 同步好友列表时发现的新好友
+pub struct FriendAddedEvent {
+    pub client: Arc<rs_qq::Client>,
+    pub friend: FriendInfo,
+}
+
+/// 同步好友列表时发现已不是好友, friend为最后一次的快照
+pub struct FriendRemovedEvent {
+    pub client: Arc<rs_qq::Client>,
+    pub friend: FriendInfo,
+}
+
+/// 好友的昵称或备注发生变化
+pub struct FriendUpdatedEvent {
+    pub client: Arc<rs_qq::Client>,
+    pub old: FriendInfo,
+    pub new: FriendInfo,
+}
+
+/// 机器人加入了群, operator_uin为邀请人或审核人 (已知时)
+pub struct BotJoinedGroupEvent {
+    pub client: Arc<rs_qq::Client>,
+    pub group_code: i64,
+    pub group_name: String,
+    pub operator_uin: Option<i64>,
+}
+
+/// 机器人退出, 被移出群, 或群被解散, operator_uin为操作人 (已知时)
+pub struct BotRemovedFromGroupEvent {
+    pub client: Arc<rs_qq::Client>,
+    pub group_code: i64,
+    pub group_name: String,
+    pub operator_uin: Option<i64>,
+}
+
+/// 好友上线或下线
+///
+/// rs-qq 目前不会上报好友状态, 可以通过 Client::report_friend_presence 上报
+pub struct FriendPresenceChangedEvent {
+    pub client: Arc<rs_qq::Client>,
+    pub uin: i64,
+    pub online: bool,
+}
+
+/// 机器人在群中被设置或取消管理员
+pub struct BotPermissionChangedEvent {
+    pub client: Arc<rs_qq::Client>,
+    pub group_code: i64,
+    pub permission: GroupMemberPermission,
+    /// 变化后机器人是否有管理权限 (管理员或群主)
+    pub is_admin: bool,
+}
+
+/// 机器人被拉入白名单之外的群, 按照自动退群策略退出
+///
+/// error为发送消息或退群失败的原因
+pub struct AutoLeftGroupEvent {
+    pub client: Arc<rs_qq::Client>,
+    pub group_code: i64,
+    pub group_name: String,
+    pub left: bool,
+    pub error: Option<String>,
+}
+
+/// 群成员的禁言到期, 或被提前解除 (early)
+///
+/// 根据GroupMuteEvent中的时长计算, 只有机器人在线时收到的禁言会被记录
+pub struct MuteExpiredEvent {
+    pub client: Arc<rs_qq::Client>,
+    pub group_code: i64,
+    pub member_uin: i64,
+    pub early: bool,
+}
@@ -0,0 +1,237 @@
+use crate::{group_profile, GroupProfile, SeededRng};
+use anyhow::Context;
+use lazy_static::lazy_static;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 分发时用于判断监听器是否执行的事件信息
+#[derive(Debug, Clone, Default)]
+pub struct EventInfo {
+    pub bot_uin: i64,
+    pub group_code: Option<i64>,
+    pub from_uin: Option<i64>,
+    /// 机器人自己发送的消息 (包括在其他设备上发送的)
+    pub own_message: bool,
+}
+
+/// 监听器过滤器, 全部通过时监听器才会执行
+pub trait HandlerFilter: Send + Sync {
+    fn check(&self, info: &EventInfo) -> bool;
+    fn describe(&self) -> String;
+}
+
+/// 只处理指定机器人账号收到的事件
+pub struct AccountsFilter {
+    accounts: Vec<i64>,
+}
+
+impl AccountsFilter {
+    pub fn new(accounts: Vec<i64>) -> Self {
+        Self { accounts }
+    }
+}
+
+impl HandlerFilter for AccountsFilter {
+    fn check(&self, info: &EventInfo) -> bool {
+        self.accounts.contains(&info.bot_uin)
+    }
+
+    fn describe(&self) -> String {
+        format!("accounts{:?}", self.accounts)
+    }
+}
+
+/// 只在每天的某个时间段内处理事件, 支持跨越零点 (如 22:00-06:00)
+pub struct ActiveHoursFilter {
+    start: u32,
+    end: u32,
+    // 相对UTC的偏移 (分钟), 为None时使用群设置的时区
+    utc_offset_minutes: Option<i32>,
+}
+
+impl ActiveHoursFilter {
+    pub fn new(start: u32, end: u32, utc_offset_minutes: Option<i32>) -> Self {
+        Self {
+            start,
+            end,
+            utc_offset_minutes,
+        }
+    }
+
+    /// 解析 "08:00-23:00" 以及时区 ("UTC" 或者 "+08:00" 形式的偏移), 格式错误时返回错误
+    ///
+    /// 有夏令时的地区没有固定的偏移, 不支持 "Europe/London" 这样的时区名称
+    pub fn parse(range: &str, timezone: Option<&str>) -> anyhow::Result<Self> {
+        let (start, end) = range
+            .split_once('-')
+            .with_context(|| format!("时间段格式错误 : {}", range))?;
+        let utc_offset_minutes = match timezone {
+            Some(timezone) => Some(
+                parse_utc_offset(timezone)
+                    .with_context(|| format!("不支持的时区 : {}", timezone))?,
+            ),
+            None => None,
+        };
+        Ok(Self::new(
+            parse_minute_of_day(start)?,
+            parse_minute_of_day(end)?,
+            utc_offset_minutes,
+        ))
+    }
+
+    fn is_active(&self, minute_of_day: u32) -> bool {
+        if self.start <= self.end {
+            self.start <= minute_of_day && minute_of_day < self.end
+        } else {
+            minute_of_day >= self.start || minute_of_day < self.end
+        }
+    }
+}
+
+impl HandlerFilter for ActiveHoursFilter {
+    fn check(&self, info: &EventInfo) -> bool {
+        let offset = match self.utc_offset_minutes {
+            Some(offset) => offset,
+            None => match info.group_code {
+                Some(group_code) => group_profile(group_code).utc_offset_minutes,
+                None => GroupProfile::default().utc_offset_minutes,
+            },
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let minute_of_day = (now / 60 + offset as i64).rem_euclid(24 * 60) as u32;
+        self.is_active(minute_of_day)
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "active_hours({:02}:{:02}-{:02}:{:02})",
+            self.start / 60,
+            self.start % 60,
+            self.end / 60,
+            self.end % 60
+        )
+    }
+}
+
+fn parse_minute_of_day(time: &str) -> anyhow::Result<u32> {
+    let (hour, minute) = time
+        .trim()
+        .split_once(':')
+        .with_context(|| format!("时间格式错误 : {}", time))?;
+    let hour: u32 = hour
+        .parse()
+        .with_context(|| format!("时间格式错误 : {}", time))?;
+    let minute: u32 = minute
+        .parse()
+        .with_context(|| format!("时间格式错误 : {}", time))?;
+    if hour > 24 || minute > 59 || (hour == 24 && minute != 0) {
+        return Err(anyhow::Error::msg(format!("时间超出范围 : {}", time)));
+    }
+    Ok(hour * 60 + minute)
+}
+
+/// UTC 或者 "+08:00" 形式的偏移, 和event宏中 #[active_hours] 的规则一致
+fn parse_utc_offset(timezone: &str) -> Option<i32> {
+    if timezone == "UTC" {
+        return Some(0);
+    }
+    let (sign, rest) = match timezone.chars().next()? {
+        '+' => (1, &timezone[1..]),
+        '-' => (-1, &timezone[1..]),
+        _ => return None,
+    };
+    let (hour, minute) = rest.split_once(':').unwrap_or((rest, "0"));
+    let hour: i32 = hour.parse().ok()?;
+    let minute: i32 = minute.parse().ok()?;
+    if hour > 14 || minute > 59 {
+        return None;
+    }
+    Some(sign * (hour * 60 + minute))
+}
+
+/// 功能开关打开时才处理事件
+pub struct FeatureFilter {
+    flag: String,
+}
+
+impl FeatureFilter {
+    pub fn new<S: Into<String>>(flag: S) -> Self {
+        Self { flag: flag.into() }
+    }
+}
+
+impl HandlerFilter for FeatureFilter {
+    fn check(&self, info: &EventInfo) -> bool {
+        crate::feature_enabled(&self.flag, info)
+    }
+
+    fn describe(&self) -> String {
+        format!("feature({})", self.flag)
+    }
+}
+
+lazy_static! {
+    static ref SAMPLE_RNG: Mutex<SeededRng> = Mutex::new(SeededRng::from_time());
+}
+
+/// 按照比例随机处理一部分事件, 用于只观察不回复的耗时处理器 (统计, 分析)
+pub struct SampleFilter {
+    rate: f64,
+}
+
+impl SampleFilter {
+    /// rate为 0.0 ~ 1.0
+    pub fn new(rate: f64) -> Self {
+        Self {
+            rate: rate.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl HandlerFilter for SampleFilter {
+    fn check(&self, _info: &EventInfo) -> bool {
+        self.rate >= 1.0 || SAMPLE_RNG.lock().unwrap().gen_bool(self.rate)
+    }
+
+    fn describe(&self) -> String {
+        format!("sample({})", self.rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn active_hours_parse() {
+        let filter = ActiveHoursFilter::parse("08:00-23:00", Some("+08:00")).unwrap();
+        assert_eq!(filter.describe(), "active_hours(08:00-23:00)");
+        assert_eq!(filter.utc_offset_minutes, Some(480));
+        assert!(ActiveHoursFilter::parse("08:00", None).is_err());
+        assert!(ActiveHoursFilter::parse("08:00-25:00", None).is_err());
+        assert!(ActiveHoursFilter::parse("08:00-23:00", Some("Asia/Shanghai")).is_err());
+    }
+
+    #[test]
+    fn active_hours_across_midnight() {
+        let filter = ActiveHoursFilter::new(22 * 60, 6 * 60, Some(0));
+        assert!(filter.is_active(23 * 60));
+        assert!(filter.is_active(0));
+        assert!(!filter.is_active(6 * 60));
+        assert!(!filter.is_active(12 * 60));
+        let filter = ActiveHoursFilter::new(8 * 60, 23 * 60, Some(0));
+        assert!(filter.is_active(8 * 60));
+        assert!(!filter.is_active(23 * 60));
+    }
+
+    #[test]
+    fn utc_offsets() {
+        assert_eq!(parse_utc_offset("UTC"), Some(0));
+        assert_eq!(parse_utc_offset("-03:30"), Some(-210));
+        assert_eq!(parse_utc_offset("+15:00"), None);
+        assert_eq!(parse_utc_offset("08:00"), None);
+    }
+}
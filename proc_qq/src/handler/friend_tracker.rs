@@ -0,0 +1,57 @@
+use lazy_static::lazy_static;
+use rq_engine::structs::FriendInfo;
+use rq_engine::RQResult;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::ClientTrait;
+
+pub(crate) enum FriendChange {
+    Added(FriendInfo),
+    Removed(FriendInfo),
+    Updated { old: FriendInfo, new: FriendInfo },
+}
+
+lazy_static! {
+    // bot_uin -> (uin -> 好友快照)
+    static ref FRIEND_SNAPSHOTS: Mutex<HashMap<i64, HashMap<i64, FriendInfo>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// 重新加载好友列表并与上次的快照比较, 第一次同步只记录快照
+pub(crate) async fn sync_friend_list(client: &Arc<rs_qq::Client>) -> RQResult<Vec<FriendChange>> {
+    let bot_uin = client.bot_uin().await;
+    let current: HashMap<i64, FriendInfo> = client
+        .friend_list(true)
+        .await?
+        .into_iter()
+        .map(|friend| (friend.uin, friend.as_ref().clone()))
+        .collect();
+    let previous = match FRIEND_SNAPSHOTS
+        .lock()
+        .unwrap()
+        .insert(bot_uin, current.clone())
+    {
+        Some(previous) => previous,
+        None => return Ok(vec![]),
+    };
+    let mut changes = vec![];
+    for (uin, friend) in &current {
+        match previous.get(uin) {
+            None => changes.push(FriendChange::Added(friend.clone())),
+            Some(old) if old.nick != friend.nick || old.remark != friend.remark => {
+                changes.push(FriendChange::Updated {
+                    old: old.clone(),
+                    new: friend.clone(),
+                })
+            }
+            _ => (),
+        }
+    }
+    for (uin, friend) in previous {
+        if !current.contains_key(&uin) {
+            changes.push(FriendChange::Removed(friend));
+        }
+    }
+    Ok(changes)
+}
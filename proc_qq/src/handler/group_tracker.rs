@@ -0,0 +1,63 @@
+use lazy_static::lazy_static;
+use rq_engine::RQResult;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+pub(crate) enum GroupChange {
+    Joined { group_code: i64, group_name: String },
+    Removed { group_code: i64, group_name: String },
+}
+
+lazy_static! {
+    // bot_uin -> (group_code -> 群名称)
+    static ref GROUP_SNAPSHOTS: Mutex<HashMap<i64, HashMap<i64, String>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// 重新加载群列表并与上次的快照比较, 第一次同步只记录快照
+pub(crate) async fn sync_group_list(client: &Arc<rs_qq::Client>) -> RQResult<Vec<GroupChange>> {
+    let bot_uin = client.uin().await;
+    client.reload_groups().await?;
+    let current: HashMap<i64, String> = client
+        .groups
+        .read()
+        .await
+        .iter()
+        .map(|(code, group)| (*code, group.info.name.clone()))
+        .collect();
+    let previous = match GROUP_SNAPSHOTS
+        .lock()
+        .unwrap()
+        .insert(bot_uin, current.clone())
+    {
+        Some(previous) => previous,
+        None => return Ok(vec![]),
+    };
+    let mut changes = vec![];
+    for (group_code, group_name) in &current {
+        if !previous.contains_key(group_code) {
+            changes.push(GroupChange::Joined {
+                group_code: *group_code,
+                group_name: group_name.clone(),
+            });
+        }
+    }
+    for (group_code, group_name) in previous {
+        if !current.contains_key(&group_code) {
+            changes.push(GroupChange::Removed {
+                group_code,
+                group_name,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+/// 收到机器人退群的协议事件时从快照中移除, 避免下次同步重复报告, 返回群名称
+pub(crate) fn forget_group(bot_uin: i64, group_code: i64) -> Option<String> {
+    GROUP_SNAPSHOTS
+        .lock()
+        .unwrap()
+        .get_mut(&bot_uin)?
+        .remove(&group_code)
+}
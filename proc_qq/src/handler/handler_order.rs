@@ -0,0 +1,173 @@
+use crate::{Module, ModuleEventHandler};
+use std::collections::BTreeSet;
+
+/// 按照处理器声明的 after / before 排序模块中的处理器
+///
+/// 没有约束的处理器保持声明的顺序, 引用不存在的处理器或者出现循环时返回错误
+pub(crate) fn order_handlers(module: &mut Module) -> Result<(), String> {
+    let order = resolve_order(module)?;
+    if order.iter().enumerate().all(|(i, index)| i == *index) {
+        return Ok(());
+    }
+    let mut handles: Vec<Option<ModuleEventHandler>> = std::mem::take(&mut module.handles)
+        .into_iter()
+        .map(Some)
+        .collect();
+    module.handles = order
+        .into_iter()
+        .map(|index| handles[index].take().unwrap())
+        .collect();
+    Ok(())
+}
+
+/// 不能重新排序时 (模块列表被共享), 只检查当前的顺序是否满足约束
+pub(crate) fn check_handler_order(module: &Module) -> Result<(), String> {
+    resolve_order(module)?;
+    for (a, b) in edges(module)? {
+        if a > b {
+            return Err(format!(
+                "模块 {} 的处理器 {} 需要在 {} 之前, 但模块列表被共享, 不能重新排序",
+                module.id, module.handles[a].name, module.handles[b].name
+            ));
+        }
+    }
+    Ok(())
+}
+
+// (先, 后)
+fn edges(module: &Module) -> Result<Vec<(usize, usize)>, String> {
+    let find = |from: &str, name: &str| {
+        let found: Vec<usize> = module
+            .handles
+            .iter()
+            .enumerate()
+            .filter(|(_, h)| h.name == name)
+            .map(|(i, _)| i)
+            .collect();
+        if found.is_empty() {
+            Err(format!(
+                "模块 {} 的处理器 {} 引用了不存在的处理器 {}",
+                module.id, from, name
+            ))
+        } else {
+            Ok(found)
+        }
+    };
+    let mut edges = vec![];
+    for (index, handle) in module.handles.iter().enumerate() {
+        for name in &handle.after {
+            for other in find(&handle.name, name)? {
+                edges.push((other, index));
+            }
+        }
+        for name in &handle.before {
+            for other in find(&handle.name, name)? {
+                edges.push((index, other));
+            }
+        }
+    }
+    Ok(edges)
+}
+
+fn resolve_order(module: &Module) -> Result<Vec<usize>, String> {
+    let count = module.handles.len();
+    let edges = edges(module)?;
+    let mut incoming = vec![0; count];
+    for (_, b) in &edges {
+        incoming[*b] += 1;
+    }
+    // 每次取声明顺序最靠前的, 结果是确定的
+    let mut ready: BTreeSet<usize> = (0..count).filter(|i| incoming[*i] == 0).collect();
+    let mut order = Vec::with_capacity(count);
+    while let Some(index) = ready.pop_first() {
+        order.push(index);
+        for (a, b) in &edges {
+            if *a == index {
+                incoming[*b] -= 1;
+                if incoming[*b] == 0 {
+                    ready.insert(*b);
+                }
+            }
+        }
+    }
+    if order.len() < count {
+        let cycle: Vec<&str> = (0..count)
+            .filter(|i| incoming[*i] > 0)
+            .map(|i| module.handles[i].name.as_str())
+            .collect();
+        return Err(format!(
+            "模块 {} 的处理器顺序有循环 : {}",
+            module.id,
+            cycle.join(", ")
+        ));
+    }
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GroupMessageEventProcess, ModuleEventProcess};
+    use async_trait::async_trait;
+    use rs_qq::client::event::GroupMessageEvent;
+
+    struct Noop;
+
+    #[async_trait]
+    impl GroupMessageEventProcess for Noop {
+        async fn handle(&self, _: &GroupMessageEvent) -> anyhow::Result<bool> {
+            Ok(false)
+        }
+    }
+
+    // (名称, after, before)
+    fn module(handles: &[(&str, &[&str], &[&str])]) -> Module {
+        Module {
+            id: "test".to_owned(),
+            name: "test".to_owned(),
+            handles: handles
+                .iter()
+                .map(|(name, after, before)| {
+                    let mut handle = ModuleEventHandler::new(
+                        *name,
+                        ModuleEventProcess::GroupMessage(Box::new(Noop)),
+                    );
+                    handle.after = after.iter().map(|s| s.to_string()).collect();
+                    handle.before = before.iter().map(|s| s.to_string()).collect();
+                    handle
+                })
+                .collect(),
+        }
+    }
+
+    fn names(module: &Module) -> Vec<&str> {
+        module.handles.iter().map(|h| h.name.as_str()).collect()
+    }
+
+    #[test]
+    fn keeps_declared_order_without_constraints() {
+        let mut m = module(&[("a", &[], &[]), ("b", &[], &[]), ("c", &[], &[])]);
+        order_handlers(&mut m).unwrap();
+        assert_eq!(names(&m), vec!["a", "b", "c"]);
+        assert!(check_handler_order(&m).is_ok());
+    }
+
+    #[test]
+    fn orders_by_after_and_before() {
+        let mut m = module(&[("a", &["c"], &[]), ("b", &[], &[]), ("c", &[], &["b"])]);
+        assert!(check_handler_order(&m).is_err());
+        order_handlers(&mut m).unwrap();
+        assert_eq!(names(&m), vec!["c", "a", "b"]);
+        assert!(check_handler_order(&m).is_ok());
+    }
+
+    #[test]
+    fn rejects_missing_and_cycles() {
+        let mut m = module(&[("a", &["missing"], &[])]);
+        assert!(order_handlers(&mut m).unwrap_err().contains("missing"));
+        let mut m = module(&[("a", &["b"], &[]), ("b", &["a"], &[]), ("c", &[], &[])]);
+        let err = order_handlers(&mut m).unwrap_err();
+        assert!(err.contains("a, b"));
+        assert_eq!(names(&m), vec!["a", "b", "c"]);
+    }
+}
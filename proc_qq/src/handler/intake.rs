@@ -0,0 +1,128 @@
+use rs_qq::handler::QEvent;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tokio::sync::Notify;
+
+/// 队列满时如何处理新到达的事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// 丢弃队列中最早的事件
+    DropOldest,
+    /// 丢弃新到达的事件
+    DropNew,
+    /// 等待队列有空位, 会阻塞rs-qq的事件回调
+    Block,
+}
+
+/// 事件接收队列, rs-qq收到的事件先进入队列, 再由固定数量的worker取出分发
+#[derive(Debug, Clone)]
+pub struct IntakeConfig {
+    // 队列最多缓存的事件数量
+    pub capacity: usize,
+    // 同时处理的事件数量
+    pub workers: usize,
+    pub overflow: OverflowPolicy,
+}
+
+impl Default for IntakeConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            workers: 64,
+            overflow: OverflowPolicy::DropOldest,
+        }
+    }
+}
+
+static QUEUE_DEPTH: AtomicUsize = AtomicUsize::new(0);
+static DROPPED_EVENTS: AtomicU64 = AtomicU64::new(0);
+
+/// 接收队列中等待处理的事件数量
+pub fn intake_queue_depth() -> usize {
+    QUEUE_DEPTH.load(Ordering::Relaxed)
+}
+
+/// 因为队列已满被丢弃的事件总数
+pub fn intake_dropped_events() -> u64 {
+    DROPPED_EVENTS.load(Ordering::Relaxed)
+}
+
+pub(crate) struct IntakeQueue {
+    pub(crate) config: IntakeConfig,
+    queue: Mutex<VecDeque<QEvent>>,
+    readable: Notify,
+    writable: Notify,
+    started: AtomicBool,
+}
+
+impl IntakeQueue {
+    pub(crate) fn new(config: IntakeConfig) -> Self {
+        Self {
+            config,
+            queue: Mutex::new(VecDeque::new()),
+            readable: Notify::new(),
+            writable: Notify::new(),
+            started: AtomicBool::new(false),
+        }
+    }
+
+    /// 第一次调用返回true, 用于启动消费者
+    pub(crate) fn start(&self) -> bool {
+        !self.started.swap(true, Ordering::SeqCst)
+    }
+
+    pub(crate) async fn push(&self, event: QEvent) {
+        let capacity = self.config.capacity.max(1);
+        loop {
+            {
+                let mut queue = self.queue.lock().unwrap();
+                if queue.len() < capacity {
+                    queue.push_back(event);
+                    QUEUE_DEPTH.store(queue.len(), Ordering::Relaxed);
+                    drop(queue);
+                    self.readable.notify_one();
+                    return;
+                }
+                match self.config.overflow {
+                    OverflowPolicy::DropOldest => {
+                        queue.pop_front();
+                        queue.push_back(event);
+                        dropped_event();
+                        return;
+                    }
+                    OverflowPolicy::DropNew => {
+                        dropped_event();
+                        return;
+                    }
+                    OverflowPolicy::Block => (),
+                }
+            }
+            self.writable.notified().await;
+        }
+    }
+
+    pub(crate) async fn pop(&self) -> QEvent {
+        loop {
+            {
+                let mut queue = self.queue.lock().unwrap();
+                if let Some(event) = queue.pop_front() {
+                    QUEUE_DEPTH.store(queue.len(), Ordering::Relaxed);
+                    drop(queue);
+                    self.writable.notify_one();
+                    return event;
+                }
+            }
+            self.readable.notified().await;
+        }
+    }
+}
+
+fn dropped_event() {
+    let dropped = DROPPED_EVENTS.fetch_add(1, Ordering::Relaxed) + 1;
+    tracing::warn!(
+        target = "proc_qq",
+        "事件接收队列已满, 丢弃事件 (累计 {})",
+        dropped
+    );
+}
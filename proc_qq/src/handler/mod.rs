@@ -1,43 +1,109 @@
 use crate::ModuleEventProcess::KickedOffline;
+use crate::{ClientTrait, MessageChainParseTrait, RequestTrait};
 use async_trait::async_trait;
+pub use context::*;
+pub use event_stream::*;
 pub use events::*;
+pub use filters::*;
+pub(crate) use handler_order::*;
+pub use intake::*;
+pub use ordering::*;
+pub use panic::*;
+pub use predicate::*;
 pub use processes::*;
+pub use quota::*;
+pub use registry::*;
+pub use report::*;
 use rs_qq::handler::{Handler, QEvent};
+pub(crate) use shutdown::*;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
 
+mod context;
+mod event_stream;
 mod events;
+mod filters;
+mod friend_tracker;
+mod group_tracker;
+mod handler_order;
+mod intake;
+mod ordering;
+mod panic;
+mod predicate;
 mod processes;
+mod quota;
+mod registry;
+mod report;
+mod shutdown;
 
+#[derive(Clone)]
 pub(crate) struct ClientHandler {
-    pub(crate) modules: Arc<Vec<Module>>,
+    pub(crate) modules: Arc<ModuleRegistry>,
+    pub(crate) dispatch_mode: DispatchMode,
+    pub(crate) group_locks: Arc<GroupLocks>,
+    pub(crate) intake: Option<Arc<IntakeQueue>>,
+    pub(crate) distributed: Option<Arc<crate::Distributed>>,
+    pub(crate) events: EventBroadcast,
+    pub(crate) shutdown: Arc<Shutdown>,
 }
 
-impl ClientHandler {}
-
-enum MapResult<'a> {
+// 模块可能在运行时被移除, 所以保存ID而不是引用
+enum MapResult {
     None,
-    Process(&'a str, &'a str),
-    Exception(&'a str, &'a str),
+    Process(String, String),
+    Exception(String, String),
 }
 
 macro_rules! map_handlers {
-    ($self:expr $(,$event:expr, $process:path)* $(,)?) => {{
+    ($self:expr, $info:expr $(,$event:expr, $process:path)* $(,)?) => {{
         let mut result = MapResult::None;
-        for m in $self.modules.as_ref() {
+        let modules = $self.modules.snapshot();
+        for m in modules.iter() {
             for h in &m.handles {
+                if $info.own_message && !h.own_messages && crate::echo_suppression() {
+                    continue;
+                }
+                if !h.filters.iter().all(|f| f.check($info)) {
+                    continue;
+                }
                 match &h.process {
                     $(
-                    $process(e) => match e.handle(&$event).await {
-                        Ok(b) => {
-                            if b {
-                                result = MapResult::Process(&m.id, &h.name);
+                    $process(e) => {
+                        // 匹配之后再占用并发名额, 排队已满时跳过这个模块
+                        let _slot = match acquire_module_slot(&m.id).await {
+                            Some(slot) => slot,
+                            None => break,
+                        };
+                        match catch_panic(
+                            &m.id,
+                            &h.name,
+                            in_handler(
+                                &m.id,
+                                &h.name,
+                                h.process.event_name(),
+                                $info,
+                                e.handle(&$event),
+                            ),
+                        )
+                        .await
+                        {
+                            Ok(b) => {
+                                if b {
+                                    result = MapResult::Process(m.id.clone(), h.name.clone());
+                                }
+                            }
+                            Err(err) => {
+                                crate::module_log(
+                                    &m.id,
+                                    tracing::Level::ERROR,
+                                    format_args!("{} 出现错误 : {:?}", h.name, err),
+                                );
+                                crate::record_handler_error();
+                                result = MapResult::Exception(m.id.clone(), h.name.clone());
                             }
                         }
-                        Err(err) => {
-                            tracing::error!(target = "proc_qq", " 出现错误 : {:?}", err);
-                            result = MapResult::Exception(&m.id, &h.name);
-                        }
-                    },
+                    }
                     )*
                     _ => (),
                 }
@@ -58,118 +124,352 @@ macro_rules! map_handlers {
 #[async_trait]
 impl Handler for ClientHandler {
     async fn handle(&self, e: QEvent) {
+        let _in_flight = self.shutdown.enter();
+        self.events.publish(&e);
+        let e = match &self.distributed {
+            Some(distributed) => match distributed.publish(e).await {
+                Some(e) => e,
+                None => return,
+            },
+            None => e,
+        };
+        // 等待回复的会话 (confirm, next_message) 优先, 在排队和加锁之前交给会话, 避免等待自己
+        if crate::deliver_to_session(&e) {
+            return;
+        }
+        match &self.intake {
+            Some(intake) => {
+                if intake.start() {
+                    self.spawn_intake_workers(intake.clone());
+                }
+                intake.push(e).await
+            }
+            None => self.process(e).await,
+        }
+    }
+}
+
+impl ClientHandler {
+    pub(crate) async fn process(&self, e: QEvent) {
+        // 接收队列中的事件由后台任务处理, 同样需要等待
+        let _in_flight = self.shutdown.enter();
+        let _guard = match (self.dispatch_mode, event_group_code(&e)) {
+            (DispatchMode::SerialPerGroup, Some(group_code)) => {
+                Some(self.group_locks.lock(group_code).await)
+            }
+            _ => None,
+        };
+        self.dispatch(e).await
+    }
+
+    // 用信号量限制同时处理的事件数量, worker都在忙时事件留在队列中
+    fn spawn_intake_workers(&self, intake: Arc<IntakeQueue>) {
+        let handler = self.clone();
+        let workers = Arc::new(Semaphore::new(intake.config.workers.max(1)));
+        tokio::spawn(async move {
+            loop {
+                let permit = workers.clone().acquire_owned().await.unwrap();
+                let e = intake.pop().await;
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    handler.process(e).await;
+                    drop(permit);
+                });
+            }
+        });
+    }
+
+    fn event_sender(&self, client: &Arc<rs_qq::Client>) -> EventSender {
+        EventSender {
+            modules: self.modules.clone(),
+            rq_client: client.clone(),
+        }
+    }
+
+    async fn dispatch(&self, e: QEvent) {
         match e {
             QEvent::Login(event) => {
                 tracing::debug!(target = "proc_qq", "LOGIN : (UIN={})", event,);
                 let _ = map_handlers!(
                     &self,
+                    &event_info(event, None, None),
                     &LoginEvent { uin: event },
                     ModuleEventProcess::LoginEvent
                 );
             }
-            QEvent::GroupMessage(event) => {
+            QEvent::GroupMessage(mut event) => {
                 tracing::debug!(
                     target = "proc_qq",
                     "(GROUP={}, UIN={}) MESSAGE : {}",
                     event.message.group_code,
+                    crate::log_uin(event.message.from_uin),
+                    crate::log_chain(&event.message.elements)
+                );
+                let info = message_info(
+                    event.client.uin().await,
+                    Some(event.message.group_code),
                     event.message.from_uin,
-                    event.message.elements.to_string()
+                );
+                crate::cache_group_message(&event.message);
+                crate::learn_member_names(info.bot_uin, &event.message);
+                crate::apply_command_alias(
+                    Some(event.message.group_code),
+                    &mut event.message.elements,
                 );
                 let me = MessageEvent::GroupMessage(event.clone());
-                let _ = map_handlers!(
-                    &self,
-                    &event,
-                    ModuleEventProcess::GroupMessage,
-                    &me,
-                    ModuleEventProcess::Message,
+                let key = content_key(
+                    event.message.from_uin,
+                    &event.message.seqs,
+                    &event.message.rands,
+                );
+                let _ = with_message_content(key, async {
+                    map_handlers!(
+                        &self,
+                        &info,
+                        &event,
+                        ModuleEventProcess::GroupMessage,
+                        &me,
+                        ModuleEventProcess::Message,
+                    )
+                })
+                .await;
+                crate::auto_mark_read(
+                    &event.client,
+                    crate::MessageIdTrait::message_id(&event.message),
                 );
             }
-            QEvent::FriendMessage(event) => {
+            QEvent::FriendMessage(mut event) => {
                 tracing::debug!(
                     target = "proc_qq",
                     "(UIN={}) MESSAGE : {}",
-                    event.message.from_uin,
-                    event.message.elements.to_string()
+                    crate::log_uin(event.message.from_uin),
+                    crate::log_chain(&event.message.elements)
                 );
+                let info = message_info(event.client.uin().await, None, event.message.from_uin);
+                crate::apply_command_alias(None, &mut event.message.elements);
                 let me = MessageEvent::FriendMessage(event.clone());
-                let _ = map_handlers!(
-                    &self,
-                    &event,
-                    ModuleEventProcess::FriendMessage,
-                    &me,
-                    ModuleEventProcess::Message,
+                let key = content_key(
+                    event.message.from_uin,
+                    &event.message.seqs,
+                    &event.message.rands,
+                );
+                let _ = with_message_content(key, async {
+                    map_handlers!(
+                        &self,
+                        &info,
+                        &event,
+                        ModuleEventProcess::FriendMessage,
+                        &me,
+                        ModuleEventProcess::Message,
+                    )
+                })
+                .await;
+                crate::auto_mark_read(
+                    &event.client,
+                    crate::MessageIdTrait::message_id(&event.message),
                 );
             }
-            QEvent::TempMessage(event) => {
+            QEvent::TempMessage(mut event) => {
                 tracing::debug!(
                     target = "proc_qq",
                     "(UIN={}) MESSAGE : {}",
+                    crate::log_uin(event.message.from_uin),
+                    crate::log_chain(&event.message.elements)
+                );
+                let info = message_info(
+                    event.client.uin().await,
+                    event.message.group_code,
                     event.message.from_uin,
-                    event.message.elements.to_string()
                 );
+                crate::apply_command_alias(event.message.group_code, &mut event.message.elements);
                 let me = MessageEvent::TempMessage(event.clone());
-                let _ = map_handlers!(
-                    &self,
-                    &event,
-                    ModuleEventProcess::TempMessage,
-                    &me,
-                    ModuleEventProcess::Message,
+                let key = content_key(
+                    event.message.from_uin,
+                    &event.message.seqs,
+                    &event.message.rands,
                 );
+                let _ = with_message_content(key, async {
+                    map_handlers!(
+                        &self,
+                        &info,
+                        &event,
+                        ModuleEventProcess::TempMessage,
+                        &me,
+                        ModuleEventProcess::Message,
+                    )
+                })
+                .await;
             }
             QEvent::GroupRequest(event) => {
                 tracing::debug!(
                     target = "proc_qq",
                     "REQUEST (GROUP={}, UIN={}): {}",
                     event.request.group_code,
-                    event.request.req_uin,
-                    event.request.message,
+                    crate::log_uin(event.request.req_uin),
+                    crate::log_text(&event.request.message),
+                );
+                let info = event_info(
+                    event.client.uin().await,
+                    Some(event.request.group_code),
+                    Some(event.request.req_uin),
+                );
+                let _ = map_handlers!(&self, &info, &event, ModuleEventProcess::GroupRequest);
+            }
+            QEvent::SelfInvited(event) => {
+                tracing::debug!(
+                    target = "proc_qq",
+                    "INVITED (GROUP={}, UIN={})",
+                    event.request.group_code,
+                    crate::log_uin(event.request.invitor_uin),
+                );
+                let info = event_info(
+                    event.client.uin().await,
+                    Some(event.request.group_code),
+                    Some(event.request.invitor_uin),
                 );
-                let _ = map_handlers!(&self, &event, ModuleEventProcess::GroupRequest);
+                let result = map_handlers!(&self, &info, &event, ModuleEventProcess::SelfInvited);
+                // 没有模块处理这个邀请时才按照策略自动同意
+                if matches!(result, MapResult::None)
+                    && crate::invite_policy()
+                        .map(|policy| policy.allows(event.request.invitor_uin))
+                        .unwrap_or(false)
+                {
+                    if let Err(err) = event.accept().await {
+                        tracing::warn!(
+                            target = "proc_qq",
+                            "同意入群邀请失败 ({}) : {:?}",
+                            event.request.group_code,
+                            err
+                        );
+                    }
+                }
             }
             QEvent::FriendRequest(event) => {
                 tracing::debug!(
                     target = "proc_qq",
                     "REQUEST (UIN={}): {}",
-                    event.request.req_uin,
-                    event.request.message
+                    crate::log_uin(event.request.req_uin),
+                    crate::log_text(&event.request.message)
                 );
-                let _ = map_handlers!(&self, &event, ModuleEventProcess::FriendRequest);
+                let info = event_info(event.client.uin().await, None, Some(event.request.req_uin));
+                let _ = map_handlers!(&self, &info, &event, ModuleEventProcess::FriendRequest);
             }
             QEvent::NewFriend(event) => {
-                let _ = map_handlers!(&self, &event, ModuleEventProcess::NewFriend);
+                let info = event_info(event.client.uin().await, None, None);
+                let _ = map_handlers!(&self, &info, &event, ModuleEventProcess::NewFriend);
+                self.event_sender(&event.client).send_friend_changes().await;
             }
             QEvent::FriendPoke(event) => {
-                let _ = map_handlers!(&self, &event, ModuleEventProcess::FriendPoke);
+                let info = event_info(event.client.uin().await, None, None);
+                let _ = map_handlers!(&self, &info, &event, ModuleEventProcess::FriendPoke);
             }
             QEvent::DeleteFriend(event) => {
-                let _ = map_handlers!(&self, &event, ModuleEventProcess::DeleteFriend);
+                let info = event_info(event.client.uin().await, None, None);
+                let _ = map_handlers!(&self, &info, &event, ModuleEventProcess::DeleteFriend);
+                self.event_sender(&event.client).send_friend_changes().await;
             }
             QEvent::GroupMute(event) => {
-                let _ = map_handlers!(&self, &event, ModuleEventProcess::GroupMute);
+                let info = event_info(event.client.uin().await, None, None);
+                let _ = map_handlers!(&self, &info, &event, ModuleEventProcess::GroupMute);
+                self.event_sender(&event.client)
+                    .track_mute(
+                        event.group_mute.group_code,
+                        event.group_mute.target_uin,
+                        event.group_mute.duration,
+                    )
+                    .await;
             }
             QEvent::GroupLeave(event) => {
-                let _ = map_handlers!(&self, &event, ModuleEventProcess::GroupLeave);
+                let info = event_info(event.client.uin().await, None, None);
+                let _ = map_handlers!(&self, &info, &event, ModuleEventProcess::GroupLeave);
+                let bot_uin = event.client.uin().await;
+                crate::forget_member_names(bot_uin, event.leave.group_code, event.leave.member_uin);
+                if event.leave.member_uin == bot_uin {
+                    let group_name = group_tracker::forget_group(bot_uin, event.leave.group_code)
+                        .unwrap_or_default();
+                    self.event_sender(&event.client)
+                        .send_bot_removed(
+                            event.leave.group_code,
+                            group_name,
+                            event.leave.operator_uin,
+                        )
+                        .await;
+                }
+            }
+            QEvent::GroupDisband(event) => {
+                let bot_uin = event.client.uin().await;
+                let group_name = group_tracker::forget_group(bot_uin, event.disband.group_code)
+                    .unwrap_or_default();
+                self.event_sender(&event.client)
+                    .send_bot_removed(
+                        event.disband.group_code,
+                        group_name,
+                        Some(event.disband.operator_uin),
+                    )
+                    .await;
+            }
+            QEvent::NewMember(event) => {
+                if event.new_member.member_uin == event.client.uin().await {
+                    self.event_sender(&event.client).send_group_changes().await;
+                }
+            }
+            QEvent::MemberPermissionChange(event) => {
+                if event.change.member_uin == event.client.uin().await {
+                    self.event_sender(&event.client)
+                        .send_bot_permission_changed(
+                            event.change.group_code,
+                            event.change.new_permission.clone(),
+                        )
+                        .await;
+                }
             }
             QEvent::GroupNameUpdate(event) => {
-                let _ = map_handlers!(&self, &event, ModuleEventProcess::GroupNameUpdate);
+                let info = event_info(event.client.uin().await, None, None);
+                let _ = map_handlers!(&self, &info, &event, ModuleEventProcess::GroupNameUpdate);
             }
             QEvent::GroupMessageRecall(event) => {
-                let _ = map_handlers!(&self, &event, ModuleEventProcess::GroupMessageRecall);
+                let info = event_info(event.client.uin().await, None, None);
+                let _ = map_handlers!(&self, &info, &event, ModuleEventProcess::GroupMessageRecall);
             }
             QEvent::FriendMessageRecall(event) => {
-                let _ = map_handlers!(&self, &event, ModuleEventProcess::FriendMessageRecall);
+                let info = event_info(event.client.uin().await, None, None);
+                let _ = map_handlers!(
+                    &self,
+                    &info,
+                    &event,
+                    ModuleEventProcess::FriendMessageRecall
+                );
             }
             QEvent::MSFOffline(event) => {
-                let _ = map_handlers!(&self, &event, ModuleEventProcess::MSFOffline);
+                let info = event_info(event.client.uin().await, None, None);
+                let _ = map_handlers!(&self, &info, &event, ModuleEventProcess::MSFOffline);
             }
             QEvent::KickedOffline(event) => {
-                let _ = map_handlers!(&self, &event, KickedOffline);
+                crate::raise_alert(crate::Alert::KickedOffline(event.offline.msg.clone()));
+                let info = event_info(event.client.uin().await, None, None);
+                let _ = map_handlers!(&self, &info, &event, KickedOffline);
             }
             _ => tracing::debug!(target = "proc_qq", "{:?}", e),
         }
     }
 }
 
+fn event_info(bot_uin: i64, group_code: Option<i64>, from_uin: Option<i64>) -> EventInfo {
+    EventInfo {
+        bot_uin,
+        group_code,
+        from_uin,
+        own_message: false,
+    }
+}
+
+fn message_info(bot_uin: i64, group_code: Option<i64>, from_uin: i64) -> EventInfo {
+    EventInfo {
+        own_message: from_uin == bot_uin,
+        ..event_info(bot_uin, group_code, Some(from_uin))
+    }
+}
+
 pub struct Module {
     pub id: String,
     pub name: String,
@@ -177,14 +477,19 @@ pub struct Module {
 }
 
 pub(crate) struct EventSender {
-    pub(crate) modules: Arc<Vec<Module>>,
+    pub(crate) modules: Arc<ModuleRegistry>,
+    pub(crate) rq_client: Arc<rs_qq::Client>,
 }
 
 impl EventSender {
     pub async fn send_connected_and_online(&self) -> anyhow::Result<()> {
+        let info = event_info(self.rq_client.uin().await, None, None);
         match map_handlers!(
             &self,
-            &ConnectedAndOnlineEvent {},
+            &info,
+            &ConnectedAndOnlineEvent {
+                client: self.rq_client.clone(),
+            },
             ModuleEventProcess::ConnectedAndOnline
         ) {
             MapResult::Exception(_, _) => Err(anyhow::Error::msg("err")),
@@ -192,8 +497,10 @@ impl EventSender {
         }
     }
     pub async fn send_disconnected_and_offline(&self) -> anyhow::Result<()> {
+        let info = event_info(self.rq_client.uin().await, None, None);
         match map_handlers!(
             &self,
+            &info,
             &DisconnectedAndOfflineEvent {},
             ModuleEventProcess::DisconnectAndOffline,
         ) {
@@ -201,4 +508,256 @@ impl EventSender {
             _ => Ok(()),
         }
     }
+
+    /// 同步好友列表, 将变化作为FriendAdded/FriendRemoved/FriendUpdated事件分发
+    pub async fn send_friend_changes(&self) {
+        let changes = match friend_tracker::sync_friend_list(&self.rq_client).await {
+            Ok(changes) => changes,
+            Err(err) => {
+                tracing::warn!(target = "proc_qq", "同步好友列表失败 : {:?}", err);
+                return;
+            }
+        };
+        let client = self.rq_client.clone();
+        for change in changes {
+            match change {
+                friend_tracker::FriendChange::Added(friend) => {
+                    let info = event_info(client.uin().await, None, Some(friend.uin));
+                    let event = FriendAddedEvent {
+                        client: client.clone(),
+                        friend,
+                    };
+                    let _ = map_handlers!(&self, &info, &event, ModuleEventProcess::FriendAdded);
+                }
+                friend_tracker::FriendChange::Removed(friend) => {
+                    let info = event_info(client.uin().await, None, Some(friend.uin));
+                    let event = FriendRemovedEvent {
+                        client: client.clone(),
+                        friend,
+                    };
+                    let _ = map_handlers!(&self, &info, &event, ModuleEventProcess::FriendRemoved);
+                }
+                friend_tracker::FriendChange::Updated { old, new } => {
+                    let info = event_info(client.uin().await, None, Some(new.uin));
+                    let event = FriendUpdatedEvent {
+                        client: client.clone(),
+                        old,
+                        new,
+                    };
+                    let _ = map_handlers!(&self, &info, &event, ModuleEventProcess::FriendUpdated);
+                }
+            }
+        }
+    }
+
+    /// 记录好友的在线状态, 状态变化时分发FriendPresenceChanged事件
+    pub async fn send_friend_presence(&self, uin: i64, online: bool) {
+        if !crate::update_friend_presence(uin, online) {
+            return;
+        }
+        tracing::debug!(
+            target = "proc_qq",
+            "PRESENCE (UIN={}) : {}",
+            crate::log_uin(uin),
+            online
+        );
+        let info = event_info(self.rq_client.uin().await, None, Some(uin));
+        let event = FriendPresenceChangedEvent {
+            client: self.rq_client.clone(),
+            uin,
+            online,
+        };
+        let _ = map_handlers!(
+            &self,
+            &info,
+            &event,
+            ModuleEventProcess::FriendPresenceChanged
+        );
+    }
+
+    /// 按照自动退群策略退出群, 结果作为AutoLeftGroup事件分发
+    async fn send_auto_left(
+        &self,
+        policy: crate::AutoLeavePolicy,
+        group_code: i64,
+        group_name: String,
+    ) {
+        let mut error = None;
+        if let Some(message) = policy.message {
+            if let Err(err) = self
+                .rq_client
+                .send_message_to_target(
+                    &crate::MessageTarget::Group(group_code, 0),
+                    message.parse_message_chain(),
+                )
+                .await
+            {
+                error = Some(format!("{:?}", err));
+            }
+        }
+        let left = match self.rq_client.group_quit(group_code).await {
+            Ok(_) => true,
+            Err(err) => {
+                error = Some(format!("{:?}", err));
+                false
+            }
+        };
+        tracing::info!(
+            target = "proc_qq",
+            "AUTO LEAVE (GROUP={}) : left={} error={:?}",
+            group_code,
+            left,
+            error
+        );
+        let info = event_info(self.rq_client.uin().await, Some(group_code), None);
+        let event = AutoLeftGroupEvent {
+            client: self.rq_client.clone(),
+            group_code,
+            group_name,
+            left,
+            error,
+        };
+        let _ = map_handlers!(&self, &info, &event, ModuleEventProcess::AutoLeftGroup);
+    }
+
+    /// 记录禁言的时长, 到期时分发MuteExpired事件, 提前解除时立即分发
+    pub async fn track_mute(&self, group_code: i64, member_uin: i64, duration: Duration) {
+        // target_uin为0时是全员禁言
+        if member_uin == 0 {
+            return;
+        }
+        let key = format!(
+            "mute_expired:{}:{}:{}",
+            self.rq_client.uin().await,
+            group_code,
+            member_uin
+        );
+        if duration.is_zero() {
+            if crate::cancel_once(&key) {
+                self.send_mute_expired(group_code, member_uin, true).await;
+            }
+            return;
+        }
+        let sender = EventSender {
+            modules: self.modules.clone(),
+            rq_client: self.rq_client.clone(),
+        };
+        crate::schedule_once(key, duration, async move {
+            sender
+                .send_mute_expired(group_code, member_uin, false)
+                .await
+        });
+    }
+
+    async fn send_mute_expired(&self, group_code: i64, member_uin: i64, early: bool) {
+        let info = event_info(
+            self.rq_client.uin().await,
+            Some(group_code),
+            Some(member_uin),
+        );
+        let event = MuteExpiredEvent {
+            client: self.rq_client.clone(),
+            group_code,
+            member_uin,
+            early,
+        };
+        let _ = map_handlers!(&self, &info, &event, ModuleEventProcess::MuteExpired);
+    }
+
+    /// 机器人的群权限变化时分发BotPermissionChanged事件
+    pub async fn send_bot_permission_changed(
+        &self,
+        group_code: i64,
+        permission: rq_engine::structs::GroupMemberPermission,
+    ) {
+        let is_admin = matches!(
+            permission,
+            rq_engine::structs::GroupMemberPermission::Owner
+                | rq_engine::structs::GroupMemberPermission::Administrator
+        );
+        tracing::debug!(
+            target = "proc_qq",
+            "BOT PERMISSION (GROUP={}) : admin={}",
+            group_code,
+            is_admin
+        );
+        let info = event_info(self.rq_client.uin().await, Some(group_code), None);
+        let event = BotPermissionChangedEvent {
+            client: self.rq_client.clone(),
+            group_code,
+            permission,
+            is_admin,
+        };
+        let _ = map_handlers!(
+            &self,
+            &info,
+            &event,
+            ModuleEventProcess::BotPermissionChanged
+        );
+    }
+
+    /// 同步群列表, 将变化作为BotJoinedGroup/BotRemovedFromGroup事件分发
+    pub async fn send_group_changes(&self) {
+        let changes = match group_tracker::sync_group_list(&self.rq_client).await {
+            Ok(changes) => changes,
+            Err(err) => {
+                tracing::warn!(target = "proc_qq", "同步群列表失败 : {:?}", err);
+                return;
+            }
+        };
+        for change in changes {
+            match change {
+                group_tracker::GroupChange::Joined {
+                    group_code,
+                    group_name,
+                } => {
+                    if let Some(policy) = crate::auto_leave_policy() {
+                        if !policy.allows(group_code) {
+                            self.send_auto_left(policy, group_code, group_name).await;
+                            continue;
+                        }
+                    }
+                    let info = event_info(self.rq_client.uin().await, Some(group_code), None);
+                    let event = BotJoinedGroupEvent {
+                        client: self.rq_client.clone(),
+                        group_code,
+                        group_name,
+                        operator_uin: None,
+                    };
+                    let _ = map_handlers!(&self, &info, &event, ModuleEventProcess::BotJoinedGroup);
+                }
+                group_tracker::GroupChange::Removed {
+                    group_code,
+                    group_name,
+                } => self.send_bot_removed(group_code, group_name, None).await,
+            }
+        }
+    }
+
+    pub async fn send_bot_removed(
+        &self,
+        group_code: i64,
+        group_name: String,
+        operator_uin: Option<i64>,
+    ) {
+        tracing::debug!(
+            target = "proc_qq",
+            "BOT REMOVED (GROUP={}, OPERATOR={:?})",
+            group_code,
+            operator_uin
+        );
+        let info = event_info(self.rq_client.uin().await, Some(group_code), operator_uin);
+        let event = BotRemovedFromGroupEvent {
+            client: self.rq_client.clone(),
+            group_code,
+            group_name,
+            operator_uin,
+        };
+        let _ = map_handlers!(
+            &self,
+            &info,
+            &event,
+            ModuleEventProcess::BotRemovedFromGroup
+        );
+    }
 }
@@ -0,0 +1,49 @@
+use rs_qq::handler::QEvent;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OwnedMutexGuard;
+
+/// 事件分发方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DispatchMode {
+    /// 所有事件并发处理 (默认)
+    #[default]
+    Concurrent,
+    /// 同一个群的事件按顺序逐个处理, 不同群之间并发处理
+    SerialPerGroup,
+}
+
+/// 每个群一把锁, tokio的Mutex按照等待顺序唤醒, 保证同群事件按到达顺序处理
+#[derive(Default)]
+pub(crate) struct GroupLocks {
+    locks: Mutex<HashMap<i64, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl GroupLocks {
+    pub(crate) async fn lock(&self, group_code: i64) -> OwnedMutexGuard<()> {
+        let lock = self
+            .locks
+            .lock()
+            .unwrap()
+            .entry(group_code)
+            .or_default()
+            .clone();
+        lock.lock_owned().await
+    }
+}
+
+/// 事件所属的群
+pub(crate) fn event_group_code(event: &QEvent) -> Option<i64> {
+    match event {
+        QEvent::GroupMessage(event) => Some(event.message.group_code),
+        QEvent::GroupRequest(event) => Some(event.request.group_code),
+        QEvent::TempMessage(event) => event.message.group_code,
+        QEvent::GroupMute(event) => Some(event.group_mute.group_code),
+        QEvent::GroupLeave(event) => Some(event.leave.group_code),
+        QEvent::GroupNameUpdate(event) => Some(event.update.group_code),
+        QEvent::GroupMessageRecall(event) => Some(event.recall.group_code),
+        QEvent::GroupDisband(event) => Some(event.disband.group_code),
+        QEvent::NewMember(event) => Some(event.new_member.group_code),
+        _ => None,
+    }
+}
@@ -0,0 +1,42 @@
+use futures::FutureExt;
+use std::any::Any;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static HANDLER_PANICS: AtomicU64 = AtomicU64::new(0);
+
+/// 处理器panic的总次数
+pub fn handler_panic_count() -> u64 {
+    HANDLER_PANICS.load(Ordering::Relaxed)
+}
+
+/// 处理器panic时转换为错误, 和处理器返回Err一样处理, 不会中断事件分发
+pub(crate) async fn catch_panic<F: Future<Output = anyhow::Result<bool>>>(
+    module_id: &str,
+    handler_name: &str,
+    fut: F,
+) -> anyhow::Result<bool> {
+    match AssertUnwindSafe(fut).catch_unwind().await {
+        Ok(result) => result,
+        Err(payload) => {
+            HANDLER_PANICS.fetch_add(1, Ordering::Relaxed);
+            Err(anyhow::Error::msg(format!(
+                "handler {}::{} panicked : {}",
+                module_id,
+                handler_name,
+                panic_message(&payload)
+            )))
+        }
+    }
+}
+
+fn panic_message(payload: &Box<dyn Any + Send>) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
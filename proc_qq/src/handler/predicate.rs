@@ -0,0 +1,173 @@
+use crate::{
+    GroupRole, MessageContentTrait, MessageEvent, MessageEventProcess, ModuleEventHandler,
+    ModuleEventProcess, RoleTrait,
+};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use regex::Regex;
+use std::future::Future;
+use std::sync::Arc;
+
+/// 可以组合的消息过滤条件, 和event宏的参数作用相同, 但可以在运行时创建
+///
+/// Filter::command("签到").and(Filter::group_in(vec![123456]))
+#[derive(Clone)]
+pub struct Filter(Arc<FilterKind>);
+
+enum FilterKind {
+    Command(String),
+    Regex(Regex),
+    GroupIn(Vec<i64>),
+    UserIn(Vec<i64>),
+    Admin,
+    Owner,
+    Group,
+    Private,
+    Custom(Box<dyn Fn(&MessageEvent) -> bool + Send + Sync>),
+    And(Filter, Filter),
+    Or(Filter, Filter),
+    Not(Filter),
+}
+
+impl Filter {
+    fn new(kind: FilterKind) -> Self {
+        Self(Arc::new(kind))
+    }
+
+    /// 消息的第一个词为command
+    pub fn command<S: Into<String>>(command: S) -> Self {
+        Self::new(FilterKind::Command(command.into()))
+    }
+
+    /// 消息内容匹配正则
+    pub fn regex(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self::new(FilterKind::Regex(Regex::new(pattern)?)))
+    }
+
+    /// 只处理这些群的消息
+    pub fn group_in(group_codes: Vec<i64>) -> Self {
+        Self::new(FilterKind::GroupIn(group_codes))
+    }
+
+    /// 只处理这些用户发送的消息
+    pub fn user_in(uins: Vec<i64>) -> Self {
+        Self::new(FilterKind::UserIn(uins))
+    }
+
+    /// 发送者是群主或管理员 (只匹配群消息)
+    pub fn admin() -> Self {
+        Self::new(FilterKind::Admin)
+    }
+
+    /// 发送者是群主 (只匹配群消息)
+    pub fn owner() -> Self {
+        Self::new(FilterKind::Owner)
+    }
+
+    pub fn group() -> Self {
+        Self::new(FilterKind::Group)
+    }
+
+    /// 好友消息
+    pub fn private() -> Self {
+        Self::new(FilterKind::Private)
+    }
+
+    pub fn custom<F: Fn(&MessageEvent) -> bool + Send + Sync + 'static>(f: F) -> Self {
+        Self::new(FilterKind::Custom(Box::new(f)))
+    }
+
+    pub fn and(self, other: Filter) -> Self {
+        Self::new(FilterKind::And(self, other))
+    }
+
+    pub fn or(self, other: Filter) -> Self {
+        Self::new(FilterKind::Or(self, other))
+    }
+
+    /// 判断消息是否通过, 判断权限时可能需要查询群成员
+    pub fn check<'a>(&'a self, event: &'a MessageEvent) -> BoxFuture<'a, bool> {
+        async move {
+            match self.0.as_ref() {
+                FilterKind::Command(command) => {
+                    event.message_content().split_whitespace().next() == Some(command.as_str())
+                }
+                FilterKind::Regex(regex) => regex.is_match(&event.message_content()),
+                FilterKind::GroupIn(group_codes) => match event {
+                    MessageEvent::GroupMessage(event) => {
+                        group_codes.contains(&event.message.group_code)
+                    }
+                    MessageEvent::TempMessage(event) => match event.message.group_code {
+                        Some(group_code) => group_codes.contains(&group_code),
+                        None => false,
+                    },
+                    MessageEvent::FriendMessage(_) => false,
+                },
+                FilterKind::UserIn(uins) => uins.contains(&event.from_uin()),
+                FilterKind::Admin => {
+                    matches!(sender_role(event).await, Some(role) if role.is_admin())
+                }
+                FilterKind::Owner => {
+                    matches!(sender_role(event).await, Some(role) if role.is_owner())
+                }
+                FilterKind::Group => event.is_group_message(),
+                FilterKind::Private => event.is_private_message(),
+                FilterKind::Custom(f) => f(event),
+                FilterKind::And(a, b) => a.check(event).await && b.check(event).await,
+                FilterKind::Or(a, b) => a.check(event).await || b.check(event).await,
+                FilterKind::Not(a) => !a.check(event).await,
+            }
+        }
+        .boxed()
+    }
+
+    /// 使用闭包创建监听器, 消息通过过滤条件时才会调用
+    pub fn handler<S, F, Fut>(self, name: S, f: F) -> ModuleEventHandler
+    where
+        S: Into<String>,
+        F: Fn(MessageEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<bool>> + Send,
+    {
+        ModuleEventHandler::new(
+            name,
+            ModuleEventProcess::Message(Box::new(FilteredHandler { filter: self, f })),
+        )
+    }
+}
+
+/// 取反, !Filter::admin()
+impl std::ops::Not for Filter {
+    type Output = Filter;
+
+    fn not(self) -> Self::Output {
+        Filter::new(FilterKind::Not(self))
+    }
+}
+
+async fn sender_role(event: &MessageEvent) -> Option<GroupRole> {
+    let event = event.as_group_message().ok()?;
+    event
+        .member_role(event.message.group_code, event.message.from_uin)
+        .await
+        .ok()
+}
+
+struct FilteredHandler<F> {
+    filter: Filter,
+    f: F,
+}
+
+#[async_trait]
+impl<F, Fut> MessageEventProcess for FilteredHandler<F>
+where
+    F: Fn(MessageEvent) -> Fut + Send + Sync,
+    Fut: Future<Output = anyhow::Result<bool>> + Send,
+{
+    async fn handle(&self, event: &MessageEvent) -> anyhow::Result<bool> {
+        if !self.filter.check(event).await {
+            return Ok(false);
+        }
+        (self.f)(event.clone()).await
+    }
+}
@@ -1,18 +1,32 @@
 use super::events::*;
+use super::filters::HandlerFilter;
+use crate::ConfigSection;
 use async_trait::async_trait;
 use rs_qq::client::event::{
     DeleteFriendEvent, FriendMessageEvent, FriendMessageRecallEvent, FriendPokeEvent,
     FriendRequestEvent, GroupLeaveEvent, GroupMessageEvent, GroupMessageRecallEvent,
-    GroupMuteEvent, GroupNameUpdateEvent, GroupRequestEvent, NewFriendEvent, TempMessageEvent,
+    GroupMuteEvent, GroupNameUpdateEvent, GroupRequestEvent, NewFriendEvent, SelfInvitedEvent,
+    TempMessageEvent,
 };
+use serde::de::DeserializeOwned;
 
+/// 声明一个模块
+/// 监听器可以是event宏生成的名称, 也可以是实现了 Into<ModuleEventHandler> 的任意表达式
+/// module!("id", "name", on_message, MyHandler::new(config))
 #[macro_export]
 macro_rules! module {
-    ($id:expr,$name:expr $(, $x:tt)* $(,)?) => (
-        ::proc_qq::Module {
+    (@handles [$($acc:expr,)*]) => (vec![$($acc,)*]);
+    (@handles [$($acc:expr,)*] $x:ident $(, $($rest:tt)*)?) => (
+        $crate::module!(@handles [$($acc,)* $x {}.into(),] $($($rest)*)?)
+    );
+    (@handles [$($acc:expr,)*] $x:expr $(, $($rest:tt)*)?) => (
+        $crate::module!(@handles [$($acc,)* $x.into(),] $($($rest)*)?)
+    );
+    ($id:expr, $name:expr $(, $($handles:tt)*)?) => (
+        $crate::Module {
             id: $id.to_owned(),
             name: $name.to_owned(),
-            handles: vec![$($x {}.into(),)*],
+            handles: $crate::module!(@handles [] $($($handles)*)?),
         }
     );
 }
@@ -20,6 +34,73 @@ macro_rules! module {
 pub struct ModuleEventHandler {
     pub name: String,
     pub process: ModuleEventProcess,
+    pub filters: Vec<Box<dyn HandlerFilter>>,
+    // 触发的命令 (event宏的regexp), 用于导出命令列表
+    pub command: Option<String>,
+    // 说明 (event宏所在函数的文档注释)
+    pub description: Option<String>,
+    // 需要在这些处理器之后 / 之前执行 (同一个模块中的处理器名称)
+    pub after: Vec<String>,
+    pub before: Vec<String>,
+    // 接收机器人自己发送的消息 (set_echo_suppression 默认跳过)
+    pub own_messages: bool,
+    // 使用的配置节, build时检查
+    pub config_sections: Vec<ConfigSection>,
+}
+
+impl ModuleEventHandler {
+    pub fn new<S: Into<String>>(name: S, process: ModuleEventProcess) -> Self {
+        Self {
+            name: name.into(),
+            process,
+            filters: vec![],
+            command: None,
+            description: None,
+            after: vec![],
+            before: vec![],
+            own_messages: false,
+            config_sections: vec![],
+        }
+    }
+
+    pub fn command<S: Into<String>>(mut self, command: S) -> Self {
+        self.command = Some(command.into());
+        self
+    }
+
+    pub fn description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// 在同一个模块中名为name的处理器之后执行
+    pub fn after<S: Into<String>>(mut self, name: S) -> Self {
+        self.after.push(name.into());
+        self
+    }
+
+    /// 在同一个模块中名为name的处理器之前执行
+    pub fn before<S: Into<String>>(mut self, name: S) -> Self {
+        self.before.push(name.into());
+        self
+    }
+
+    /// 接收机器人自己发送的消息 (包括在其他设备上发送的)
+    pub fn own_messages(mut self, own_messages: bool) -> Self {
+        self.own_messages = own_messages;
+        self
+    }
+
+    /// 声明使用的配置节, build时检查能否解析为T
+    pub fn config_section<T: DeserializeOwned>(mut self, section: &str) -> Self {
+        self.config_sections.push(ConfigSection::new::<T>(section));
+        self
+    }
+
+    pub fn filter<F: HandlerFilter + 'static>(mut self, filter: F) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
 }
 
 pub enum ModuleEventProcess {
@@ -27,15 +108,25 @@ pub enum ModuleEventProcess {
     FriendMessage(Box<dyn FriendMessageEventProcess>),
     TempMessage(Box<dyn TempMessageEventProcess>),
     GroupRequest(Box<dyn GroupRequestEventProcess>),
+    SelfInvited(Box<dyn SelfInvitedEventProcess>),
     FriendRequest(Box<dyn FriendRequestEventProcess>),
 
     NewFriend(Box<dyn NewFriendEventProcess>),
     FriendPoke(Box<dyn FriendPokeEventProcess>),
     DeleteFriend(Box<dyn DeleteFriendEventProcess>),
+    FriendAdded(Box<dyn FriendAddedEventProcess>),
+    FriendRemoved(Box<dyn FriendRemovedEventProcess>),
+    FriendUpdated(Box<dyn FriendUpdatedEventProcess>),
+    FriendPresenceChanged(Box<dyn FriendPresenceChangedEventProcess>),
 
     GroupMute(Box<dyn GroupMuteEventProcess>),
+    MuteExpired(Box<dyn MuteExpiredEventProcess>),
     GroupLeave(Box<dyn GroupLeaveEventProcess>),
     GroupNameUpdate(Box<dyn GroupNameUpdateEventProcess>),
+    BotJoinedGroup(Box<dyn BotJoinedGroupEventProcess>),
+    BotRemovedFromGroup(Box<dyn BotRemovedFromGroupEventProcess>),
+    BotPermissionChanged(Box<dyn BotPermissionChangedEventProcess>),
+    AutoLeftGroup(Box<dyn AutoLeftGroupEventProcess>),
 
     GroupMessageRecall(Box<dyn GroupMessageRecallEventProcess>),
     FriendMessageRecall(Box<dyn FriendMessageRecallEventProcess>),
@@ -49,6 +140,42 @@ pub enum ModuleEventProcess {
     DisconnectAndOffline(Box<dyn DisconnectedAndOfflineEventProcess>),
 }
 
+impl ModuleEventProcess {
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            ModuleEventProcess::GroupMessage(_) => "GroupMessageEvent",
+            ModuleEventProcess::FriendMessage(_) => "FriendMessageEvent",
+            ModuleEventProcess::TempMessage(_) => "TempMessageEvent",
+            ModuleEventProcess::GroupRequest(_) => "GroupRequestEvent",
+            ModuleEventProcess::SelfInvited(_) => "SelfInvitedEvent",
+            ModuleEventProcess::FriendRequest(_) => "FriendRequestEvent",
+            ModuleEventProcess::NewFriend(_) => "NewFriendEvent",
+            ModuleEventProcess::FriendPoke(_) => "FriendPokeEvent",
+            ModuleEventProcess::DeleteFriend(_) => "DeleteFriendEvent",
+            ModuleEventProcess::FriendAdded(_) => "FriendAddedEvent",
+            ModuleEventProcess::FriendRemoved(_) => "FriendRemovedEvent",
+            ModuleEventProcess::FriendUpdated(_) => "FriendUpdatedEvent",
+            ModuleEventProcess::FriendPresenceChanged(_) => "FriendPresenceChangedEvent",
+            ModuleEventProcess::GroupMute(_) => "GroupMuteEvent",
+            ModuleEventProcess::MuteExpired(_) => "MuteExpiredEvent",
+            ModuleEventProcess::GroupLeave(_) => "GroupLeaveEvent",
+            ModuleEventProcess::GroupNameUpdate(_) => "GroupNameUpdateEvent",
+            ModuleEventProcess::BotJoinedGroup(_) => "BotJoinedGroupEvent",
+            ModuleEventProcess::BotRemovedFromGroup(_) => "BotRemovedFromGroupEvent",
+            ModuleEventProcess::BotPermissionChanged(_) => "BotPermissionChangedEvent",
+            ModuleEventProcess::AutoLeftGroup(_) => "AutoLeftGroupEvent",
+            ModuleEventProcess::GroupMessageRecall(_) => "GroupMessageRecallEvent",
+            ModuleEventProcess::FriendMessageRecall(_) => "FriendMessageRecallEvent",
+            ModuleEventProcess::MSFOffline(_) => "MSFOfflineEvent",
+            ModuleEventProcess::KickedOffline(_) => "KickedOfflineEvent",
+            ModuleEventProcess::LoginEvent(_) => "LoginEvent",
+            ModuleEventProcess::Message(_) => "MessageEvent",
+            ModuleEventProcess::ConnectedAndOnline(_) => "ConnectedAndOnlineEvent",
+            ModuleEventProcess::DisconnectAndOffline(_) => "DisconnectedAndOfflineEvent",
+        }
+    }
+}
+
 macro_rules! process_trait {
     ($name:ident, $event:path) => {
         #[async_trait]
@@ -63,15 +190,28 @@ process_trait!(FriendMessageEventProcess, FriendMessageEvent);
 process_trait!(TempMessageEventProcess, TempMessageEvent);
 
 process_trait!(GroupRequestEventProcess, GroupRequestEvent);
+process_trait!(SelfInvitedEventProcess, SelfInvitedEvent);
 process_trait!(FriendRequestEventProcess, FriendRequestEvent);
 
 process_trait!(NewFriendEventProcess, NewFriendEvent);
 process_trait!(FriendPokeEventProcess, FriendPokeEvent);
 process_trait!(DeleteFriendEventProcess, DeleteFriendEvent);
+process_trait!(FriendAddedEventProcess, FriendAddedEvent);
+process_trait!(FriendRemovedEventProcess, FriendRemovedEvent);
+process_trait!(FriendUpdatedEventProcess, FriendUpdatedEvent);
+process_trait!(
+    FriendPresenceChangedEventProcess,
+    FriendPresenceChangedEvent
+);
 
 process_trait!(GroupMuteEventProcess, GroupMuteEvent);
+process_trait!(MuteExpiredEventProcess, MuteExpiredEvent);
 process_trait!(GroupLeaveEventProcess, GroupLeaveEvent);
 process_trait!(GroupNameUpdateEventProcess, GroupNameUpdateEvent);
+process_trait!(BotJoinedGroupEventProcess, BotJoinedGroupEvent);
+process_trait!(BotRemovedFromGroupEventProcess, BotRemovedFromGroupEvent);
+process_trait!(BotPermissionChangedEventProcess, BotPermissionChangedEvent);
+process_trait!(AutoLeftGroupEventProcess, AutoLeftGroupEvent);
 
 process_trait!(GroupMessageRecallEventProcess, GroupMessageRecallEvent);
 process_trait!(FriendMessageRecallEventProcess, FriendMessageRecallEvent);
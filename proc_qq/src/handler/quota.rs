@@ -0,0 +1,138 @@
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// 单个模块最多排队等待的事件数量, 超过后跳过新事件
+pub const MODULE_QUEUE_LIMIT: usize = 100;
+
+struct ModuleQuota {
+    slots: Arc<Semaphore>,
+    waiting: Arc<AtomicUsize>,
+    rejections: u64,
+}
+
+impl ModuleQuota {
+    fn new(limit: usize) -> Self {
+        Self {
+            slots: Arc::new(Semaphore::new(limit.max(1))),
+            waiting: Arc::new(AtomicUsize::new(0)),
+            rejections: 0,
+        }
+    }
+
+    fn handles(&self) -> (Arc<Semaphore>, Arc<AtomicUsize>) {
+        (self.slots.clone(), self.waiting.clone())
+    }
+}
+
+lazy_static! {
+    static ref DEFAULT_LIMIT: RwLock<Option<usize>> = RwLock::new(None);
+    static ref QUOTAS: RwLock<HashMap<String, ModuleQuota>> = RwLock::new(HashMap::new());
+}
+
+/// 单个模块同时执行的处理器数量上限, 达到上限时这个模块的事件排队等待,
+/// 排队超过 MODULE_QUEUE_LIMIT 时跳过新事件, 不影响其他模块
+pub fn set_module_concurrency(module_id: &str, limit: usize) {
+    QUOTAS
+        .write()
+        .unwrap()
+        .insert(module_id.to_owned(), ModuleQuota::new(limit));
+}
+
+/// 没有单独设置的模块使用的上限, None为不限制 (默认), 需要在处理事件之前设置
+pub fn set_default_module_concurrency(limit: Option<usize>) {
+    *DEFAULT_LIMIT.write().unwrap() = limit;
+}
+
+/// 模块因排队已满而跳过的事件数量
+pub fn module_quota_rejections(module_id: &str) -> u64 {
+    QUOTAS
+        .read()
+        .unwrap()
+        .get(module_id)
+        .map(|quota| quota.rejections)
+        .unwrap_or(0)
+}
+
+/// 处理器匹配后调用, 不限制时返回Some(None), 排队已满时返回None
+pub(crate) async fn acquire_module_slot(module_id: &str) -> Option<Option<OwnedSemaphorePermit>> {
+    let quota = QUOTAS
+        .read()
+        .unwrap()
+        .get(module_id)
+        .map(ModuleQuota::handles);
+    let (slots, waiting) = match quota {
+        Some(quota) => quota,
+        None => match *DEFAULT_LIMIT.read().unwrap() {
+            Some(limit) => QUOTAS
+                .write()
+                .unwrap()
+                .entry(module_id.to_owned())
+                .or_insert_with(|| ModuleQuota::new(limit))
+                .handles(),
+            None => return Some(None),
+        },
+    };
+    if let Ok(permit) = slots.clone().try_acquire_owned() {
+        return Some(Some(permit));
+    }
+    if waiting.fetch_add(1, Ordering::SeqCst) >= MODULE_QUEUE_LIMIT {
+        waiting.fetch_sub(1, Ordering::SeqCst);
+        if let Some(quota) = QUOTAS.write().unwrap().get_mut(module_id) {
+            quota.rejections += 1;
+        }
+        crate::module_log(
+            module_id,
+            tracing::Level::WARN,
+            format_args!(
+                "模块 {} 的处理器并发已达上限且排队已满, 跳过本次事件",
+                module_id
+            ),
+        );
+        return None;
+    }
+    let permit = slots.acquire_owned().await.ok();
+    waiting.fetch_sub(1, Ordering::SeqCst);
+    permit.map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn waits_for_a_free_slot() {
+        set_module_concurrency("quota_wait", 1);
+        let first = acquire_module_slot("quota_wait").await.unwrap();
+        let waiter = tokio::spawn(async { acquire_module_slot("quota_wait").await.is_some() });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+        drop(first);
+        assert!(waiter.await.unwrap());
+        assert_eq!(module_quota_rejections("quota_wait"), 0);
+    }
+
+    #[tokio::test]
+    async fn rejects_when_the_queue_is_full() {
+        set_module_concurrency("quota_full", 1);
+        let _slot = acquire_module_slot("quota_full").await.unwrap();
+        let waiters: Vec<_> = (0..MODULE_QUEUE_LIMIT)
+            .map(|_| tokio::spawn(acquire_module_slot("quota_full")))
+            .collect();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(acquire_module_slot("quota_full").await.is_none());
+        assert_eq!(module_quota_rejections("quota_full"), 1);
+        waiters.iter().for_each(|waiter| waiter.abort());
+    }
+
+    #[tokio::test]
+    async fn unlimited_without_quota() {
+        assert!(matches!(
+            acquire_module_slot("quota_none").await,
+            Some(None)
+        ));
+    }
+}
@@ -0,0 +1,166 @@
+use crate::Module;
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+
+/// 某一时刻注册的模块, 分发事件时使用, 不受之后添加/移除的影响
+#[derive(Clone)]
+pub struct ModuleSnapshot {
+    // build时注册的模块
+    base: Arc<Vec<Module>>,
+    // 被移除的build时注册的模块
+    removed: HashSet<String>,
+    // 运行时添加的模块
+    added: Vec<Arc<Module>>,
+}
+
+impl ModuleSnapshot {
+    pub fn iter(&self) -> impl Iterator<Item = &Module> {
+        self.base
+            .iter()
+            .filter(|m| !self.removed.contains(&m.id))
+            .chain(self.added.iter().map(|m| m.as_ref()))
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.iter().any(|m| m.id == id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+/// 客户端的模块列表, 可以在运行时添加和移除模块
+///
+/// 正在执行的监听器不受影响, 修改从下一个事件开始生效
+pub struct ModuleRegistry {
+    snapshot: RwLock<Arc<ModuleSnapshot>>,
+}
+
+impl ModuleRegistry {
+    pub(crate) fn new(modules: Arc<Vec<Module>>) -> Self {
+        Self {
+            snapshot: RwLock::new(Arc::new(ModuleSnapshot {
+                base: modules,
+                removed: HashSet::new(),
+                added: vec![],
+            })),
+        }
+    }
+
+    pub fn snapshot(&self) -> Arc<ModuleSnapshot> {
+        self.snapshot.read().unwrap().clone()
+    }
+
+    /// 添加模块, 排在已有的模块之后
+    ///
+    /// 和build时一样检查处理器顺序和使用的配置节, 检查失败或者模块ID已经存在时返回错误
+    pub fn add(&self, mut module: Module) -> anyhow::Result<()> {
+        crate::check_config_sections(&module)?;
+        crate::order_handlers(&mut module).map_err(crate::BuildError::HandlerOrder)?;
+        let mut lock = self.snapshot.write().unwrap();
+        if lock.contains(&module.id) {
+            return Err(anyhow::Error::msg(format!("模块ID重复 : {}", module.id)));
+        }
+        let mut snapshot = lock.as_ref().clone();
+        snapshot.added.push(Arc::new(module));
+        *lock = Arc::new(snapshot);
+        Ok(())
+    }
+
+    /// 移除模块, 返回模块是否存在
+    pub fn remove(&self, id: &str) -> bool {
+        let mut lock = self.snapshot.write().unwrap();
+        if !lock.contains(id) {
+            return false;
+        }
+        let mut snapshot = lock.as_ref().clone();
+        let added = snapshot.added.len();
+        snapshot.added.retain(|m| m.id != id);
+        if snapshot.added.len() == added {
+            snapshot.removed.insert(id.to_owned());
+        }
+        *lock = Arc::new(snapshot);
+        true
+    }
+
+    pub fn ids(&self) -> Vec<String> {
+        self.snapshot().iter().map(|m| m.id.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConfigSection, GroupMessageEventProcess, ModuleEventHandler, ModuleEventProcess};
+    use async_trait::async_trait;
+    use rs_qq::client::event::GroupMessageEvent;
+    use serde_derive::Deserialize;
+
+    struct Noop;
+
+    #[async_trait]
+    impl GroupMessageEventProcess for Noop {
+        async fn handle(&self, _: &GroupMessageEvent) -> anyhow::Result<bool> {
+            Ok(false)
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Required {
+        token: String,
+    }
+
+    fn module(id: &str) -> Module {
+        Module {
+            id: id.to_owned(),
+            name: id.to_owned(),
+            handles: vec![ModuleEventHandler::new(
+                "noop",
+                ModuleEventProcess::GroupMessage(Box::new(Noop)),
+            )],
+        }
+    }
+
+    #[test]
+    fn add_rejects_duplicate_ids() {
+        let registry = ModuleRegistry::new(Arc::new(vec![module("a")]));
+        assert!(registry.add(module("a")).is_err());
+        assert!(registry.add(module("b")).is_ok());
+        assert!(registry.add(module("b")).is_err());
+        assert_eq!(registry.ids(), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn add_checks_config_sections() {
+        let registry = ModuleRegistry::new(Arc::new(vec![]));
+        let mut m = module("needs_config");
+        m.handles[0]
+            .config_sections
+            .push(ConfigSection::new::<Required>("registry_test_missing"));
+        assert!(registry.add(m).is_err());
+        assert!(!registry.snapshot().contains("needs_config"));
+    }
+
+    #[test]
+    fn add_checks_handler_order() {
+        let registry = ModuleRegistry::new(Arc::new(vec![]));
+        let mut m = module("bad_order");
+        m.handles[0].after.push("missing".to_owned());
+        assert!(registry.add(m).is_err());
+    }
+
+    #[test]
+    fn removed_modules_leave_the_snapshot() {
+        let registry = ModuleRegistry::new(Arc::new(vec![module("a")]));
+        registry.add(module("b")).unwrap();
+        let before = registry.snapshot();
+        assert!(registry.remove("a"));
+        assert!(registry.remove("b"));
+        assert!(!registry.remove("b"));
+        assert!(registry.snapshot().is_empty());
+        // 已经取得的快照不受影响
+        assert_eq!(before.iter().count(), 2);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{Module, ModuleRegistry};
+use lazy_static::lazy_static;
+use serde_derive::Serialize;
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+
+lazy_static! {
+    static ref ACTIVE_MODULES: RwLock<Option<Arc<ModuleRegistry>>> = RwLock::new(None);
+}
+
+/// 模块注册报告, 用于确认宏展开后实际注册了哪些模块和监听器
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistrationReport {
+    pub modules: Vec<ModuleReport>,
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleReport {
+    pub id: String,
+    pub name: String,
+    pub handlers: Vec<HandlerReport>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HandlerReport {
+    pub name: String,
+    pub event: String,
+    // 分发顺序, 越小越先执行
+    pub priority: usize,
+    pub filters: Vec<String>,
+    pub command: Option<String>,
+    pub description: Option<String>,
+}
+
+/// 命令列表中的一项, 用于外部的面板或文档站点
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandReport {
+    pub module_id: String,
+    pub module_name: String,
+    pub handler: String,
+    pub event: String,
+    pub command: Option<String>,
+    pub description: Option<String>,
+}
+
+impl RegistrationReport {
+    pub fn from_modules<'a, I: IntoIterator<Item = &'a Module>>(modules: I) -> Self {
+        let mut conflicts = vec![];
+        let mut module_ids = HashSet::new();
+        let mut priority = 0;
+        let modules = modules
+            .into_iter()
+            .map(|m| {
+                if !module_ids.insert(m.id.as_str()) {
+                    conflicts.push(format!("模块ID重复 : {}", m.id));
+                }
+                let mut handler_names = HashSet::new();
+                let handlers = m
+                    .handles
+                    .iter()
+                    .map(|h| {
+                        if !handler_names.insert(h.name.as_str()) {
+                            conflicts.push(format!("监听器名称重复 : {}::{}", m.id, h.name));
+                        }
+                        priority += 1;
+                        HandlerReport {
+                            name: h.name.clone(),
+                            event: h.process.event_name().to_owned(),
+                            priority,
+                            filters: h.filters.iter().map(|f| f.describe()).collect(),
+                            command: h.command.clone(),
+                            description: h.description.clone(),
+                        }
+                    })
+                    .collect();
+                ModuleReport {
+                    id: m.id.clone(),
+                    name: m.name.clone(),
+                    handlers,
+                }
+            })
+            .collect();
+        Self { modules, conflicts }
+    }
+
+    /// 有命令或说明的监听器
+    pub fn commands(&self) -> Vec<CommandReport> {
+        self.modules
+            .iter()
+            .flat_map(|m| {
+                m.handlers
+                    .iter()
+                    .filter(|h| h.command.is_some() || h.description.is_some())
+                    .map(move |h| CommandReport {
+                        module_id: m.id.clone(),
+                        module_name: m.name.clone(),
+                        handler: h.name.clone(),
+                        event: h.event.clone(),
+                        command: h.command.clone(),
+                        description: h.description.clone(),
+                    })
+            })
+            .collect()
+    }
+
+    /// 命令列表导出为JSON
+    pub fn commands_json(&self) -> String {
+        serde_json::to_string_pretty(&self.commands()).unwrap_or_default()
+    }
+
+    /// 适合打印到日志的文字
+    pub fn to_banner(&self) -> String {
+        let mut lines = vec![format!("已注册 {} 个模块", self.modules.len())];
+        for m in &self.modules {
+            lines.push(format!(" - {} ({})", m.id, m.name));
+            for h in &m.handlers {
+                let mut line = format!("   {}. {} : {}", h.priority, h.name, h.event);
+                if !h.filters.is_empty() {
+                    line.push_str(&format!(" [{}]", h.filters.join(", ")));
+                }
+                lines.push(line);
+            }
+        }
+        for conflict in &self.conflicts {
+            lines.push(format!(" ! {}", conflict));
+        }
+        lines.join("\n")
+    }
+}
+
+pub(crate) fn set_active_modules(modules: Arc<ModuleRegistry>) {
+    *ACTIVE_MODULES.write().unwrap() = Some(modules);
+}
+
+/// 最后启动的客户端的命令列表, 客户端未启动时为空
+pub fn command_catalog() -> Vec<CommandReport> {
+    match ACTIVE_MODULES.read().unwrap().as_ref() {
+        Some(modules) => RegistrationReport::from_modules(modules.snapshot().iter()).commands(),
+        None => vec![],
+    }
+}
@@ -0,0 +1,74 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// 正在处理的事件和关闭状态, 关闭时等待处理中的事件完成后再退出
+#[derive(Default)]
+pub(crate) struct Shutdown {
+    stopping: AtomicBool,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+/// 事件处理完成 (drop) 时减少计数
+pub(crate) struct InFlightGuard(Arc<Shutdown>);
+
+impl Shutdown {
+    pub(crate) fn enter(self: &Arc<Self>) -> InFlightGuard {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard(self.clone())
+    }
+
+    pub(crate) fn stop(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+    }
+
+    pub(crate) fn stopping(&self) -> bool {
+        self.stopping.load(Ordering::SeqCst)
+    }
+
+    /// 等待处理中的事件全部完成, 超时返回false
+    pub(crate) async fn wait_idle(&self, timeout: Duration) -> bool {
+        tokio::time::timeout(timeout, async {
+            loop {
+                // 先注册再检查, 不会错过检查之后的通知
+                let notified = self.idle.notified();
+                if self.in_flight.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+                notified.await;
+            }
+        })
+        .await
+        .is_ok()
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if self.0.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn waits_for_in_flight_events() {
+        let shutdown = Arc::new(Shutdown::default());
+        assert!(shutdown.wait_idle(Duration::from_millis(10)).await);
+        let guard = shutdown.enter();
+        assert!(!shutdown.wait_idle(Duration::from_millis(10)).await);
+        let waiting = {
+            let shutdown = shutdown.clone();
+            tokio::spawn(async move { shutdown.wait_idle(Duration::from_secs(5)).await })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        drop(guard);
+        assert!(waiting.await.unwrap());
+    }
+}
@@ -0,0 +1,210 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::{build_info, command_catalog, login_qrcode, login_state};
+
+// 读取请求头的超时, 连接后一直不发完请求头的连接会被关闭
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+const INDEX_HTML: &str = r#"<!DOCTYPE html>
+<html><head><meta charset="utf-8"><title>proc_qq login</title></head>
+<body>
+<p id="state"></p>
+<img id="qrcode">
+<script>
+const token = encodeURIComponent(new URLSearchParams(location.search).get('token') || '');
+async function refresh() {
+  const state = await (await fetch('/login/state?token=' + token)).json();
+  document.getElementById('state').innerText = JSON.stringify(state);
+  document.getElementById('qrcode').src = '/login/qrcode?token=' + token + '&t=' + Date.now();
+}
+refresh();
+setInterval(refresh, 3000);
+</script>
+</body></html>"#;
+
+/// 启动http接口, 可以在浏览器中打开查看登录状态并扫描二维码
+///
+/// 所有请求都需要携带token, 放在请求头 `Authorization: Bearer <token>` 或者查询参数 `?token=<token>` 中,
+/// 否则返回401, token不能为空
+///
+/// - GET /?token=<token> : 登录页面
+/// - GET /login/state : 登录状态 (json)
+/// - GET /login/qrcode : 二维码 (png), 没有二维码时404
+/// - GET /commands : 命令列表 (json)
+/// - GET /health : 版本和登录状态 (json)
+pub async fn start_http_api(addr: SocketAddr, token: &str) -> anyhow::Result<()> {
+    if token.is_empty() {
+        return Err(anyhow::anyhow!("http接口的token不能为空"));
+    }
+    let token: Arc<str> = Arc::from(token);
+    let listener = TcpListener::bind(addr).await?;
+    tracing::info!(target = "proc_qq", "http接口已启动 : http://{}", addr);
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    let token = token.clone();
+                    tokio::spawn(async move {
+                        if let Err(err) = handle_connection(stream, &token).await {
+                            tracing::debug!(target = "proc_qq", "http请求处理失败 : {:?}", err);
+                        }
+                    });
+                }
+                Err(err) => tracing::warn!(target = "proc_qq", "http接口接收连接失败 : {:?}", err),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// 只需要请求行和请求头, 超时返回错误
+async fn read_request(stream: &mut TcpStream, timeout: Duration) -> anyhow::Result<Vec<u8>> {
+    let mut buf = vec![0u8; 4096];
+    let mut len = 0;
+    tokio::time::timeout(timeout, async {
+        while !buf[..len].windows(4).any(|w| w == b"\r\n\r\n") && len < buf.len() {
+            let n = stream.read(&mut buf[len..]).await?;
+            if n == 0 {
+                break;
+            }
+            len += n;
+        }
+        anyhow::Ok(())
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("读取http请求超时"))??;
+    buf.truncate(len);
+    Ok(buf)
+}
+
+async fn handle_connection(mut stream: TcpStream, token: &str) -> anyhow::Result<()> {
+    let buf = read_request(&mut stream, REQUEST_TIMEOUT).await?;
+    let request = String::from_utf8_lossy(&buf);
+    let mut lines = request.lines();
+    let mut parts = lines.next().unwrap_or_default().split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let authorized = matches!(request_token(lines, query), Some(t) if token_eq(t, token));
+    let (status, content_type, body) = match (method, path) {
+        _ if !authorized => ("401 Unauthorized", "text/plain", b"unauthorized".to_vec()),
+        ("GET", "/") => (
+            "200 OK",
+            "text/html; charset=utf-8",
+            INDEX_HTML.as_bytes().to_vec(),
+        ),
+        ("GET", "/login/state") => (
+            "200 OK",
+            "application/json",
+            serde_json::to_vec(&login_state())?,
+        ),
+        ("GET", "/commands") => (
+            "200 OK",
+            "application/json",
+            serde_json::to_vec(&command_catalog())?,
+        ),
+        ("GET", "/health") => (
+            "200 OK",
+            "application/json",
+            serde_json::to_vec(&serde_json::json!({
+                "build": build_info(),
+                "login": login_state(),
+            }))?,
+        ),
+        ("GET", "/login/qrcode") => match login_qrcode() {
+            Some(png) => ("200 OK", "image/png", png),
+            None => ("404 Not Found", "text/plain", b"no qrcode".to_vec()),
+        },
+        _ => ("404 Not Found", "text/plain", b"not found".to_vec()),
+    };
+    let header = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    stream.write_all(header.as_bytes()).await?;
+    stream.write_all(&body).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// 请求头 Authorization: Bearer 优先, 其次是查询参数token
+fn request_token<'a>(
+    mut headers: impl Iterator<Item = &'a str>,
+    query: &'a str,
+) -> Option<&'a str> {
+    let bearer = headers.find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if !name.trim().eq_ignore_ascii_case("authorization") {
+            return None;
+        }
+        value.trim().strip_prefix("Bearer ").map(str::trim)
+    });
+    bearer.or_else(|| {
+        query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("token="))
+    })
+}
+
+// 比较时间不依赖于第一个不同的位置
+fn token_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_from_header_or_query() {
+        let headers = "Host: localhost\r\nAuthorization: Bearer secret\r\n";
+        assert_eq!(request_token(headers.lines(), ""), Some("secret"));
+        assert_eq!(
+            request_token("".lines(), "t=1&token=secret"),
+            Some("secret")
+        );
+        assert_eq!(request_token("Host: localhost".lines(), "t=1"), None);
+    }
+
+    #[test]
+    fn token_comparison() {
+        assert!(token_eq("secret", "secret"));
+        assert!(!token_eq("secret", "secreT"));
+        assert!(!token_eq("secret", "secret2"));
+        assert!(!token_eq("", "secret"));
+    }
+
+    #[tokio::test]
+    async fn empty_token_is_rejected() {
+        let addr = "127.0.0.1:0".parse().unwrap();
+        assert!(start_http_api(addr, "").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn slow_requests_time_out() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (mut server, _) = listener.accept().await.unwrap();
+        client.write_all(b"GET / HTTP/1.1\r\n").await.unwrap();
+        assert!(read_request(&mut server, Duration::from_millis(50))
+            .await
+            .is_err());
+        client.write_all(b"Host: localhost\r\n\r\n").await.unwrap();
+        let request = read_request(&mut server, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert!(request.ends_with(b"\r\n\r\n"));
+    }
+}
@@ -0,0 +1,125 @@
+use lazy_static::lazy_static;
+use rq_engine::msg::MessageChain;
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+
+use crate::{current_module_id, SeededRng};
+
+/// 发送前按照消息长度等待一段随机的时间, 模拟打字, 减少瞬间回复被判断为机器人的风险
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Humanization {
+    /// 每个字增加的延迟
+    pub per_char: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    /// 随机浮动的比例 (0~1), 0.3为 ±30%
+    pub jitter: f64,
+}
+
+impl Default for Humanization {
+    fn default() -> Self {
+        Self {
+            per_char: Duration::from_millis(150),
+            min: Duration::from_millis(500),
+            max: Duration::from_secs(5),
+            jitter: 0.3,
+        }
+    }
+}
+
+impl Humanization {
+    /// 不等待, 用于在开启了默认延迟时关闭单个模块的延迟
+    pub fn disabled() -> Self {
+        Self {
+            per_char: Duration::ZERO,
+            min: Duration::ZERO,
+            max: Duration::ZERO,
+            jitter: 0.0,
+        }
+    }
+
+    /// 发送一段长度为chars的消息之前等待的时间
+    pub fn delay(&self, chars: usize, rng: &mut SeededRng) -> Duration {
+        let base = self
+            .per_char
+            .saturating_mul(chars.min(u32::MAX as usize) as u32)
+            .clamp(self.min, self.max.max(self.min));
+        if base.is_zero() || self.jitter <= 0.0 {
+            return base;
+        }
+        let jitter = self.jitter.min(1.0);
+        // [1 - jitter, 1 + jitter)
+        let factor = 1.0 - jitter + (rng.next_u64() as f64 / u64::MAX as f64) * jitter * 2.0;
+        base.mul_f64(factor)
+    }
+}
+
+lazy_static! {
+    static ref DEFAULT_HUMANIZATION: RwLock<Option<Humanization>> = RwLock::new(None);
+    static ref MODULE_HUMANIZATION: RwLock<HashMap<String, Humanization>> =
+        RwLock::new(HashMap::new());
+    static ref HUMANIZE_RNG: Mutex<SeededRng> = Mutex::new(SeededRng::from_time());
+}
+
+/// 所有模块默认的发送延迟, None为不等待 (默认)
+pub fn set_humanization(humanization: Option<Humanization>) {
+    *DEFAULT_HUMANIZATION.write().unwrap() = humanization;
+}
+
+/// 单独设置一个模块的发送延迟, 为None时使用默认的
+pub fn set_module_humanization<S: Into<String>>(module_id: S, humanization: Option<Humanization>) {
+    let mut lock = MODULE_HUMANIZATION.write().unwrap();
+    match humanization {
+        Some(humanization) => lock.insert(module_id.into(), humanization),
+        None => lock.remove(&module_id.into()),
+    };
+}
+
+fn humanization() -> Option<Humanization> {
+    if let Some(module_id) = current_module_id() {
+        if let Some(humanization) = MODULE_HUMANIZATION.read().unwrap().get(&module_id) {
+            return Some(*humanization);
+        }
+    }
+    *DEFAULT_HUMANIZATION.read().unwrap()
+}
+
+/// 发送之前调用, 按照当前模块的设置等待
+pub(crate) async fn humanize_send(message: &MessageChain) {
+    let humanization = match humanization() {
+        Some(humanization) => humanization,
+        None => return,
+    };
+    let chars = message.to_string().chars().count();
+    let delay = humanization.delay(chars, &mut HUMANIZE_RNG.lock().unwrap());
+    if !delay.is_zero() {
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_is_clamped_and_jittered() {
+        let mut rng = SeededRng::from_key("humanize");
+        let fixed = Humanization {
+            jitter: 0.0,
+            ..Default::default()
+        };
+        assert_eq!(fixed.delay(1, &mut rng), Duration::from_millis(500));
+        assert_eq!(fixed.delay(10, &mut rng), Duration::from_millis(1500));
+        assert_eq!(fixed.delay(1000, &mut rng), Duration::from_secs(5));
+        assert_eq!(
+            Humanization::disabled().delay(1000, &mut rng),
+            Duration::ZERO
+        );
+        let humanization = Humanization::default();
+        for _ in 0..100 {
+            let delay = humanization.delay(10, &mut rng);
+            assert!(delay >= Duration::from_millis(1050) && delay < Duration::from_millis(1950));
+        }
+    }
+}
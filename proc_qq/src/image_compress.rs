@@ -0,0 +1,167 @@
+use lazy_static::lazy_static;
+use std::sync::RwLock;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{ColorType, DynamicImage, RgbImage};
+
+/// 上传前压缩图片的限制
+#[derive(Debug, Clone)]
+pub struct ImageCompressConfig {
+    /// 最大宽度, 超出按比例缩小
+    pub max_width: u32,
+    /// 最大高度, 超出按比例缩小
+    pub max_height: u32,
+    /// 最大字节数, 超出重新编码为JPEG
+    pub max_bytes: usize,
+}
+
+impl Default for ImageCompressConfig {
+    fn default() -> Self {
+        Self {
+            max_width: 2048,
+            max_height: 2048,
+            max_bytes: 2 * 1024 * 1024,
+        }
+    }
+}
+
+lazy_static! {
+    static ref IMAGE_COMPRESS_CONFIG: RwLock<ImageCompressConfig> =
+        RwLock::new(ImageCompressConfig::default());
+}
+
+pub fn set_image_compress_config(config: ImageCompressConfig) {
+    *IMAGE_COMPRESS_CONFIG.write().unwrap() = config;
+}
+
+pub fn image_compress_config() -> ImageCompressConfig {
+    IMAGE_COMPRESS_CONFIG.read().unwrap().clone()
+}
+
+/// 压缩图片, 未超出限制或无法解析时原样返回 (动图不处理以保留动画)
+pub fn compress_image(data: Vec<u8>, config: &ImageCompressConfig) -> Vec<u8> {
+    if crate::is_animated_gif(&data) || image::guess_format(&data).is_err() {
+        return data;
+    }
+    let img = match image::load_from_memory(&data) {
+        Ok(img) => img,
+        Err(err) => {
+            tracing::debug!(target = "proc_qq", "图片解析失败, 不进行压缩 : {:?}", err);
+            return data;
+        }
+    };
+    let oversize = img.width() > config.max_width || img.height() > config.max_height;
+    if !oversize && data.len() <= config.max_bytes {
+        return data;
+    }
+    let img = if oversize {
+        img.resize(config.max_width, config.max_height, FilterType::Triangle)
+    } else {
+        img
+    };
+    let rgb = flatten_alpha(&img);
+    let mut best: Option<Vec<u8>> = None;
+    for quality in [85, 70, 55, 40] {
+        let mut buf = vec![];
+        if let Err(err) = JpegEncoder::new_with_quality(&mut buf, quality).encode(
+            rgb.as_raw(),
+            rgb.width(),
+            rgb.height(),
+            ColorType::Rgb8,
+        ) {
+            tracing::debug!(target = "proc_qq", "图片编码失败 : {:?}", err);
+            break;
+        }
+        let fit = buf.len() <= config.max_bytes;
+        best = Some(buf);
+        if fit {
+            break;
+        }
+    }
+    match best {
+        Some(buf) if buf.len() < data.len() || oversize => {
+            tracing::debug!(
+                target = "proc_qq",
+                "图片已压缩 : {} -> {} bytes",
+                data.len(),
+                buf.len()
+            );
+            buf
+        }
+        _ => data,
+    }
+}
+
+/// 上传时使用全局配置压缩, 在阻塞线程中执行
+pub(crate) async fn compress_image_for_upload(data: Vec<u8>) -> Vec<u8> {
+    let config = image_compress_config();
+    let backup = data.clone();
+    tokio::task::spawn_blocking(move || compress_image(data, &config))
+        .await
+        .unwrap_or(backup)
+}
+
+/// JPEG不支持透明, 透明部分以白色填充
+fn flatten_alpha(img: &DynamicImage) -> RgbImage {
+    let rgba = img.to_rgba8();
+    let mut rgb = RgbImage::new(rgba.width(), rgba.height());
+    for (x, y, pixel) in rgba.enumerate_pixels() {
+        let [r, g, b, a] = pixel.0;
+        let blend = |c: u8| ((c as u32 * a as u32 + 255 * (255 - a as u32)) / 255) as u8;
+        rgb.put_pixel(x, y, image::Rgb([blend(r), blend(g), blend(b)]));
+    }
+    rgb
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::gif::{GifDecoder, GifEncoder};
+    use image::{AnimationDecoder, Frame, RgbaImage};
+
+    fn encode_gif(frames: usize) -> Vec<u8> {
+        let mut buf = vec![];
+        {
+            let mut encoder = GifEncoder::new(&mut buf);
+            for i in 0..frames {
+                let pixel = image::Rgba([i as u8 * 80, 0, 0, 255]);
+                let frame = Frame::new(RgbaImage::from_pixel(64, 64, pixel));
+                encoder.encode_frame(frame).unwrap();
+            }
+        }
+        buf
+    }
+
+    fn small() -> ImageCompressConfig {
+        ImageCompressConfig {
+            max_width: 16,
+            max_height: 16,
+            max_bytes: 1,
+        }
+    }
+
+    #[test]
+    fn animated_gif_round_trips() {
+        let gif = encode_gif(2);
+        assert!(crate::is_animated_gif(&gif));
+        let out = compress_image(gif.clone(), &small());
+        assert_eq!(out, gif);
+        let frames = GifDecoder::new(out.as_slice())
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        assert_eq!(frames.len(), 2);
+    }
+
+    #[test]
+    fn static_gif_is_compressed() {
+        let gif = encode_gif(1);
+        assert!(!crate::is_animated_gif(&gif));
+        let out = compress_image(gif, &small());
+        let img = image::load_from_memory(&out).unwrap();
+        assert_eq!((img.width(), img.height()), (16, 16));
+        assert!(out.starts_with(&[0xFF, 0xD8]));
+    }
+}
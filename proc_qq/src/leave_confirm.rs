@@ -0,0 +1,40 @@
+use lazy_static::lazy_static;
+use rq_engine::{RQError, RQResult};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+lazy_static! {
+    static ref LEAVE_CONFIRM_WINDOW: Mutex<Option<Duration>> = Mutex::new(None);
+    static ref PENDING_LEAVES: Mutex<HashMap<i64, Instant>> = Mutex::new(HashMap::new());
+}
+
+/// 开启后, leave_group 需要在window内对同一个群调用两次才会退群, 第一次调用返回错误
+///
+/// 用于防止有问题的模块或误操作让机器人批量退群, 默认关闭
+pub fn set_leave_confirmation(window: Option<Duration>) {
+    *LEAVE_CONFIRM_WINDOW.lock().unwrap() = window;
+    if window.is_none() {
+        PENDING_LEAVES.lock().unwrap().clear();
+    }
+}
+
+/// 第一次调用记录并返回错误, window内的第二次调用通过
+pub(crate) fn confirm_leave(group_code: i64) -> RQResult<()> {
+    let window = match *LEAVE_CONFIRM_WINDOW.lock().unwrap() {
+        Some(window) => window,
+        None => return Ok(()),
+    };
+    let now = Instant::now();
+    let mut pending = PENDING_LEAVES.lock().unwrap();
+    pending.retain(|_, time| now.duration_since(*time) < window);
+    if pending.remove(&group_code).is_some() {
+        return Ok(());
+    }
+    pending.insert(group_code, now);
+    Err(RQError::Other(format!(
+        "退出群 {} 需要确认, 请在{}秒内再次执行",
+        group_code,
+        window.as_secs()
+    )))
+}
@@ -1,12 +1,136 @@
 /// 此模块用于重新导出引入, 以便macros使用
+pub use admin_notify::*;
+pub use alert::*;
+pub use announce::*;
+pub use at_all::*;
+pub use attachment::*;
+pub use audit::*;
+pub use auto_leave::*;
+pub use bridge::*;
+pub use build_info::*;
+pub use capabilities::*;
+#[cfg(feature = "render")]
+pub use card::*;
+#[cfg(feature = "chaos")]
+pub use chaos::*;
 pub use client::*;
+pub use command_alias::*;
+pub use degradation::*;
+pub use distributed::*;
+pub use dry_run::*;
+pub use echo::*;
 pub use entities::*;
+pub use feature_flags::*;
+pub use forward::*;
+pub use framework_messages::*;
+pub use group_config::*;
+pub use group_join::*;
 pub use handler::*;
+#[cfg(feature = "http-api")]
+pub use http_api::*;
+pub use humanize::*;
+#[cfg(feature = "image_compress")]
+pub use image_compress::*;
+pub use leave_confirm::*;
+pub use login_state::*;
+pub use member_alias::*;
+pub use message_cache::*;
+pub use message_id::*;
+pub use message_json::*;
+pub use module_config::*;
+pub use module_log::*;
+pub use poller::*;
+pub use presence::*;
+pub use proc_client::*;
 pub use proc_qq_codegen::*;
+pub use profile_cache::*;
+pub use read_receipt::*;
+pub use redact::*;
+pub use roles::*;
+pub use scheduler::*;
+pub use self_test::*;
+pub use send_batch::*;
+pub use send_dedup::*;
+pub use service_message::*;
+pub use session::*;
+pub use storage::*;
+pub use synthetic::*;
+pub use target_lock::*;
+pub use templates::*;
+pub use ticket::*;
 pub use traits::*;
+pub use transfer::*;
+pub use translation::*;
+pub use utils::*;
+#[cfg(feature = "wasm-plugin")]
+pub use wasm_plugin::*;
 
+mod admin_notify;
+mod alert;
+mod announce;
+mod at_all;
+mod attachment;
+mod audit;
+mod auto_leave;
+mod bridge;
+mod build_info;
+pub mod builtin;
+mod capabilities;
+#[cfg(feature = "render")]
+mod card;
+#[cfg(feature = "chaos")]
+mod chaos;
 mod client;
+mod command_alias;
+mod degradation;
+mod distributed;
+mod dry_run;
+mod echo;
 mod entities;
+mod feature_flags;
+mod forward;
+mod framework_messages;
+mod group_config;
+mod group_join;
 mod handler;
+#[cfg(feature = "http-api")]
+mod http_api;
+mod humanize;
+#[cfg(feature = "image_compress")]
+mod image_compress;
+mod leave_confirm;
+mod login_state;
+mod member_alias;
+mod message_cache;
+mod message_id;
+mod message_json;
+pub mod moderation;
+mod module_config;
+mod module_log;
+mod poller;
+mod presence;
+mod proc_client;
+mod profile_cache;
 pub mod re_exports;
+mod read_receipt;
+mod redact;
+mod roles;
+mod scheduler;
+mod self_test;
+mod send_batch;
+mod send_dedup;
+mod service_message;
+mod session;
+mod storage;
+mod synthetic;
+mod target_lock;
+mod templates;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod ticket;
 mod traits;
+mod transfer;
+mod translation;
+mod utils;
+#[cfg(feature = "wasm-plugin")]
+mod wasm_plugin;
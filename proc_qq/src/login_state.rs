@@ -0,0 +1,46 @@
+use lazy_static::lazy_static;
+use serde_derive::Serialize;
+use std::sync::RwLock;
+
+/// 当前的登录状态, 供无界面的服务器在网页上查看和扫码
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum LoginState {
+    Offline,
+    Connecting,
+    WaitingForScan,
+    WaitingForConfirm,
+    QRCodeExpired,
+    Captcha { verify_url: String },
+    Online { uin: i64 },
+    Failed { message: String },
+}
+
+lazy_static! {
+    static ref LOGIN_STATE: RwLock<LoginState> = RwLock::new(LoginState::Offline);
+    static ref LOGIN_QRCODE: RwLock<Option<Vec<u8>>> = RwLock::new(None);
+}
+
+/// 同时登录多个账号时为最后更新的状态
+pub fn login_state() -> LoginState {
+    LOGIN_STATE.read().unwrap().clone()
+}
+
+/// 当前等待扫描的二维码 (png)
+pub fn login_qrcode() -> Option<Vec<u8>> {
+    LOGIN_QRCODE.read().unwrap().clone()
+}
+
+pub(crate) fn set_login_state(state: LoginState) {
+    if let LoginState::Online { .. } | LoginState::Failed { .. } = state {
+        *LOGIN_QRCODE.write().unwrap() = None;
+    }
+    if let LoginState::Failed { message } = &state {
+        crate::raise_alert(crate::Alert::LoginFailed(message.clone()));
+    }
+    *LOGIN_STATE.write().unwrap() = state;
+}
+
+pub(crate) fn set_login_qrcode(png: Vec<u8>) {
+    *LOGIN_QRCODE.write().unwrap() = Some(png);
+}
@@ -0,0 +1,272 @@
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use rq_engine::msg::elem::RQElem;
+use rq_engine::structs::GroupMessage;
+use rq_engine::RQResult;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use crate::ClientTrait;
+
+// 每个群最多记录的成员数量, 超出时删除最久没有发言的
+const LEARNED_LIMIT: usize = 2000;
+
+lazy_static! {
+    // (bot_uin, group_code) -> (uin -> 从消息中学到的名字)
+    static ref LEARNED_NAMES: RwLock<HashMap<(i64, i64), HashMap<i64, LearnedNames>>> =
+        RwLock::new(HashMap::new());
+    static ref INTRODUCTION: regex::Regex =
+        regex::Regex::new(r"^(?i:我是|我叫|叫我|call me )\s*(\S{1,16})$").unwrap();
+}
+
+#[derive(Debug, Clone, Default)]
+struct LearnedNames {
+    // 消息中携带的群名片, 比成员列表缓存的新
+    // rs-qq没有群名片修改的事件, 成员改名片后的第一条消息就会更新这里
+    card: Option<String>,
+    // 自我介绍 (我是张三, 叫我张三)
+    introduced: Option<String>,
+    // 最后一次发言的时间, 用于淘汰
+    time: i32,
+}
+
+// 只有整条消息是一段文字时才可能是自我介绍, 不需要把消息转换为文字
+fn introduction(message: &GroupMessage) -> Option<String> {
+    let mut elems = message
+        .elements
+        .clone()
+        .into_iter()
+        .filter(|elem| !matches!(elem, RQElem::Other(_)));
+    match (elems.next(), elems.next()) {
+        (Some(RQElem::Text(text)), None) => INTRODUCTION
+            .captures(text.content.trim())
+            .map(|captures| captures[1].to_owned()),
+        _ => None,
+    }
+}
+
+/// 收到群消息时记录群名片和自我介绍
+pub(crate) fn learn_member_names(bot_uin: i64, message: &GroupMessage) {
+    let introduced = introduction(message);
+    let card = Some(message.group_card.trim())
+        .filter(|card| !card.is_empty())
+        .map(str::to_owned);
+    if card.is_none() && introduced.is_none() {
+        return;
+    }
+    let mut names = LEARNED_NAMES.write().unwrap();
+    let group = names.entry((bot_uin, message.group_code)).or_default();
+    if !group.contains_key(&message.from_uin) && group.len() >= LEARNED_LIMIT {
+        let oldest = group
+            .iter()
+            .min_by_key(|(_, learned)| learned.time)
+            .map(|(uin, _)| *uin);
+        if let Some(oldest) = oldest {
+            group.remove(&oldest);
+        }
+    }
+    let learned = group.entry(message.from_uin).or_default();
+    learned.time = message.time;
+    if card.is_some() {
+        learned.card = card;
+    }
+    if introduced.is_some() {
+        learned.introduced = introduced;
+    }
+}
+
+/// 成员退群时删除学到的名字, 机器人自己退群时删除整个群
+pub(crate) fn forget_member_names(bot_uin: i64, group_code: i64, uin: i64) {
+    let mut names = LEARNED_NAMES.write().unwrap();
+    if uin == bot_uin {
+        names.remove(&(bot_uin, group_code));
+    } else if let Some(group) = names.get_mut(&(bot_uin, group_code)) {
+        group.remove(&uin);
+    }
+}
+
+/// find_members 的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberMatch {
+    pub uin: i64,
+    /// 匹配到的名字 (群名片, 昵称或自我介绍)
+    pub name: String,
+    /// 匹配程度, 完全相同为100, 自我介绍的匹配程度低于群名片和昵称
+    pub score: u8,
+}
+
+#[async_trait]
+pub trait MemberAliasTrait: ClientTrait {
+    /// 按照名字查找群成员, 匹配程度从高到低排序
+    ///
+    /// 名字来自缓存的成员列表 (群名片, 昵称), 群消息中的群名片和自我介绍 (我是张三), 也可以直接使用QQ号
+    async fn find_members(&self, group_code: i64, name: &str) -> RQResult<Vec<MemberMatch>> {
+        let query = normalize(name);
+        if query.is_empty() {
+            return Ok(vec![]);
+        }
+        // (名字, 是否为自我介绍)
+        let mut names: HashMap<i64, Vec<(String, bool)>> = HashMap::new();
+        let group = self.must_find_group(group_code, false).await?;
+        for member in group.members.read().await.iter() {
+            let entry = names.entry(member.uin).or_default();
+            entry.push((member.card_name.clone(), false));
+            entry.push((member.nickname.clone(), false));
+        }
+        let bot_uin = self.bot_uin().await;
+        if let Some(learned) = LEARNED_NAMES.read().unwrap().get(&(bot_uin, group_code)) {
+            for (uin, learned) in learned {
+                let entry = names.entry(*uin).or_default();
+                entry.extend(learned.card.clone().map(|card| (card, false)));
+                entry.extend(learned.introduced.clone().map(|name| (name, true)));
+            }
+        }
+        let mut matches: Vec<MemberMatch> = names
+            .into_iter()
+            .filter_map(|(uin, names)| {
+                if query == uin.to_string() {
+                    return Some(MemberMatch {
+                        uin,
+                        name: query.clone(),
+                        score: 100,
+                    });
+                }
+                names
+                    .into_iter()
+                    .filter_map(|(name, introduced)| {
+                        let score = match_score(&query, &normalize(&name), introduced)?;
+                        Some(MemberMatch { uin, name, score })
+                    })
+                    .max_by_key(|m| m.score)
+            })
+            .collect();
+        matches.sort_by(|a, b| b.score.cmp(&a.score).then(a.uin.cmp(&b.uin)));
+        Ok(matches)
+    }
+
+    /// 按照名字确定一个群成员, 用于命令的目标 ("禁言 张三"),
+    /// 没有匹配或者最佳匹配不唯一时返回None
+    async fn resolve_member(&self, group_code: i64, name: &str) -> RQResult<Option<i64>> {
+        let matches = self.find_members(group_code, name).await?;
+        Ok(match matches.as_slice() {
+            [best, second, ..] if best.score == second.score => None,
+            [best, ..] => Some(best.uin),
+            [] => None,
+        })
+    }
+}
+
+impl<C: ClientTrait> MemberAliasTrait for C {}
+
+fn normalize(name: &str) -> String {
+    name.trim().trim_start_matches('@').trim().to_lowercase()
+}
+
+// 任何人都可以自我介绍成别人的名字, 自我介绍只能完全相同或前缀匹配, 并且低于群名片和昵称的所有匹配
+fn match_score(query: &str, name: &str, introduced: bool) -> Option<u8> {
+    if name.is_empty() {
+        None
+    } else if introduced {
+        if name == query {
+            Some(30)
+        } else if name.starts_with(query) {
+            Some(20)
+        } else {
+            None
+        }
+    } else if name == query {
+        Some(100)
+    } else if name.starts_with(query) {
+        Some(80)
+    } else if name.contains(query) {
+        Some(60)
+    } else if query.chars().count() >= 3 && edit_distance(query, name) <= 1 {
+        Some(40)
+    } else {
+        None
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            current.push(
+                (previous[j] + cost)
+                    .min(previous[j + 1] + 1)
+                    .min(current[j] + 1),
+            );
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("张三丰", "张三丰"), 0);
+        assert_eq!(edit_distance("张三丰", "张二丰"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("abc", "abcd"), 1);
+    }
+
+    #[test]
+    fn match_scores() {
+        assert_eq!(match_score("张三", "张三", false), Some(100));
+        assert_eq!(match_score("张三", "张三丰", false), Some(80));
+        assert_eq!(match_score("三丰", "张三丰", false), Some(60));
+        assert_eq!(match_score("张三丰", "张二丰", false), Some(40));
+        // 太短的名字不做模糊匹配
+        assert_eq!(match_score("张三", "张二", false), None);
+        assert_eq!(match_score("张三", "", false), None);
+        assert_eq!(match_score("李四", "张三", false), None);
+    }
+
+    #[test]
+    fn introductions_rank_below_cards() {
+        let introduced = match_score("张三", "张三", true).unwrap();
+        assert!(introduced < match_score("张三丰", "张二丰", false).unwrap());
+        assert_eq!(match_score("张三", "张三丰", true), Some(20));
+        assert_eq!(match_score("三丰", "张三丰", true), None);
+    }
+
+    #[test]
+    fn normalized_names() {
+        assert_eq!(normalize(" @Alice "), "alice");
+        assert_eq!(normalize("@ 张三"), "张三");
+    }
+
+    #[test]
+    fn learned_names_are_bounded_and_keyed_by_bot() {
+        let group_code = 7370001;
+        for uin in 0..LEARNED_LIMIT as i64 + 10 {
+            learn_member_names(
+                1,
+                &GroupMessage {
+                    group_code,
+                    from_uin: uin,
+                    time: uin as i32,
+                    group_card: format!("member{}", uin),
+                    ..Default::default()
+                },
+            );
+        }
+        let names = LEARNED_NAMES.read().unwrap();
+        let group = &names[&(1, group_code)];
+        assert_eq!(group.len(), LEARNED_LIMIT);
+        // 最早发言的被淘汰
+        assert!(!group.contains_key(&0));
+        assert!(group.contains_key(&(LEARNED_LIMIT as i64 + 9)));
+        assert!(!names.contains_key(&(2, group_code)));
+    }
+}
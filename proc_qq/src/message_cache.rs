@@ -0,0 +1,105 @@
+use lazy_static::lazy_static;
+use rq_engine::msg::MessageChain;
+use rq_engine::structs::GroupMessage;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+
+use crate::MessageContent;
+
+/// 缓存的群消息
+#[derive(Debug, Clone)]
+pub struct CachedMessage {
+    pub group_code: i64,
+    pub from_uin: i64,
+    pub seqs: Vec<i32>,
+    pub rands: Vec<i32>,
+    pub time: i32,
+    pub elements: MessageChain,
+    content: MessageContent,
+}
+
+impl CachedMessage {
+    /// 消息的文字, 缓存时转换一次, 之后共享同一份
+    pub fn content(&self) -> MessageContent {
+        self.content.clone()
+    }
+}
+
+lazy_static! {
+    static ref GROUP_MESSAGES: RwLock<HashMap<i64, VecDeque<CachedMessage>>> =
+        RwLock::new(HashMap::new());
+}
+
+static CAPACITY: AtomicUsize = AtomicUsize::new(100);
+
+/// 每个群缓存的消息条数, 默认100
+pub fn set_message_cache_capacity(capacity: usize) {
+    CAPACITY.store(capacity, Ordering::Relaxed);
+}
+
+pub(crate) fn cache_group_message(message: &GroupMessage) {
+    let capacity = CAPACITY.load(Ordering::Relaxed);
+    if capacity == 0 {
+        return;
+    }
+    let content = MessageContent::from(message.elements.to_string());
+    let mut lock = GROUP_MESSAGES.write().unwrap();
+    let messages = lock.entry(message.group_code).or_default();
+    messages.push_back(CachedMessage {
+        group_code: message.group_code,
+        from_uin: message.from_uin,
+        seqs: message.seqs.clone(),
+        rands: message.rands.clone(),
+        time: message.time,
+        elements: message.elements.clone(),
+        content,
+    });
+    while messages.len() > capacity {
+        messages.pop_front();
+    }
+}
+
+/// 群中最近的n条消息, 按时间从旧到新排列
+pub fn recent_group_messages(group_code: i64, n: usize) -> Vec<CachedMessage> {
+    match GROUP_MESSAGES.read().unwrap().get(&group_code) {
+        Some(messages) => messages
+            .iter()
+            .skip(messages.len().saturating_sub(n))
+            .cloned()
+            .collect(),
+        None => vec![],
+    }
+}
+
+/// 按seq查找缓存的群消息
+pub fn find_group_message(group_code: i64, seq: i32) -> Option<CachedMessage> {
+    GROUP_MESSAGES
+        .read()
+        .unwrap()
+        .get(&group_code)?
+        .iter()
+        .rev()
+        .find(|message| message.seqs.contains(&seq))
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_is_rendered_once() {
+        let message = GroupMessage {
+            group_code: 6980001,
+            seqs: vec![1],
+            ..Default::default()
+        };
+        cache_group_message(&message);
+        let cached = find_group_message(6980001, 1).unwrap();
+        let first = cached.content();
+        let second = find_group_message(6980001, 1).unwrap().content();
+        assert_eq!(first.as_ptr(), second.as_ptr());
+        assert_eq!(first, message.elements.to_string());
+    }
+}
@@ -0,0 +1,132 @@
+use rq_engine::msg::elem::Reply;
+use rq_engine::msg::MessageChain;
+use rq_engine::structs::{FriendMessage, GroupMessage, MessageReceipt, TempMessage};
+use rs_qq::client::event::{FriendMessageEvent, GroupMessageEvent, TempMessageEvent};
+
+use crate::{CachedMessage, MessageEvent, MessageTarget, MessageTargetTrait};
+
+/// 一条消息的标识, 可以用来撤回, 引用回复, 或作为缓存的key
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MessageId {
+    /// 消息所在的会话
+    pub target: MessageTarget,
+    /// 消息的发送者
+    pub sender: i64,
+    pub seqs: Vec<i32>,
+    pub rands: Vec<i32>,
+    pub time: i64,
+}
+
+impl MessageId {
+    /// 由发送消息的回执得到, sender为机器人的QQ号
+    pub fn from_receipt(target: MessageTarget, sender: i64, receipt: &MessageReceipt) -> Self {
+        Self {
+            target,
+            sender,
+            seqs: receipt.seqs.clone(),
+            rands: receipt.rands.clone(),
+            time: receipt.time,
+        }
+    }
+
+    pub fn seq(&self) -> i32 {
+        self.seqs.first().cloned().unwrap_or_default()
+    }
+
+    /// 引用此消息的回复元素, elements为被引用消息的内容 (显示在引用框中)
+    pub fn reply(&self, elements: MessageChain) -> Reply {
+        Reply {
+            reply_seq: self.seq(),
+            sender: self.sender,
+            time: self.time as i32,
+            elements,
+        }
+    }
+
+    /// 为消息加上对此消息的引用
+    pub fn reply_to(&self, message: MessageChain) -> MessageChain {
+        let mut message = message;
+        message.with_reply(self.reply(MessageChain::default()));
+        message
+    }
+}
+
+pub trait MessageIdTrait {
+    fn message_id(&self) -> MessageId;
+}
+
+impl MessageIdTrait for GroupMessage {
+    fn message_id(&self) -> MessageId {
+        MessageId {
+            target: self.target(),
+            sender: self.from_uin,
+            seqs: self.seqs.clone(),
+            rands: self.rands.clone(),
+            time: self.time as i64,
+        }
+    }
+}
+
+impl MessageIdTrait for FriendMessage {
+    fn message_id(&self) -> MessageId {
+        MessageId {
+            target: self.target(),
+            sender: self.from_uin,
+            seqs: self.seqs.clone(),
+            rands: self.rands.clone(),
+            time: self.time as i64,
+        }
+    }
+}
+
+impl MessageIdTrait for TempMessage {
+    fn message_id(&self) -> MessageId {
+        MessageId {
+            target: self.target(),
+            sender: self.from_uin,
+            seqs: self.seqs.clone(),
+            rands: self.rands.clone(),
+            time: self.time as i64,
+        }
+    }
+}
+
+impl MessageIdTrait for GroupMessageEvent {
+    fn message_id(&self) -> MessageId {
+        self.message.message_id()
+    }
+}
+
+impl MessageIdTrait for FriendMessageEvent {
+    fn message_id(&self) -> MessageId {
+        self.message.message_id()
+    }
+}
+
+impl MessageIdTrait for TempMessageEvent {
+    fn message_id(&self) -> MessageId {
+        self.message.message_id()
+    }
+}
+
+impl MessageIdTrait for MessageEvent {
+    fn message_id(&self) -> MessageId {
+        match self {
+            MessageEvent::GroupMessage(event) => event.message_id(),
+            MessageEvent::FriendMessage(event) => event.message_id(),
+            MessageEvent::TempMessage(event) => event.message_id(),
+        }
+    }
+}
+
+impl MessageIdTrait for CachedMessage {
+    fn message_id(&self) -> MessageId {
+        MessageId {
+            target: MessageTarget::Group(self.group_code, self.from_uin),
+            sender: self.from_uin,
+            seqs: self.seqs.clone(),
+            rands: self.rands.clone(),
+            time: self.time as i64,
+        }
+    }
+}
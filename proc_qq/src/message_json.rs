@@ -0,0 +1,134 @@
+use rq_engine::msg::elem::{FingerGuessing, FlashImage, RQElem};
+use rq_engine::msg::MessageChain;
+use serde_json::{json, Value};
+
+use crate::MessageEvent;
+
+/// 导出的JSON格式版本, 字段只增不改, 有不兼容的修改时增加版本号
+pub const MESSAGE_JSON_VERSION: u32 = 1;
+
+impl MessageEvent {
+    /// 导出为稳定的JSON格式, 供外部规则引擎或脚本使用
+    ///
+    /// ```json
+    /// {
+    ///   "version": 1,
+    ///   "type": "group",
+    ///   "sender": {"uin": 123, "nick": "群名片"},
+    ///   "target": {"type": "group", "group_code": 456},
+    ///   "time": 1660000000,
+    ///   "seqs": [1], "rands": [2],
+    ///   "content": "你好",
+    ///   "elements": [{"type": "text", "text": "你好"}]
+    /// }
+    /// ```
+    pub fn to_json(&self) -> Value {
+        let (message_type, sender, target, time, seqs, rands, elements) = match self {
+            MessageEvent::GroupMessage(event) => {
+                let message = &event.message;
+                (
+                    "group",
+                    json!({"uin": message.from_uin, "nick": message.group_card}),
+                    json!({
+                        "type": "group",
+                        "group_code": message.group_code,
+                        "group_name": message.group_name,
+                    }),
+                    message.time as i64,
+                    &message.seqs,
+                    &message.rands,
+                    &message.elements,
+                )
+            }
+            MessageEvent::FriendMessage(event) => {
+                let message = &event.message;
+                (
+                    "friend",
+                    json!({"uin": message.from_uin, "nick": message.from_nick}),
+                    json!({"type": "friend", "uin": message.target}),
+                    message.time as i64,
+                    &message.seqs,
+                    &message.rands,
+                    &message.elements,
+                )
+            }
+            MessageEvent::TempMessage(event) => {
+                let message = &event.message;
+                (
+                    "temp",
+                    json!({"uin": message.from_uin, "nick": message.from_nick}),
+                    json!({"type": "temp", "group_code": message.group_code}),
+                    message.time as i64,
+                    &message.seqs,
+                    &message.rands,
+                    &message.elements,
+                )
+            }
+        };
+        json!({
+            "version": MESSAGE_JSON_VERSION,
+            "type": message_type,
+            "sender": sender,
+            "target": target,
+            "time": time,
+            "seqs": seqs,
+            "rands": rands,
+            "content": elements.to_string(),
+            "elements": message_chain_to_json(elements),
+        })
+    }
+}
+
+/// 消息元素转为JSON数组, 每个元素带有type字段, 不认识的元素为 {"type": "other"}
+pub fn message_chain_to_json(chain: &MessageChain) -> Value {
+    Value::Array(chain.clone().into_iter().map(element_to_json).collect())
+}
+
+fn element_to_json(elem: RQElem) -> Value {
+    match elem {
+        RQElem::Text(text) => json!({"type": "text", "text": text.content}),
+        RQElem::At(at) => json!({"type": "at", "target": at.target, "display": at.display}),
+        RQElem::Face(face) => json!({"type": "face", "index": face.index, "name": face.name}),
+        RQElem::MarketFace(face) => json!({"type": "market_face", "name": face.name}),
+        RQElem::Dice(dice) => json!({"type": "dice", "value": dice.value}),
+        RQElem::FingerGuessing(finger_guessing) => json!({
+            "type": "finger_guessing",
+            "value": match finger_guessing {
+                FingerGuessing::Rock => "rock",
+                FingerGuessing::Scissors => "scissors",
+                FingerGuessing::Paper => "paper",
+            },
+        }),
+        RQElem::LightApp(app) => json!({"type": "light_app", "content": app.content}),
+        RQElem::RichMsg(rich) => json!({
+            "type": "rich_msg",
+            "service_id": rich.service_id,
+            "content": rich.template1,
+        }),
+        RQElem::FriendImage(image) => json!({
+            "type": "image",
+            "url": image.url(),
+            "width": image.width,
+            "height": image.height,
+            "size": image.size,
+        }),
+        RQElem::GroupImage(image) => json!({
+            "type": "image",
+            "url": image.url(),
+            "width": image.width,
+            "height": image.height,
+            "size": image.size,
+        }),
+        RQElem::FlashImage(image) => json!({
+            "type": "flash_image",
+            "url": match image {
+                FlashImage::FriendImage(image) => image.url(),
+                FlashImage::GroupImage(image) => image.url(),
+            },
+        }),
+        RQElem::VideoFile(video) => {
+            json!({"type": "video", "name": video.name, "size": video.size})
+        }
+        _ => json!({"type": "other"}),
+    }
+}
@@ -0,0 +1,47 @@
+//! 群管理常用操作的组合
+
+use anyhow::anyhow;
+use rq_engine::msg::elem::Reply;
+use rs_qq::client::event::GroupMessageEvent;
+
+use crate::find_group_message;
+
+/// 事件消息引用的消息
+pub fn replied(event: &GroupMessageEvent) -> Option<Reply> {
+    event.message.elements.reply()
+}
+
+/// 把事件消息引用的消息设为精华
+///
+/// 被引用的消息需要在消息缓存中, 机器人需要是管理员
+pub async fn pin(event: &GroupMessageEvent) -> anyhow::Result<()> {
+    operate_essence(event, true).await
+}
+
+/// 取消事件消息引用的消息的精华
+pub async fn unpin(event: &GroupMessageEvent) -> anyhow::Result<()> {
+    operate_essence(event, false).await
+}
+
+async fn operate_essence(event: &GroupMessageEvent, flag: bool) -> anyhow::Result<()> {
+    let group_code = event.message.group_code;
+    let reply = replied(event).ok_or_else(|| anyhow!("没有引用消息"))?;
+    // 引用中没有rand, 需要从缓存中取得
+    let message = find_group_message(group_code, reply.reply_seq)
+        .ok_or_else(|| anyhow!("被引用的消息不在缓存中 : {}", reply.reply_seq))?;
+    if crate::capture_dry_run_action(if flag { "pin" } else { "unpin" }, || {
+        format!("{} {}", group_code, reply.reply_seq)
+    }) {
+        return Ok(());
+    }
+    event
+        .client
+        .operate_group_essence(
+            group_code,
+            reply.reply_seq,
+            message.rands.first().cloned().unwrap_or_default(),
+            flag,
+        )
+        .await?;
+    Ok(())
+}
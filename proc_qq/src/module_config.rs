@@ -0,0 +1,96 @@
+use lazy_static::lazy_static;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::sync::{Arc, RwLock};
+
+lazy_static! {
+    static ref MODULE_CONFIG: RwLock<Arc<Value>> = RwLock::new(Arc::new(Value::Null));
+}
+
+/// 设置模块使用的配置, 通常由 ClientBuilder::config_file 在build时加载
+pub fn set_config(config: Value) {
+    *MODULE_CONFIG.write().unwrap() = Arc::new(config);
+}
+
+pub fn config() -> Arc<Value> {
+    MODULE_CONFIG.read().unwrap().clone()
+}
+
+/// 读取配置中的一节并反序列化, section可以用 . 分隔 (如 plugins.weather)
+///
+/// 配置中没有这一节时按照空的对象解析, 带有 #[serde(default)] 的字段使用默认值
+pub fn config_section<T: DeserializeOwned>(section: &str) -> anyhow::Result<T> {
+    let config = config();
+    let value = section
+        .split('.')
+        .try_fold(config.as_ref(), |value, key| value.get(key))
+        .cloned()
+        .unwrap_or_else(|| Value::Object(Default::default()));
+    serde_json::from_value(value)
+        .map_err(|err| anyhow::Error::msg(format!("配置 {} 格式错误 : {}", section, err)))
+}
+
+/// 处理器使用的配置节 (#[config(section = "weather")]), build时检查能否解析
+pub struct ConfigSection {
+    pub section: String,
+    pub type_name: &'static str,
+    check: fn(&str) -> anyhow::Result<()>,
+}
+
+impl ConfigSection {
+    pub fn new<T: DeserializeOwned>(section: &str) -> Self {
+        Self {
+            section: section.to_owned(),
+            type_name: std::any::type_name::<T>(),
+            check: |section| config_section::<T>(section).map(|_| ()),
+        }
+    }
+
+    pub(crate) fn check(&self) -> anyhow::Result<()> {
+        (self.check)(&self.section)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_derive::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, Deserialize)]
+    struct Weather {
+        key: String,
+        #[serde(default)]
+        days: u32,
+    }
+
+    #[derive(Deserialize)]
+    struct Optional {
+        #[serde(default)]
+        enabled: bool,
+    }
+
+    // 配置是全局的, 放在同一个测试中避免并发修改
+    #[test]
+    fn reads_sections() {
+        set_config(json!({
+            "module_config_test": {
+                "weather": { "key": "abc" },
+                "broken": { "key": 1 }
+            }
+        }));
+        let weather: Weather = config_section("module_config_test.weather").unwrap();
+        assert_eq!((weather.key.as_str(), weather.days), ("abc", 0));
+        let optional: Optional = config_section("module_config_test.missing").unwrap();
+        assert!(!optional.enabled);
+        assert!(config_section::<Weather>("module_config_test.missing").is_err());
+        let err = config_section::<Weather>("module_config_test.broken").unwrap_err();
+        assert!(err.to_string().contains("module_config_test.broken"));
+        assert!(ConfigSection::new::<Weather>("module_config_test.weather")
+            .check()
+            .is_ok());
+        assert!(ConfigSection::new::<Weather>("module_config_test.broken")
+            .check()
+            .is_err());
+    }
+}
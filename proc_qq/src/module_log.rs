@@ -0,0 +1,89 @@
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+use tracing::callsite::{Callsite, Identifier};
+use tracing::field::{FieldSet, Value};
+use tracing::metadata::Kind;
+use tracing::subscriber::Interest;
+use tracing::{Event, Level, Metadata};
+
+use crate::current_module_id;
+
+/// 模块日志的target前缀, 如 proc_qq::module::rss
+pub const MODULE_TARGET_PREFIX: &str = "proc_qq::module::";
+
+// tracing的target需要是'static, 每个模块和级别创建一次, 之后一直复用
+struct ModuleCallsite {
+    metadata: OnceLock<Metadata<'static>>,
+}
+
+impl Callsite for ModuleCallsite {
+    fn set_interest(&self, _interest: Interest) {}
+
+    fn metadata(&self) -> &Metadata<'_> {
+        self.metadata.get().unwrap()
+    }
+}
+
+lazy_static! {
+    static ref CALLSITES: Mutex<HashMap<(String, Level), &'static ModuleCallsite>> =
+        Mutex::new(HashMap::new());
+}
+
+fn callsite(module_id: &str, level: Level) -> &'static ModuleCallsite {
+    let mut lock = CALLSITES.lock().unwrap();
+    if let Some(callsite) = lock.get(&(module_id.to_owned(), level)) {
+        return callsite;
+    }
+    let callsite: &'static ModuleCallsite = Box::leak(Box::new(ModuleCallsite {
+        metadata: OnceLock::new(),
+    }));
+    let target: &'static str =
+        Box::leak(format!("{}{}", MODULE_TARGET_PREFIX, module_id).into_boxed_str());
+    let _ = callsite.metadata.set(Metadata::new(
+        "module_log",
+        target,
+        level,
+        None,
+        None,
+        None,
+        FieldSet::new(&["message"], Identifier(callsite)),
+        Kind::EVENT,
+    ));
+    tracing::callsite::register(callsite);
+    lock.insert((module_id.to_owned(), level), callsite);
+    callsite
+}
+
+/// 以 proc_qq::module::<模块ID> 为target输出日志,
+/// 可以用EnvFilter单独调整某个模块的日志级别, 如 RUST_LOG=proc_qq::module::rss=debug
+pub fn module_log(module_id: &str, level: Level, message: fmt::Arguments) {
+    let metadata = callsite(module_id, level).metadata.get().unwrap();
+    if !tracing::dispatcher::get_default(|dispatch| dispatch.enabled(metadata)) {
+        return;
+    }
+    let fields = metadata.fields();
+    let field = match fields.field("message") {
+        Some(field) => field,
+        None => return,
+    };
+    let values = [(&field, Some(&message as &dyn Value))];
+    Event::dispatch(metadata, &fields.value_set(&values));
+}
+
+/// 在处理器中使用, 以当前模块的target输出日志
+///
+/// proc_qq::module_log!(Level::INFO, "收到 {} 条消息", count);
+#[macro_export]
+macro_rules! module_log {
+    ($level:expr, $($arg:tt)+) => {
+        $crate::log_current_module($level, format_args!($($arg)+))
+    };
+}
+
+/// 当前模块的日志, 不在处理器中时使用 proc_qq 作为模块ID
+pub fn log_current_module(level: Level, message: fmt::Arguments) {
+    let module_id = current_module_id().unwrap_or_else(|| "proc_qq".to_owned());
+    module_log(&module_id, level, message)
+}
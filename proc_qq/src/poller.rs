@@ -0,0 +1,180 @@
+use crate::{
+    scheduled_module, ClientTrait, Job, KvStore, MessageChainParseTrait, MessageTarget, Module,
+};
+use async_trait::async_trait;
+use rq_engine::msg::MessageChain;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 一次轮询得到的一条数据, key相同而content变化时视为更新
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PollItem {
+    pub key: String,
+    pub content: String,
+}
+
+impl PollItem {
+    pub fn new<K: Into<String>, C: Into<String>>(key: K, content: C) -> Self {
+        Self {
+            key: key.into(),
+            content: content.into(),
+        }
+    }
+}
+
+/// 轮询的数据来源
+#[async_trait]
+pub trait PollSource: Send + Sync {
+    async fn fetch(&self) -> anyhow::Result<Vec<PollItem>>;
+}
+
+#[async_trait]
+impl<F, Fut> PollSource for F
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = anyhow::Result<Vec<PollItem>>> + Send,
+{
+    async fn fetch(&self) -> anyhow::Result<Vec<PollItem>> {
+        self().await
+    }
+}
+
+/// 请求一个返回JSON的HTTP接口, 由extract从响应中取出数据
+pub struct HttpJsonSource {
+    url: String,
+    extract: Box<dyn Fn(serde_json::Value) -> Vec<PollItem> + Send + Sync>,
+}
+
+impl HttpJsonSource {
+    pub fn new<S: Into<String>, F>(url: S, extract: F) -> Self
+    where
+        F: Fn(serde_json::Value) -> Vec<PollItem> + Send + Sync + 'static,
+    {
+        Self {
+            url: url.into(),
+            extract: Box::new(extract),
+        }
+    }
+}
+
+#[async_trait]
+impl PollSource for HttpJsonSource {
+    async fn fetch(&self) -> anyhow::Result<Vec<PollItem>> {
+        let bytes = reqwest::get(&self.url)
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        Ok((self.extract)(serde_json::from_slice(&bytes)?))
+    }
+}
+
+/// 轮询到的变化
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PollChange {
+    Added,
+    Updated,
+}
+
+type Transform = dyn Fn(&PollItem, PollChange) -> Option<MessageChain> + Send + Sync;
+
+/// 定时轮询source, 把新增或变化的数据转换成消息发送到targets
+///
+/// 上一次的结果保存在Storage中, 第一次轮询只记录不发送
+pub struct Poller {
+    id: String,
+    interval: Duration,
+    source: Arc<dyn PollSource>,
+    transform: Arc<Transform>,
+    targets: Vec<MessageTarget>,
+    notify_updates: bool,
+}
+
+impl Poller {
+    pub fn new<S: PollSource + 'static>(id: &str, interval: Duration, source: S) -> Self {
+        Self {
+            id: id.to_owned(),
+            interval,
+            source: Arc::new(source),
+            transform: Arc::new(|item, _| Some(item.content.clone().parse_message_chain())),
+            targets: vec![],
+            notify_updates: true,
+        }
+    }
+
+    /// 数据转换为消息, 返回None时不发送, 默认发送content
+    pub fn transform<F>(mut self, transform: F) -> Self
+    where
+        F: Fn(&PollItem, PollChange) -> Option<MessageChain> + Send + Sync + 'static,
+    {
+        self.transform = Arc::new(transform);
+        self
+    }
+
+    pub fn target(mut self, target: MessageTarget) -> Self {
+        self.targets.push(target);
+        self
+    }
+
+    pub fn targets(mut self, targets: Vec<MessageTarget>) -> Self {
+        self.targets.extend(targets);
+        self
+    }
+
+    /// content变化时是否发送, 默认发送
+    pub fn notify_updates(mut self, notify_updates: bool) -> Self {
+        self.notify_updates = notify_updates;
+        self
+    }
+
+    /// 生成模块, 和其他模块一起注册到客户端
+    pub fn build(self) -> Module {
+        let id = format!("poller_{}", self.id);
+        scheduled_module(&id, self.interval, PollJob(self))
+    }
+}
+
+struct PollJob(Poller);
+
+#[async_trait]
+impl Job for PollJob {
+    async fn run(&self, client: &Arc<rs_qq::Client>) -> anyhow::Result<()> {
+        let poller = &self.0;
+        let items = poller.source.fetch().await?;
+        let store = KvStore::new("poller", None, None);
+        let last: Option<HashMap<String, String>> = store.get(&poller.id).await?;
+        if let Some(last) = &last {
+            for item in &items {
+                let change = match last.get(&item.key) {
+                    None => PollChange::Added,
+                    Some(content) if content != &item.content && poller.notify_updates => {
+                        PollChange::Updated
+                    }
+                    Some(_) => continue,
+                };
+                let chain = match (poller.transform)(item, change) {
+                    Some(chain) => chain,
+                    None => continue,
+                };
+                for target in &poller.targets {
+                    if let Err(err) = client.send_message_to_target(target, chain.clone()).await {
+                        tracing::warn!(
+                            target = "proc_qq",
+                            "轮询 {} 发送消息失败 : {:?}",
+                            poller.id,
+                            err
+                        );
+                    }
+                }
+            }
+        }
+        let current: HashMap<String, String> = items
+            .into_iter()
+            .map(|item| (item.key, item.content))
+            .collect();
+        store.set(&poller.id, &current).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+/// 好友的在线状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FriendPresence {
+    pub online: bool,
+    // 最后一次状态变化的时间
+    pub since: SystemTime,
+}
+
+lazy_static! {
+    static ref FRIEND_PRESENCE: RwLock<HashMap<i64, FriendPresence>> = RwLock::new(HashMap::new());
+}
+
+/// 好友最后一次上报的在线状态, 没有收到过上报时为None
+pub fn friend_presence(uin: i64) -> Option<FriendPresence> {
+    FRIEND_PRESENCE.read().unwrap().get(&uin).cloned()
+}
+
+/// 当前在线的好友
+pub fn online_friends() -> Vec<i64> {
+    FRIEND_PRESENCE
+        .read()
+        .unwrap()
+        .iter()
+        .filter(|(_, presence)| presence.online)
+        .map(|(uin, _)| *uin)
+        .collect()
+}
+
+/// 记录在线状态, 状态发生变化时返回true
+pub(crate) fn update_friend_presence(uin: i64, online: bool) -> bool {
+    let mut lock = FRIEND_PRESENCE.write().unwrap();
+    if let Some(presence) = lock.get(&uin) {
+        if presence.online == online {
+            return false;
+        }
+    }
+    lock.insert(
+        uin,
+        FriendPresence {
+            online,
+            since: SystemTime::now(),
+        },
+    );
+    true
+}
@@ -0,0 +1,40 @@
+use std::ops::Deref;
+use std::sync::Arc;
+
+use crate::ClientTrait;
+
+/// 包装 Arc<rs_qq::Client>, 实现proc_qq的所有trait
+///
+/// 在事件中和后台任务中都使用它调用, 缓存/去重/日志等行为保持一致
+#[derive(Clone)]
+pub struct ProcClient(pub Arc<rs_qq::Client>);
+
+impl ProcClient {
+    pub fn new(client: Arc<rs_qq::Client>) -> Self {
+        Self(client)
+    }
+
+    pub fn rq_client(&self) -> &Arc<rs_qq::Client> {
+        &self.0
+    }
+}
+
+impl From<Arc<rs_qq::Client>> for ProcClient {
+    fn from(client: Arc<rs_qq::Client>) -> Self {
+        Self(client)
+    }
+}
+
+impl Deref for ProcClient {
+    type Target = rs_qq::Client;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl ClientTrait for ProcClient {
+    fn protocol_client(&self) -> &rs_qq::Client {
+        &self.0
+    }
+}
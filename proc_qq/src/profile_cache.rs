@@ -0,0 +1,88 @@
+use lazy_static::lazy_static;
+use serde_derive::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// 用户的资料卡概要
+#[derive(Debug, Clone, Serialize)]
+pub struct UserProfile {
+    pub uin: i64,
+    pub nickname: String,
+    pub level: i32,
+    pub sex: u8,
+    pub age: u8,
+    pub city: String,
+    pub sign: String,
+}
+
+// 头像最多缓存的数量, 每个约几十KB
+const AVATAR_CAPACITY: usize = 200;
+
+// 缓存的时间和内容
+type Cached<T> = Mutex<HashMap<i64, (Instant, T)>>;
+
+lazy_static! {
+    static ref PROFILE_TTL: RwLock<Duration> = RwLock::new(Duration::from_secs(600));
+    static ref PROFILES: Cached<UserProfile> = Mutex::new(HashMap::new());
+    static ref AVATARS: Cached<Arc<Vec<u8>>> = Mutex::new(HashMap::new());
+}
+
+/// 资料和头像的缓存时间, 默认10分钟, 为0时不缓存
+pub fn set_profile_cache_ttl(ttl: Duration) {
+    *PROFILE_TTL.write().unwrap() = ttl;
+    PROFILES.lock().unwrap().clear();
+    AVATARS.lock().unwrap().clear();
+}
+
+fn ttl() -> Duration {
+    *PROFILE_TTL.read().unwrap()
+}
+
+pub(crate) fn cached_profile(uin: i64) -> Option<UserProfile> {
+    match PROFILES.lock().unwrap().get(&uin) {
+        Some((time, profile)) if time.elapsed() < ttl() => Some(profile.clone()),
+        _ => None,
+    }
+}
+
+pub(crate) fn cache_profile(profile: &UserProfile) {
+    if ttl().is_zero() {
+        return;
+    }
+    let mut lock = PROFILES.lock().unwrap();
+    let ttl = ttl();
+    lock.retain(|_, (time, _)| time.elapsed() < ttl);
+    lock.insert(profile.uin, (Instant::now(), profile.clone()));
+}
+
+pub(crate) fn cached_avatar(uin: i64) -> Option<Arc<Vec<u8>>> {
+    match AVATARS.lock().unwrap().get(&uin) {
+        Some((time, avatar)) if time.elapsed() < ttl() => Some(avatar.clone()),
+        _ => None,
+    }
+}
+
+pub(crate) fn cache_avatar(uin: i64, avatar: Arc<Vec<u8>>) {
+    if ttl().is_zero() {
+        return;
+    }
+    let mut lock = AVATARS.lock().unwrap();
+    let ttl = ttl();
+    lock.retain(|_, (time, _)| time.elapsed() < ttl);
+    if lock.len() >= AVATAR_CAPACITY {
+        // 移除最早缓存的头像
+        if let Some(oldest) = lock
+            .iter()
+            .min_by_key(|(_, (time, _))| *time)
+            .map(|(uin, _)| *uin)
+        {
+            lock.remove(&oldest);
+        }
+    }
+    lock.insert(uin, (Instant::now(), avatar));
+}
+
+pub(crate) fn avatar_url(uin: i64) -> String {
+    format!("https://q1.qlogo.cn/g?b=qq&nk={}&s=640", uin)
+}
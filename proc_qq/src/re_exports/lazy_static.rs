@@ -0,0 +1 @@
+pub use lazy_static::*;
@@ -1,6 +1,8 @@
 pub mod anyhow;
 pub mod async_trait;
 pub mod bytes;
+pub mod lazy_static;
+pub mod regex;
 pub mod reqwest;
 pub mod rq_engine;
 pub mod rs_qq;
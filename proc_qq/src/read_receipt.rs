@@ -0,0 +1,23 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::{ClientTrait, MessageId};
+
+static AUTO_MARK_READ: AtomicBool = AtomicBool::new(false);
+
+/// 收到的群消息和好友消息处理完之后自动标记为已读, 避免只观察不回复的账号堆积未读消息
+pub fn set_auto_mark_read(enabled: bool) {
+    AUTO_MARK_READ.store(enabled, Ordering::Relaxed);
+}
+
+pub(crate) fn auto_mark_read(client: &Arc<rs_qq::Client>, message_id: MessageId) {
+    if !AUTO_MARK_READ.load(Ordering::Relaxed) {
+        return;
+    }
+    let client = client.clone();
+    tokio::spawn(async move {
+        if let Err(err) = client.mark_read(&message_id).await {
+            tracing::debug!(target = "proc_qq", "标记已读失败 : {:?}", err);
+        }
+    });
+}
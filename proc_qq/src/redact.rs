@@ -0,0 +1,192 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use rq_engine::msg::MessageChain;
+use serde_json::Value;
+use std::sync::{Arc, RwLock};
+
+use crate::MessageTarget;
+
+lazy_static! {
+    static ref PHONE: Regex = Regex::new(r"(^|\D)(1[3-9]\d{9})(\D|$)").unwrap();
+    static ref UIN: Regex = Regex::new(r"(^|\D)([1-9]\d{4,10})(\D|$)").unwrap();
+    static ref LOG_REDACTOR: RwLock<Option<Arc<Redactor>>> = RwLock::new(None);
+}
+
+// JSON中会被当作QQ号处理的字段
+const UIN_FIELDS: [&str; 4] = ["uin", "target", "group_code", "from_uin"];
+
+/// 隐藏消息内容中的QQ号, 手机号和自定义的敏感信息, 用于日志和存档
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    mask_uins: bool,
+    mask_phones: bool,
+    patterns: Vec<Regex>,
+    mask: String,
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self {
+            mask_uins: true,
+            mask_phones: true,
+            patterns: vec![],
+            mask: "***".to_owned(),
+        }
+    }
+}
+
+impl Redactor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mask_uins(mut self, mask_uins: bool) -> Self {
+        self.mask_uins = mask_uins;
+        self
+    }
+
+    pub fn mask_phones(mut self, mask_phones: bool) -> Self {
+        self.mask_phones = mask_phones;
+        self
+    }
+
+    /// 匹配正则的内容会被替换
+    pub fn pattern(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.patterns.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// 替换敏感信息的文字, 默认为 ***
+    pub fn mask<S: Into<String>>(mut self, mask: S) -> Self {
+        self.mask = mask.into();
+        self
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        let mut text = text.to_owned();
+        for pattern in &self.patterns {
+            text = pattern.replace_all(&text, self.mask.as_str()).into_owned();
+        }
+        let replacement = format!("${{1}}{}${{3}}", self.mask.replace('$', "$$"));
+        if self.mask_phones {
+            text = replace_all_overlapping(&PHONE, &text, &replacement);
+        }
+        if self.mask_uins {
+            text = replace_all_overlapping(&UIN, &text, &replacement);
+        }
+        text
+    }
+
+    /// 只保留QQ号的首尾两位
+    pub fn redact_uin(&self, uin: i64) -> String {
+        let uin = uin.to_string();
+        if !self.mask_uins || uin.len() < 5 {
+            return uin;
+        }
+        format!("{}{}{}", &uin[..2], self.mask, &uin[uin.len() - 2..])
+    }
+
+    pub fn redact_chain(&self, chain: &MessageChain) -> String {
+        self.redact(&chain.to_string())
+    }
+
+    /// 处理JSON中所有的字符串, 以及uin/group_code等字段
+    pub fn redact_json(&self, value: &Value) -> Value {
+        match value {
+            Value::String(text) => Value::String(self.redact(text)),
+            Value::Array(values) => {
+                Value::Array(values.iter().map(|value| self.redact_json(value)).collect())
+            }
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(key, value)| {
+                        let value = match value.as_i64() {
+                            Some(uin) if UIN_FIELDS.contains(&key.as_str()) => {
+                                Value::String(self.redact_uin(uin))
+                            }
+                            _ => self.redact_json(value),
+                        };
+                        (key.clone(), value)
+                    })
+                    .collect(),
+            ),
+            value => value.clone(),
+        }
+    }
+}
+
+// 相邻的两个号码共用分隔符, 第一次只能替换其中一个, 需要再替换一次
+fn replace_all_overlapping(regex: &Regex, text: &str, replacement: &str) -> String {
+    let text = regex.replace_all(text, replacement);
+    regex.replace_all(&text, replacement).into_owned()
+}
+
+/// 设置框架日志使用的Redactor, 未设置时日志中为原始内容
+pub fn set_log_redactor(redactor: Redactor) {
+    *LOG_REDACTOR.write().unwrap() = Some(Arc::new(redactor));
+}
+
+pub fn log_redactor() -> Option<Arc<Redactor>> {
+    LOG_REDACTOR.read().unwrap().clone()
+}
+
+pub(crate) fn log_uin(uin: i64) -> String {
+    match log_redactor() {
+        Some(redactor) => redactor.redact_uin(uin),
+        None => uin.to_string(),
+    }
+}
+
+pub(crate) fn log_target(target: &MessageTarget) -> String {
+    match *target {
+        MessageTarget::Group(group_code, uin) => format!("Group({}, {})", group_code, log_uin(uin)),
+        MessageTarget::Private(uin) => format!("Private({})", log_uin(uin)),
+        MessageTarget::Temp(group_code, uin) => format!("Temp({:?}, {})", group_code, log_uin(uin)),
+    }
+}
+
+pub(crate) fn log_text(text: &str) -> String {
+    match log_redactor() {
+        Some(redactor) => redactor.redact(text),
+        None => text.to_owned(),
+    }
+}
+
+pub(crate) fn log_chain(chain: &MessageChain) -> String {
+    match log_redactor() {
+        Some(redactor) => redactor.redact_chain(chain),
+        None => chain.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn redacts_text() {
+        let redactor = Redactor::new().pattern("密码\\S+").unwrap();
+        assert_eq!(
+            redactor.redact("12345678 和 13812345678 ,密码abc"),
+            "*** 和 *** ,***"
+        );
+        // 相邻的号码都会被替换
+        assert_eq!(redactor.redact("12345,67890"), "***,***");
+        assert_eq!(redactor.redact("1234 ¥99"), "1234 ¥99");
+        let keep = Redactor::new().mask_uins(false).mask("[x]");
+        assert_eq!(keep.redact("12345678 13812345678"), "12345678 [x]");
+    }
+
+    #[test]
+    fn redacts_json() {
+        let redactor = Redactor::new();
+        assert_eq!(redactor.redact_uin(123456789), "12***89");
+        assert_eq!(redactor.redact_uin(1234), "1234");
+        let value = json!({"uin": 123456789, "count": 123456789, "text": ["QQ 123456789"]});
+        assert_eq!(
+            redactor.redact_json(&value),
+            json!({"uin": "12***89", "count": 123456789, "text": ["QQ ***"]})
+        );
+    }
+}
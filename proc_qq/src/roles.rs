@@ -0,0 +1,53 @@
+use async_trait::async_trait;
+use rq_engine::structs::GroupMemberPermission;
+use rq_engine::RQResult;
+use serde_derive::Serialize;
+
+use crate::{ClientTrait, GroupTrait};
+
+/// 群成员的身份, 可以比较大小 (Owner > Admin > Member)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub enum GroupRole {
+    Member,
+    Admin,
+    Owner,
+}
+
+impl GroupRole {
+    /// 群主或管理员
+    pub fn is_admin(&self) -> bool {
+        *self >= GroupRole::Admin
+    }
+
+    pub fn is_owner(&self) -> bool {
+        *self == GroupRole::Owner
+    }
+}
+
+impl From<&GroupMemberPermission> for GroupRole {
+    fn from(permission: &GroupMemberPermission) -> Self {
+        match permission {
+            GroupMemberPermission::Owner => GroupRole::Owner,
+            GroupMemberPermission::Administrator => GroupRole::Admin,
+            _ => GroupRole::Member,
+        }
+    }
+}
+
+#[async_trait]
+pub trait RoleTrait: ClientTrait {
+    /// 群成员的身份, 使用缓存的群成员列表, 不会请求服务器
+    async fn member_role(&self, group_code: i64, uin: i64) -> RQResult<GroupRole> {
+        let group = self.must_find_group(group_code, false).await?;
+        let member = group.must_find_member(uin).await?;
+        Ok(GroupRole::from(&member.permission))
+    }
+
+    /// 机器人在群中的身份, 用于在执行管理操作之前检查
+    async fn bot_group_role(&self, group_code: i64) -> RQResult<GroupRole> {
+        let bot_uin = self.bot_uin().await;
+        self.member_role(group_code, bot_uin).await
+    }
+}
+
+impl<C: ClientTrait> RoleTrait for C {}
@@ -0,0 +1,294 @@
+use crate::{
+    module, ClientTrait, ConnectedAndOnlineEvent, ConnectedAndOnlineEventProcess, KvStore,
+    MessageChainParseTrait, MessageTarget, Module, ModuleEventHandler, ModuleEventProcess,
+};
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+
+// 定时任务的上次执行时间和待发送的消息保存在存储中, 重启后恢复
+const SCHEDULER_STORE: &str = "_scheduler";
+
+fn scheduler_store() -> KvStore {
+    KvStore::new(SCHEDULER_STORE, None, None)
+}
+
+fn unix_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+/// 定时执行的任务
+#[async_trait]
+pub trait Job: Send + Sync {
+    async fn run(&self, client: &Arc<rs_qq::Client>) -> anyhow::Result<()>;
+}
+
+#[async_trait]
+impl<F, Fut> Job for F
+where
+    F: Fn(Arc<rs_qq::Client>) -> Fut + Send + Sync,
+    Fut: Future<Output = anyhow::Result<()>> + Send,
+{
+    async fn run(&self, client: &Arc<rs_qq::Client>) -> anyhow::Result<()> {
+        self(client.clone()).await
+    }
+}
+
+struct Scheduled {
+    name: String,
+    interval: Duration,
+    job: Arc<dyn Job>,
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+#[async_trait]
+impl ConnectedAndOnlineEventProcess for Scheduled {
+    async fn handle(&self, event: &ConnectedAndOnlineEvent) -> anyhow::Result<bool> {
+        let client = event.client.clone();
+        let job = self.job.clone();
+        let name = self.name.clone();
+        let interval = self.interval;
+        let last_run_key = format!("last_run:{}", name);
+        // 从上次执行的时间继续计算, 重启或重连后不会提前执行
+        let last_run: Option<i64> = scheduler_store().get(&last_run_key).await.unwrap_or(None);
+        let elapsed = last_run
+            .map(|last_run| Duration::from_millis((unix_millis() - last_run).max(0) as u64))
+            .unwrap_or(interval);
+        let start = tokio::time::Instant::now() + interval.saturating_sub(elapsed);
+        let handle = crate::spawn_with_actor(async move {
+            let mut ticker = tokio::time::interval_at(start, interval);
+            // 执行时间超过间隔时不补执行
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                if let Err(err) = scheduler_store().set(&last_run_key, &unix_millis()).await {
+                    tracing::debug!(
+                        target = "proc_qq",
+                        "保存定时任务 {} 的执行时间失败 : {:?}",
+                        name,
+                        err
+                    );
+                }
+                if let Err(err) = job.run(&client).await {
+                    tracing::warn!(target = "proc_qq", "定时任务 {} 出现错误 : {:?}", name, err);
+                }
+            }
+        });
+        // 重新连接后使用新的客户端, 停止上一次的任务
+        if let Some(old) = self.handle.lock().unwrap().replace(handle) {
+            old.abort();
+        }
+        Ok(false)
+    }
+}
+
+impl Into<ModuleEventHandler> for Scheduled {
+    fn into(self) -> ModuleEventHandler {
+        ModuleEventHandler::new(
+            self.name.clone(),
+            ModuleEventProcess::ConnectedAndOnline(Box::new(self)),
+        )
+    }
+}
+
+/// 定时任务模块, 机器人上线后每隔interval执行一次job
+///
+/// 上次执行的时间保存在存储中, 从未执行过或者已经超过interval时上线立即执行一次, 否则等到间隔满后执行
+pub fn scheduled_module<J: Job + 'static>(id: &str, interval: Duration, job: J) -> Module {
+    module!(
+        id,
+        id,
+        Scheduled {
+            name: id.to_owned(),
+            interval,
+            job: Arc::new(job),
+            handle: Mutex::new(None),
+        }
+    )
+}
+
+lazy_static! {
+    static ref ONCE_TIMERS: Mutex<HashMap<String, JoinHandle<()>>> = Mutex::new(HashMap::new());
+}
+
+/// delay之后执行一次, 相同的key会取消之前还没有执行的任务
+pub(crate) fn schedule_once<F>(key: String, delay: Duration, fut: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let mut timers = ONCE_TIMERS.lock().unwrap();
+    timers.retain(|_, handle| !handle.is_finished());
+    let task_key = key.clone();
+    let handle = crate::spawn_with_actor(async move {
+        tokio::time::sleep(delay).await;
+        ONCE_TIMERS.lock().unwrap().remove(&task_key);
+        fut.await
+    });
+    if let Some(old) = timers.insert(key, handle) {
+        old.abort();
+    }
+}
+
+/// 取消还没有执行的任务, 返回是否有被取消的任务
+pub(crate) fn cancel_once(key: &str) -> bool {
+    match ONCE_TIMERS.lock().unwrap().remove(key) {
+        Some(handle) => {
+            let pending = !handle.is_finished();
+            handle.abort();
+            pending
+        }
+        None => false,
+    }
+}
+
+/// 持久化的定时发送, 保存在存储中, 重启后上线时恢复
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledSend {
+    pub id: String,
+    pub target: MessageTarget,
+    /// 消息内容, 发送时使用 parse_message_chain 解析
+    pub message: String,
+    /// 发送时间 (毫秒时间戳)
+    pub due_at: i64,
+}
+
+static SEND_SEQ: AtomicU64 = AtomicU64::new(0);
+
+fn send_key(id: &str) -> String {
+    format!("send:{}", id)
+}
+
+/// delay之后向target发送message, 返回任务ID, 可以用于取消
+///
+/// 任务保存在存储中 (set_storage), 使用持久化的存储时重启后仍然会发送, 离线期间到期的任务在上线后立即发送
+pub async fn schedule_send<S: Into<String>>(
+    client: &Arc<rs_qq::Client>,
+    target: MessageTarget,
+    message: S,
+    delay: Duration,
+) -> anyhow::Result<String> {
+    let now = unix_millis();
+    let send = ScheduledSend {
+        id: format!("{}-{}", now, SEND_SEQ.fetch_add(1, Ordering::Relaxed)),
+        target,
+        message: message.into(),
+        due_at: now + delay.as_millis() as i64,
+    };
+    scheduler_store().set(&send_key(&send.id), &send).await?;
+    let id = send.id.clone();
+    arm_send(client.clone(), send);
+    Ok(id)
+}
+
+/// 取消还没有发送的任务, 返回任务是否存在
+pub async fn cancel_scheduled_send(id: &str) -> anyhow::Result<bool> {
+    let key = send_key(id);
+    cancel_once(&key);
+    let store = scheduler_store();
+    let exists = store.get::<ScheduledSend>(&key).await?.is_some();
+    store.remove(&key).await?;
+    Ok(exists)
+}
+
+/// 所有还没有发送的任务, 按照发送时间排序
+pub async fn scheduled_sends() -> anyhow::Result<Vec<ScheduledSend>> {
+    let store = scheduler_store();
+    let mut sends = vec![];
+    for key in store.keys().await? {
+        if !key.starts_with("send:") {
+            continue;
+        }
+        if let Some(send) = store.get::<ScheduledSend>(&key).await? {
+            sends.push(send);
+        }
+    }
+    sends.sort_by_key(|send| send.due_at);
+    Ok(sends)
+}
+
+/// 上线时 (包括重连) 使用新的客户端重新设置所有未发送的任务
+pub(crate) async fn resume_scheduled_sends(client: &Arc<rs_qq::Client>) {
+    let sends = match scheduled_sends().await {
+        Ok(sends) => sends,
+        Err(err) => {
+            tracing::warn!(target = "proc_qq", "读取定时发送任务失败 : {:?}", err);
+            return;
+        }
+    };
+    if !sends.is_empty() {
+        tracing::info!(target = "proc_qq", "恢复 {} 个定时发送任务", sends.len());
+    }
+    for send in sends {
+        arm_send(client.clone(), send);
+    }
+}
+
+fn arm_send(client: Arc<rs_qq::Client>, send: ScheduledSend) {
+    let key = send_key(&send.id);
+    let delay = Duration::from_millis((send.due_at - unix_millis()).max(0) as u64);
+    schedule_once(key.clone(), delay, async move {
+        let chain = send.message.parse_message_chain();
+        if let Err(err) = client.send_message_to_target(&send.target, chain).await {
+            tracing::warn!(
+                target = "proc_qq",
+                "定时发送 {} 失败 ({}) : {:?}",
+                send.id,
+                crate::log_target(&send.target),
+                err
+            );
+        }
+        if let Err(err) = scheduler_store().remove(&key).await {
+            tracing::warn!(
+                target = "proc_qq",
+                "删除定时发送任务 {} 失败 : {:?}",
+                send.id,
+                err
+            );
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[tokio::test]
+    async fn once_timers_replace_and_cancel() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        for _ in 0..3 {
+            let runs = runs.clone();
+            schedule_once(
+                "scheduler_test".to_owned(),
+                Duration::from_millis(20),
+                async move {
+                    runs.fetch_add(1, Ordering::SeqCst);
+                },
+            );
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        // 相同的key只执行最后一次
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert!(!cancel_once("scheduler_test"));
+        let cancelled = runs.clone();
+        schedule_once(
+            "scheduler_test".to_owned(),
+            Duration::from_millis(20),
+            async move {
+                cancelled.fetch_add(1, Ordering::SeqCst);
+            },
+        );
+        assert!(cancel_once("scheduler_test"));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+}
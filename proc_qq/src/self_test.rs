@@ -0,0 +1,135 @@
+use serde_derive::Serialize;
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Instant;
+
+use crate::{Client, ClientTrait, MessageChainParseTrait, MessageTarget};
+
+/// 自检的选项
+#[derive(Debug, Clone)]
+pub struct SelfTestOptions {
+    /// 向这个QQ号发送一条消息, 检查发送是否正常 (需要是好友)
+    pub echo_to: Option<i64>,
+    pub echo_text: String,
+}
+
+impl Default for SelfTestOptions {
+    fn default() -> Self {
+        Self {
+            echo_to: None,
+            echo_text: "proc_qq 自检消息".to_owned(),
+        }
+    }
+}
+
+/// 一项检查的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestCheck {
+    pub name: String,
+    pub ok: bool,
+    // 成功时为概要, 失败时为错误信息
+    pub detail: String,
+    pub latency_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestReport {
+    pub uin: i64,
+    pub checks: Vec<SelfTestCheck>,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.ok)
+    }
+
+    pub fn failures(&self) -> Vec<&SelfTestCheck> {
+        self.checks.iter().filter(|c| !c.ok).collect()
+    }
+
+    /// 适合打印到日志的文字
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![format!(
+            "自检 (UIN={}) : {}",
+            crate::log_uin(self.uin),
+            if self.passed() { "通过" } else { "失败" }
+        )];
+        for check in &self.checks {
+            lines.push(format!(
+                " - [{}] {} ({}ms) : {}",
+                if check.ok { "OK" } else { "FAIL" },
+                check.name,
+                check.latency_ms,
+                check.detail
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+async fn check<F, T, E>(name: &str, f: F) -> SelfTestCheck
+where
+    F: Future<Output = Result<T, E>>,
+    T: Display,
+    E: std::fmt::Debug,
+{
+    let start = Instant::now();
+    let result = f.await;
+    let latency_ms = start.elapsed().as_millis() as u64;
+    let (ok, detail) = match result {
+        Ok(detail) => (true, detail.to_string()),
+        Err(err) => (false, format!("{:?}", err)),
+    };
+    SelfTestCheck {
+        name: name.to_owned(),
+        ok,
+        detail,
+        latency_ms,
+    }
+}
+
+impl Client {
+    /// 登录之后检查基本的功能是否正常 (好友列表, 群列表, 自己的资料, 发送消息),
+    /// 用于尽早发现token失效或协议变化, 失败时发出告警
+    pub async fn self_test(&self, options: SelfTestOptions) -> SelfTestReport {
+        let uin = self.bot_uin().await;
+        let mut checks = vec![
+            check("friend_list", async {
+                self.friend_list(true)
+                    .await
+                    .map(|friends| format!("{} 个好友", friends.len()))
+            })
+            .await,
+            check("group_list", async {
+                self.rq_client
+                    .get_group_list()
+                    .await
+                    .map(|groups| format!("{} 个群", groups.len()))
+            })
+            .await,
+            check("profile", async {
+                self.user_profile(uin).await.map(|profile| profile.nickname)
+            })
+            .await,
+        ];
+        if let Some(echo_to) = options.echo_to {
+            checks.push(
+                check("echo", async {
+                    self.send_message_to_target(
+                        &MessageTarget::Private(echo_to),
+                        options.echo_text.clone().parse_message_chain(),
+                    )
+                    .await
+                    .map(|receipt| format!("seqs={:?}", receipt.seqs))
+                })
+                .await,
+            );
+        }
+        let report = SelfTestReport { uin, checks };
+        if !report.passed() {
+            let failures: Vec<&str> = report.failures().iter().map(|c| c.name.as_str()).collect();
+            crate::raise_alert(crate::Alert::SelfTestFailed(failures.join(",")));
+        }
+        report
+    }
+}
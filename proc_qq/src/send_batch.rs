@@ -0,0 +1,222 @@
+use lazy_static::lazy_static;
+use rq_engine::msg::elem::{RQElem, Text};
+use rq_engine::msg::MessageChain;
+use rq_engine::structs::MessageReceipt;
+use rq_engine::{RQError, RQResult};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+use crate::MessageTarget;
+
+/// 合并短时间内发送到同一个目标的消息
+#[derive(Debug, Clone)]
+pub struct SendBatching {
+    /// 第一条消息等待的时间, 期间发送到同一目标的消息会合并到一起
+    pub window: Duration,
+    /// 合并的消息之间插入的文字
+    pub separator: String,
+}
+
+impl SendBatching {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            separator: "\n".to_owned(),
+        }
+    }
+}
+
+type Waiter = oneshot::Sender<RQResult<MessageReceipt>>;
+
+struct Batch {
+    chain: MessageChain,
+    waiters: Vec<Waiter>,
+    // 提前结束等待, 把合并的消息交给发送的调用
+    flush: oneshot::Sender<(MessageChain, Vec<Waiter>)>,
+}
+
+lazy_static! {
+    static ref SEND_BATCHING: Mutex<Option<SendBatching>> = Mutex::new(None);
+    static ref BATCHES: Mutex<HashMap<MessageTarget, Batch>> = Mutex::new(HashMap::new());
+}
+
+/// 开启后处理器连续发送的多条消息合并为一条, 减少消息数量和频率限制的压力, 默认关闭
+///
+/// 第一条消息会延迟window后发送
+pub fn set_send_batching(batching: Option<SendBatching>) {
+    *SEND_BATCHING.lock().unwrap() = batching;
+}
+
+pub(crate) enum Batched {
+    /// 由这次调用发送合并后的消息, 发送后把结果交给其他调用
+    Send(MessageChain, Vec<Waiter>),
+    /// 已经合并到其他调用中, 为那次发送的结果
+    Merged(RQResult<MessageReceipt>),
+}
+
+// 卡片等消息只能单独发送, 回复的消息合并后引用会落到其他消息上, 也单独发送
+fn standalone(chain: &MessageChain) -> bool {
+    chain.reply().is_some()
+        || chain.clone().into_iter().any(|elem| {
+            matches!(
+                elem,
+                RQElem::LightApp(_) | RQElem::RichMsg(_) | RQElem::VideoFile(_)
+            )
+        })
+}
+
+// 立即发送目标等待中的合并消息, 发送完成后返回, 保证之后单独发送的消息在它后面
+async fn flush_batch(target: &MessageTarget) {
+    let receiver = {
+        let mut batches = BATCHES.lock().unwrap();
+        match batches.remove(target) {
+            Some(mut batch) => {
+                let (sender, receiver) = oneshot::channel();
+                batch.waiters.push(sender);
+                let _ = batch.flush.send((batch.chain, batch.waiters));
+                Some(receiver)
+            }
+            None => None,
+        }
+    };
+    if let Some(receiver) = receiver {
+        let _ = receiver.await;
+    }
+}
+
+enum Joined {
+    /// 合并到了等待中的消息, 等待那次发送的结果
+    Merged(oneshot::Receiver<RQResult<MessageReceipt>>),
+    /// 第一条消息, 等待window或者被提前发送
+    First(oneshot::Receiver<(MessageChain, Vec<Waiter>)>),
+}
+
+pub(crate) async fn batch_send(target: &MessageTarget, message: MessageChain) -> Batched {
+    let batching = match SEND_BATCHING.lock().unwrap().clone() {
+        Some(batching) => batching,
+        None => return Batched::Send(message, vec![]),
+    };
+    if standalone(&message) {
+        flush_batch(target).await;
+        return Batched::Send(message, vec![]);
+    }
+    // 已经有等待中的消息时合并进去, 否则由这次调用发送
+    let joined = {
+        let mut batches = BATCHES.lock().unwrap();
+        match batches.get_mut(target) {
+            Some(batch) => {
+                batch.chain.push(Text::new(batching.separator.clone()));
+                batch.chain.0.extend(message.0);
+                let (sender, receiver) = oneshot::channel();
+                batch.waiters.push(sender);
+                Joined::Merged(receiver)
+            }
+            None => {
+                let (flush, flushed) = oneshot::channel();
+                batches.insert(
+                    *target,
+                    Batch {
+                        chain: message,
+                        waiters: vec![],
+                        flush,
+                    },
+                );
+                Joined::First(flushed)
+            }
+        }
+    };
+    match joined {
+        Joined::Merged(receiver) => Batched::Merged(
+            receiver
+                .await
+                .unwrap_or_else(|_| Err(RQError::Other("合并的消息没有发送".to_owned()))),
+        ),
+        Joined::First(mut flushed) => {
+            tokio::select! {
+                _ = tokio::time::sleep(batching.window) => (),
+                // 有单独发送的消息, 提前发送
+                Ok((chain, waiters)) = &mut flushed => return Batched::Send(chain, waiters),
+            }
+            let batch = BATCHES.lock().unwrap().remove(target);
+            match batch {
+                Some(batch) => Batched::Send(batch.chain, batch.waiters),
+                // 等待结束时刚好被提前发送
+                None => match flushed.await {
+                    Ok((chain, waiters)) => Batched::Send(chain, waiters),
+                    Err(_) => Batched::Merged(Err(RQError::Other("消息合并失败".to_owned()))),
+                },
+            }
+        }
+    }
+}
+
+/// 合并的消息共用同一个回执
+pub(crate) fn finish_batch(waiters: Vec<Waiter>, result: &RQResult<MessageReceipt>) {
+    for waiter in waiters {
+        let _ = waiter.send(match result {
+            Ok(receipt) => Ok(receipt.clone()),
+            Err(err) => Err(RQError::Other(format!("{:?}", err))),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 测试并行执行, 共用同一个设置
+    const WINDOW: Duration = Duration::from_millis(300);
+
+    fn text(content: &str) -> MessageChain {
+        MessageChain::new(Text::new(content.to_owned()))
+    }
+
+    #[tokio::test]
+    async fn messages_are_merged() {
+        set_send_batching(Some(SendBatching::new(WINDOW)));
+        let target = MessageTarget::Private(7170001);
+        let first = tokio::spawn(async move { batch_send(&target, text("a")).await });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let second = tokio::spawn(async move { batch_send(&target, text("b")).await });
+        let (chain, waiters) = match first.await.unwrap() {
+            Batched::Send(chain, waiters) => (chain, waiters),
+            Batched::Merged(_) => panic!("first message should send the batch"),
+        };
+        assert_eq!(chain, {
+            let mut chain = text("a");
+            chain.push(Text::new("\n".to_owned()));
+            chain.push(Text::new("b".to_owned()));
+            chain
+        });
+        let receipt = MessageReceipt::default();
+        finish_batch(waiters, &Ok(receipt.clone()));
+        assert!(matches!(second.await.unwrap(), Batched::Merged(Ok(r)) if r == receipt));
+    }
+
+    #[tokio::test]
+    async fn flush_sends_pending_batch_first() {
+        set_send_batching(Some(SendBatching::new(WINDOW)));
+        let target = MessageTarget::Private(7170002);
+        let first = tokio::spawn(async move { batch_send(&target, text("a")).await });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let flush = tokio::spawn(async move { flush_batch(&target).await });
+        let waiters = match tokio::time::timeout(WINDOW / 3, first)
+            .await
+            .expect("pending batch should be sent without waiting for the window")
+            .unwrap()
+        {
+            Batched::Send(chain, waiters) => {
+                assert_eq!(chain, text("a"));
+                waiters
+            }
+            Batched::Merged(_) => panic!("first message should send the batch"),
+        };
+        // 合并的消息发送完成之前, 单独发送的消息不能发出
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!flush.is_finished());
+        finish_batch(waiters, &Ok(MessageReceipt::default()));
+        flush.await.unwrap();
+    }
+}
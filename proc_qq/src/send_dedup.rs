@@ -0,0 +1,86 @@
+use lazy_static::lazy_static;
+use rq_engine::msg::MessageChain;
+use rq_engine::structs::MessageReceipt;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::{MessageChainTrait, MessageTarget};
+
+// 被去重的消息的回执中的time, 正常的回执不会是负数
+pub(crate) const DEDUPLICATED_TIME: i64 = -1;
+
+lazy_static! {
+    static ref DEDUP_WINDOW: Mutex<Option<Duration>> = Mutex::new(None);
+    static ref RECENT_SENDS: Mutex<HashMap<(MessageTarget, u64), Instant>> =
+        Mutex::new(HashMap::new());
+}
+
+/// 在window内向同一个目标发送内容相同 (content_hash) 的消息时不再发送, 返回被去重的回执 (is_deduplicated)
+///
+/// 用于避免重复触发导致的刷屏, 默认关闭
+pub fn set_send_dedup_window(window: Option<Duration>) {
+    *DEDUP_WINDOW.lock().unwrap() = window;
+    if window.is_none() {
+        RECENT_SENDS.lock().unwrap().clear();
+    }
+}
+
+/// 记录将要发送的消息, 窗口内已经发送过时返回false
+pub(crate) fn reserve_send(target: &MessageTarget, chain: &MessageChain) -> bool {
+    let window = match *DEDUP_WINDOW.lock().unwrap() {
+        Some(window) => window,
+        None => return true,
+    };
+    let now = Instant::now();
+    let mut lock = RECENT_SENDS.lock().unwrap();
+    lock.retain(|_, time| now.duration_since(*time) < window);
+    let key = (*target, chain.content_hash());
+    if lock.contains_key(&key) {
+        return false;
+    }
+    lock.insert(key, now);
+    true
+}
+
+/// 发送失败或需要重发时移除记录
+pub(crate) fn release_send(target: &MessageTarget, chain: &MessageChain) {
+    if DEDUP_WINDOW.lock().unwrap().is_none() {
+        return;
+    }
+    RECENT_SENDS
+        .lock()
+        .unwrap()
+        .remove(&(*target, chain.content_hash()));
+}
+
+pub(crate) fn deduplicated_receipt() -> MessageReceipt {
+    MessageReceipt {
+        time: DEDUPLICATED_TIME,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 窗口是全局的, 放在同一个测试中避免并发修改
+    #[test]
+    fn deduplicates_within_window() {
+        let chain = MessageChain::default();
+        let target = MessageTarget::Private(7_000_691);
+        assert!(reserve_send(&target, &chain));
+        assert!(reserve_send(&target, &chain));
+        set_send_dedup_window(Some(Duration::from_millis(30)));
+        assert!(reserve_send(&target, &chain));
+        assert!(!reserve_send(&target, &chain));
+        assert!(reserve_send(&MessageTarget::Private(7_000_692), &chain));
+        release_send(&target, &chain);
+        assert!(reserve_send(&target, &chain));
+        std::thread::sleep(Duration::from_millis(40));
+        assert!(reserve_send(&target, &chain));
+        set_send_dedup_window(None);
+        assert!(reserve_send(&target, &chain));
+    }
+}
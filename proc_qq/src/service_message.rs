@@ -0,0 +1,137 @@
+use rq_engine::msg::elem::RQElem;
+use rq_engine::msg::MessageChain;
+
+/// 收到的富文本服务消息 (XML卡片 / JSON小程序)
+#[derive(Debug, Clone)]
+pub enum ServiceMessage {
+    Xml {
+        service_id: i32,
+        content: String,
+    },
+    Json(serde_json::Value),
+    /// JSON解析失败时保留原文
+    RawJson(String),
+}
+
+/// 群公告 (com.tencent.mannounce)
+#[derive(Debug, Clone, Default)]
+pub struct GroupAnnouncement {
+    pub title: String,
+    pub text: String,
+}
+
+impl ServiceMessage {
+    pub fn from_elem(elem: RQElem) -> Option<Self> {
+        match elem {
+            RQElem::RichMsg(rich) => Some(ServiceMessage::Xml {
+                service_id: rich.service_id,
+                content: rich.template1,
+            }),
+            RQElem::LightApp(app) => Some(match serde_json::from_str(&app.content) {
+                Ok(value) => ServiceMessage::Json(value),
+                Err(_) => ServiceMessage::RawJson(app.content),
+            }),
+            _ => None,
+        }
+    }
+
+    /// JSON消息的app字段, 如 com.tencent.mannounce
+    pub fn app(&self) -> Option<&str> {
+        match self {
+            ServiceMessage::Json(value) => value.get("app")?.as_str(),
+            _ => None,
+        }
+    }
+
+    /// 消息列表中显示的摘要, JSON为prompt字段, XML为brief属性
+    pub fn prompt(&self) -> Option<String> {
+        match self {
+            ServiceMessage::Json(value) => value.get("prompt")?.as_str().map(str::to_owned),
+            ServiceMessage::Xml { .. } => self.xml_attribute("brief"),
+            ServiceMessage::RawJson(_) => None,
+        }
+    }
+
+    /// 按JSON Pointer取值, 如 "/meta/news/title"
+    pub fn json_pointer(&self, pointer: &str) -> Option<&serde_json::Value> {
+        match self {
+            ServiceMessage::Json(value) => value.pointer(pointer),
+            _ => None,
+        }
+    }
+
+    /// XML中第一个名为name的属性值
+    pub fn xml_attribute(&self, name: &str) -> Option<String> {
+        let content = match self {
+            ServiceMessage::Xml { content, .. } => content,
+            _ => return None,
+        };
+        let pattern = format!(" {}=\"", name);
+        let start = content.find(&pattern)? + pattern.len();
+        let end = content[start..].find('"')? + start;
+        Some(xml_unescape(&content[start..end]))
+    }
+
+    /// XML中第一个tag标签的文本, 如 "title", "summary"
+    pub fn xml_tag_text(&self, tag: &str) -> Option<String> {
+        let content = match self {
+            ServiceMessage::Xml { content, .. } => content,
+            _ => return None,
+        };
+        let open = format!("<{}", tag);
+        let mut from = 0;
+        let start = loop {
+            let i = content[from..].find(&open)? + from;
+            let after = content[i + open.len()..].chars().next()?;
+            if after == '>' || after == ' ' {
+                break content[i..].find('>')? + i + 1;
+            }
+            from = i + open.len();
+        };
+        let end = content[start..].find(&format!("</{}>", tag))? + start;
+        Some(xml_unescape(&content[start..end]))
+    }
+
+    /// 解析群公告, 公告的标题和正文为base64编码
+    pub fn group_announcement(&self) -> Option<GroupAnnouncement> {
+        if self.app()? != "com.tencent.mannounce" {
+            return None;
+        }
+        let field = |name: &str| -> String {
+            self.json_pointer(&format!("/meta/mannounce/{}", name))
+                .and_then(|v| v.as_str())
+                .map(|v| match crate::base64_decode(v) {
+                    Some(bytes) => String::from_utf8_lossy(&bytes).to_string(),
+                    None => v.to_owned(),
+                })
+                .unwrap_or_default()
+        };
+        Some(GroupAnnouncement {
+            title: field("title"),
+            text: field("text"),
+        })
+    }
+}
+
+fn xml_unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#10;", "\n")
+        .replace("&amp;", "&")
+}
+
+pub trait ServiceMessageTrait {
+    /// 消息中的XML/JSON服务消息
+    fn service_messages(&self) -> Vec<ServiceMessage>;
+}
+
+impl ServiceMessageTrait for MessageChain {
+    fn service_messages(&self) -> Vec<ServiceMessage> {
+        self.clone()
+            .into_iter()
+            .filter_map(ServiceMessage::from_elem)
+            .collect()
+    }
+}
@@ -0,0 +1,104 @@
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use rq_engine::msg::MessageChain;
+use rq_engine::RQResult;
+use rs_qq::handler::QEvent;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+use crate::{
+    MessageContentTrait, MessageEvent, MessageSendToSourceTrait, MessageTarget, MessageTargetTrait,
+};
+
+lazy_static! {
+    static ref SESSIONS: Mutex<HashMap<MessageTarget, oneshot::Sender<MessageEvent>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// 等待同一个来源 (群+用户, 好友) 的下一条消息, 超时返回None
+///
+/// 等待期间这条消息交给等待的处理器, 不再分发给模块。同一个来源同时只能有一个等待, 后来的会替换之前的
+pub async fn wait_next_message(target: MessageTarget, timeout: Duration) -> Option<MessageEvent> {
+    let (sender, receiver) = oneshot::channel();
+    SESSIONS.lock().unwrap().insert(target, sender);
+    let result = tokio::time::timeout(timeout, receiver).await;
+    let mut sessions = SESSIONS.lock().unwrap();
+    if sessions
+        .get(&target)
+        .map(|s| s.is_closed())
+        .unwrap_or(false)
+    {
+        sessions.remove(&target);
+    }
+    result.ok()?.ok()
+}
+
+/// 收到消息时调用, 有等待中的会话时交给会话并返回true
+pub(crate) fn deliver_to_session(e: &QEvent) -> bool {
+    let target = match e {
+        QEvent::GroupMessage(event) => event.target(),
+        QEvent::FriendMessage(event) => event.target(),
+        QEvent::TempMessage(event) => event.target(),
+        _ => return false,
+    };
+    let sender = match SESSIONS.lock().unwrap().remove(&target) {
+        Some(sender) => sender,
+        None => return false,
+    };
+    let event = match e {
+        QEvent::GroupMessage(event) => MessageEvent::GroupMessage(event.clone()),
+        QEvent::FriendMessage(event) => MessageEvent::FriendMessage(event.clone()),
+        QEvent::TempMessage(event) => MessageEvent::TempMessage(event.clone()),
+        _ => return false,
+    };
+    sender.send(event).is_ok()
+}
+
+/// confirm 的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confirmation {
+    Confirmed,
+    /// 回复了否定或其他内容
+    Denied,
+    Timeout,
+}
+
+impl Confirmation {
+    pub fn is_confirmed(&self) -> bool {
+        *self == Confirmation::Confirmed
+    }
+}
+
+const CONFIRM_WORDS: [&str; 8] = ["是", "确认", "确定", "好", "y", "yes", "ok", "confirm"];
+
+#[async_trait]
+pub trait SessionTrait: MessageSendToSourceTrait + MessageTargetTrait {
+    /// 等待同一个来源的下一条消息
+    async fn next_message(&self, timeout: Duration) -> Option<MessageEvent> {
+        wait_next_message(self.target(), timeout).await
+    }
+
+    /// 发送提示并等待回复, 回复 是/确认/yes 等为确认, 其他内容为拒绝
+    async fn confirm<S: Into<MessageChain> + Send + Sync>(
+        &self,
+        prompt: S,
+        timeout: Duration,
+    ) -> RQResult<Confirmation> {
+        self.send_message_to_source(prompt).await?;
+        Ok(match self.next_message(timeout).await {
+            Some(reply) => {
+                let content = reply.message_content().trim().to_lowercase();
+                if CONFIRM_WORDS.contains(&content.as_str()) {
+                    Confirmation::Confirmed
+                } else {
+                    Confirmation::Denied
+                }
+            }
+            None => Confirmation::Timeout,
+        })
+    }
+}
+
+impl<E: MessageSendToSourceTrait + MessageTargetTrait> SessionTrait for E {}
@@ -0,0 +1,486 @@
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use rs_qq::client::event::{FriendMessageEvent, GroupMessageEvent, TempMessageEvent};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, Once, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use crate::{current_module_id, MessageEvent};
+
+/// 键值存储, 值为序列化后的JSON
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<String>>;
+    async fn set(&self, key: &str, value: String) -> anyhow::Result<()>;
+    async fn remove(&self, key: &str) -> anyhow::Result<()>;
+    /// 以prefix开头的所有键
+    async fn keys(&self, prefix: &str) -> anyhow::Result<Vec<String>>;
+
+    /// 把整数值加上delta并返回新值, 不存在时视为0
+    /// 默认实现用一个全局锁保证原子性, 存储本身支持原子操作时应当覆盖
+    async fn incr(&self, key: &str, delta: i64) -> anyhow::Result<i64> {
+        let _guard = INCR_LOCK.lock().await;
+        let value = parse_counter(self.get(key).await?)? + delta;
+        self.set(key, value.to_string()).await?;
+        Ok(value)
+    }
+
+    /// 设置值并在ttl之后过期, 再次set或remove时取消过期
+    /// 默认不支持, 存储本身支持过期时间时应当覆盖
+    async fn set_ex(&self, key: &str, _value: String, _ttl: Duration) -> anyhow::Result<()> {
+        Err(anyhow::Error::msg(format!(
+            "存储不支持过期时间 (set_ex) : {}",
+            key
+        )))
+    }
+
+    /// 删除已经过期的键, 返回删除的数量, 由后台任务定期调用
+    async fn purge_expired(&self) -> anyhow::Result<usize> {
+        Ok(0)
+    }
+}
+
+fn parse_counter(value: Option<String>) -> anyhow::Result<i64> {
+    Ok(match value {
+        Some(value) => serde_json::from_str(&value)?,
+        None => 0,
+    })
+}
+
+/// 保存在内存中, 重启后丢失 (默认)
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    data: Mutex<MemoryData>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryData {
+    values: BTreeMap<String, String>,
+    expires: HashMap<String, Instant>,
+}
+
+impl MemoryData {
+    /// 访问之前删除已经过期的键, 后台任务还没有清理时也不会读到
+    fn expire(&mut self, key: &str) {
+        if let Some(deadline) = self.expires.get(key) {
+            if *deadline <= Instant::now() {
+                self.expires.remove(key);
+                self.values.remove(key);
+            }
+        }
+    }
+
+    fn purge(&mut self) -> usize {
+        let now = Instant::now();
+        let expired: Vec<String> = self
+            .expires
+            .iter()
+            .filter(|(_, deadline)| **deadline <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &expired {
+            self.expires.remove(key);
+            self.values.remove(key);
+        }
+        expired.len()
+    }
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<String>> {
+        let mut data = self.data.lock().await;
+        data.expire(key);
+        Ok(data.values.get(key).cloned())
+    }
+
+    async fn set(&self, key: &str, value: String) -> anyhow::Result<()> {
+        let mut data = self.data.lock().await;
+        data.expires.remove(key);
+        data.values.insert(key.to_owned(), value);
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> anyhow::Result<()> {
+        let mut data = self.data.lock().await;
+        data.expires.remove(key);
+        data.values.remove(key);
+        Ok(())
+    }
+
+    async fn keys(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        let mut data = self.data.lock().await;
+        data.purge();
+        Ok(prefix_keys(&data.values, prefix))
+    }
+
+    /// 和redis一致, 不改变过期时间
+    async fn incr(&self, key: &str, delta: i64) -> anyhow::Result<i64> {
+        let mut data = self.data.lock().await;
+        data.expire(key);
+        let value = parse_counter(data.values.get(key).cloned())? + delta;
+        data.values.insert(key.to_owned(), value.to_string());
+        Ok(value)
+    }
+
+    async fn set_ex(&self, key: &str, value: String, ttl: Duration) -> anyhow::Result<()> {
+        let mut data = self.data.lock().await;
+        data.expires.insert(key.to_owned(), Instant::now() + ttl);
+        data.values.insert(key.to_owned(), value);
+        Ok(())
+    }
+
+    async fn purge_expired(&self) -> anyhow::Result<usize> {
+        Ok(self.data.lock().await.purge())
+    }
+}
+
+/// 保存在一个JSON文件中, 每次修改后整体写入, 适合数据量不大的机器人
+#[derive(Debug)]
+pub struct FileStorage {
+    path: PathBuf,
+    data: Mutex<BTreeMap<String, String>>,
+}
+
+impl FileStorage {
+    /// 文件不存在时创建空的存储
+    pub async fn open<P: Into<PathBuf>>(path: P) -> anyhow::Result<Self> {
+        let path = path.into();
+        let data = if path.exists() {
+            serde_json::from_slice(&tokio::fs::read(&path).await?)?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self {
+            path,
+            data: Mutex::new(data),
+        })
+    }
+
+    async fn flush(&self, data: &BTreeMap<String, String>) -> anyhow::Result<()> {
+        // 先写临时文件再重命名, 避免写到一半退出导致文件损坏
+        let tmp = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec(data)?).await?;
+        tokio::fs::rename(&tmp, &self.path).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Storage for FileStorage {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<String>> {
+        Ok(self.data.lock().await.get(key).cloned())
+    }
+
+    async fn set(&self, key: &str, value: String) -> anyhow::Result<()> {
+        let mut data = self.data.lock().await;
+        data.insert(key.to_owned(), value);
+        self.flush(&data).await
+    }
+
+    async fn remove(&self, key: &str) -> anyhow::Result<()> {
+        let mut data = self.data.lock().await;
+        if data.remove(key).is_some() {
+            self.flush(&data).await?;
+        }
+        Ok(())
+    }
+
+    async fn keys(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        Ok(prefix_keys(&*self.data.lock().await, prefix))
+    }
+
+    async fn incr(&self, key: &str, delta: i64) -> anyhow::Result<i64> {
+        let mut data = self.data.lock().await;
+        let value = parse_counter(data.get(key).cloned())? + delta;
+        data.insert(key.to_owned(), value.to_string());
+        self.flush(&data).await?;
+        Ok(value)
+    }
+}
+
+fn prefix_keys(data: &BTreeMap<String, String>, prefix: &str) -> Vec<String> {
+    data.range(prefix.to_owned()..)
+        .take_while(|(k, _)| k.starts_with(prefix))
+        .map(|(k, _)| k.clone())
+        .collect()
+}
+
+lazy_static! {
+    static ref INCR_LOCK: Mutex<()> = Mutex::new(());
+    static ref STORAGE: RwLock<Arc<dyn Storage>> = RwLock::new(Arc::new(MemoryStorage::new()));
+}
+
+pub fn set_storage<S: Storage + 'static>(storage: S) {
+    *STORAGE.write().unwrap() = Arc::new(storage);
+}
+
+pub fn storage() -> Arc<dyn Storage> {
+    STORAGE.read().unwrap().clone()
+}
+
+static EXPIRY_TASK: Once = Once::new();
+
+/// 后台定期清理存储中过期的键, 只会启动一次
+pub(crate) fn start_storage_expiry() {
+    EXPIRY_TASK.call_once(|| {
+        tokio::spawn(async {
+            loop {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                if let Err(err) = storage().purge_expired().await {
+                    tracing::debug!(target = "proc_qq", "清理过期的键失败 : {:?}", err);
+                }
+            }
+        });
+    });
+}
+
+/// 按 (模块, 群, 用户) 划分命名空间的键值视图
+#[derive(Debug, Clone)]
+pub struct KvStore {
+    namespace: String,
+}
+
+impl KvStore {
+    /// group_code和uin为None时表示不区分群或用户
+    pub fn new(module_id: &str, group_code: Option<i64>, uin: Option<i64>) -> Self {
+        let part = |v: Option<i64>| v.map(|v| v.to_string()).unwrap_or_else(|| "_".to_owned());
+        Self {
+            namespace: format!("{}:{}:{}:", module_id, part(group_code), part(uin)),
+        }
+    }
+
+    fn full_key(&self, key: &str) -> String {
+        format!("{}{}", self.namespace, key)
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> anyhow::Result<Option<T>> {
+        match storage().get(&self.full_key(key)).await? {
+            Some(value) => Ok(Some(serde_json::from_str(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn set<T: Serialize + ?Sized>(&self, key: &str, value: &T) -> anyhow::Result<()> {
+        storage()
+            .set(&self.full_key(key), serde_json::to_string(value)?)
+            .await
+    }
+
+    /// 设置值并在ttl之后过期, 用于验证码, 待确认的操作等临时数据
+    pub async fn set_ex<T: Serialize + ?Sized>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> anyhow::Result<()> {
+        storage()
+            .set_ex(&self.full_key(key), serde_json::to_string(value)?, ttl)
+            .await
+    }
+
+    pub async fn remove(&self, key: &str) -> anyhow::Result<()> {
+        storage().remove(&self.full_key(key)).await
+    }
+
+    /// 原子地加上delta并返回新值
+    pub async fn incr(&self, key: &str, delta: i64) -> anyhow::Result<i64> {
+        storage().incr(&self.full_key(key), delta).await
+    }
+
+    /// 这个命名空间下的所有键 (不含命名空间前缀)
+    pub async fn keys(&self) -> anyhow::Result<Vec<String>> {
+        Ok(storage()
+            .keys(&self.namespace)
+            .await?
+            .into_iter()
+            .map(|k| k[self.namespace.len()..].to_owned())
+            .collect())
+    }
+}
+
+/// 排行榜, 每个用户一个计数, 保存在 (模块, 群) 的命名空间下
+#[derive(Debug, Clone)]
+pub struct Leaderboard {
+    store: KvStore,
+    prefix: String,
+}
+
+impl Leaderboard {
+    /// group_code为None时为全局排行榜
+    pub fn new(module_id: &str, group_code: Option<i64>, name: &str) -> Self {
+        Self {
+            store: KvStore::new(module_id, group_code, None),
+            prefix: format!("leaderboard:{}:", name),
+        }
+    }
+
+    /// 给用户加分并返回新的分数
+    pub async fn incr(&self, uin: i64, delta: i64) -> anyhow::Result<i64> {
+        self.store
+            .incr(&format!("{}{}", self.prefix, uin), delta)
+            .await
+    }
+
+    pub async fn score(&self, uin: i64) -> anyhow::Result<i64> {
+        Ok(self
+            .store
+            .get(&format!("{}{}", self.prefix, uin))
+            .await?
+            .unwrap_or(0))
+    }
+
+    /// 分数最高的n个用户, 按分数从高到低, 同分按uin从小到大
+    pub async fn top_n(&self, n: usize) -> anyhow::Result<Vec<(i64, i64)>> {
+        let mut scores = vec![];
+        for key in self.store.keys().await? {
+            let uin = match key.strip_prefix(&self.prefix).map(str::parse::<i64>) {
+                Some(Ok(uin)) => uin,
+                _ => continue,
+            };
+            if let Some(score) = self.store.get::<i64>(&key).await? {
+                scores.push((uin, score));
+            }
+        }
+        scores.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        scores.truncate(n);
+        Ok(scores)
+    }
+}
+
+/// 在处理器中获取当前模块对消息来源 (群+用户) 的存储
+pub trait KvStoreTrait {
+    fn store(&self) -> KvStore;
+
+    /// 当前模块在消息所在群的排行榜, 好友消息为全局排行榜
+    fn leaderboard(&self, name: &str) -> Leaderboard;
+}
+
+// 不在处理器中调用时 (例如自己spawn的任务) 没有模块信息
+fn module_id() -> String {
+    current_module_id().unwrap_or_else(|| "_".to_owned())
+}
+
+fn module_store(group_code: Option<i64>, uin: i64) -> KvStore {
+    KvStore::new(&module_id(), group_code, Some(uin))
+}
+
+impl KvStoreTrait for GroupMessageEvent {
+    fn store(&self) -> KvStore {
+        module_store(Some(self.message.group_code), self.message.from_uin)
+    }
+
+    fn leaderboard(&self, name: &str) -> Leaderboard {
+        Leaderboard::new(&module_id(), Some(self.message.group_code), name)
+    }
+}
+
+impl KvStoreTrait for FriendMessageEvent {
+    fn store(&self) -> KvStore {
+        module_store(None, self.message.from_uin)
+    }
+
+    fn leaderboard(&self, name: &str) -> Leaderboard {
+        Leaderboard::new(&module_id(), None, name)
+    }
+}
+
+impl KvStoreTrait for TempMessageEvent {
+    fn store(&self) -> KvStore {
+        module_store(self.message.group_code, self.message.from_uin)
+    }
+
+    fn leaderboard(&self, name: &str) -> Leaderboard {
+        Leaderboard::new(&module_id(), self.message.group_code, name)
+    }
+}
+
+impl KvStoreTrait for MessageEvent {
+    fn store(&self) -> KvStore {
+        match self {
+            MessageEvent::GroupMessage(event) => event.store(),
+            MessageEvent::FriendMessage(event) => event.store(),
+            MessageEvent::TempMessage(event) => event.store(),
+        }
+    }
+
+    fn leaderboard(&self, name: &str) -> Leaderboard {
+        match self {
+            MessageEvent::GroupMessage(event) => event.leaderboard(name),
+            MessageEvent::FriendMessage(event) => event.leaderboard(name),
+            MessageEvent::TempMessage(event) => event.leaderboard(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn memory_storage_expires_keys() {
+        let storage = MemoryStorage::new();
+        storage.set("a:1", "1".to_owned()).await.unwrap();
+        storage
+            .set_ex("a:2", "2".to_owned(), Duration::from_millis(20))
+            .await
+            .unwrap();
+        storage.set("b:1", "3".to_owned()).await.unwrap();
+        assert_eq!(storage.keys("a:").await.unwrap(), vec!["a:1", "a:2"]);
+        assert_eq!(storage.incr("a:2", 5).await.unwrap(), 7);
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(storage.get("a:2").await.unwrap(), None);
+        assert_eq!(storage.purge_expired().await.unwrap(), 0);
+        assert_eq!(storage.keys("a:").await.unwrap(), vec!["a:1"]);
+    }
+
+    #[tokio::test]
+    async fn file_storage_persists() {
+        let path =
+            std::env::temp_dir().join(format!("proc_qq_storage_{}.json", std::process::id()));
+        let _ = tokio::fs::remove_file(&path).await;
+        {
+            let storage = FileStorage::open(&path).await.unwrap();
+            storage.set("key", "\"value\"".to_owned()).await.unwrap();
+            assert_eq!(storage.incr("count", 2).await.unwrap(), 2);
+            assert!(storage
+                .set_ex("temp", "1".to_owned(), Duration::from_secs(1))
+                .await
+                .is_err());
+        }
+        let storage = FileStorage::open(&path).await.unwrap();
+        assert_eq!(
+            storage.get("key").await.unwrap().as_deref(),
+            Some("\"value\"")
+        );
+        assert_eq!(storage.incr("count", 1).await.unwrap(), 3);
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn leaderboard_orders_scores() {
+        let board = Leaderboard::new("storage_test", Some(1), "points");
+        board.incr(30, 5).await.unwrap();
+        board.incr(10, 5).await.unwrap();
+        board.incr(20, 9).await.unwrap();
+        board.incr(20, -1).await.unwrap();
+        assert_eq!(board.score(20).await.unwrap(), 8);
+        assert_eq!(board.score(40).await.unwrap(), 0);
+        assert_eq!(board.top_n(2).await.unwrap(), vec![(20, 8), (10, 5)]);
+        // 其他群和其他命名空间的数据互不影响
+        let other = KvStore::new("storage_test", Some(2), None);
+        other.set("leaderboard:points:10", &100).await.unwrap();
+        assert_eq!(board.score(10).await.unwrap(), 5);
+        assert_eq!(other.keys().await.unwrap(), vec!["leaderboard:points:10"]);
+    }
+}
@@ -0,0 +1,97 @@
+use rq_engine::msg::elem::Text;
+use rq_engine::msg::MessageChain;
+use rq_engine::structs::{FriendMessage, GroupMessage};
+use rs_qq::client::event::{FriendMessageEvent, GroupMessageEvent};
+use rs_qq::handler::QEvent;
+use std::sync::Arc;
+
+use crate::SeededRng;
+
+/// 生成模拟的消息事件, 用于压测分发性能或分析自己的模块
+///
+/// 相同的种子生成相同的事件序列, 配合 Client::dispatch_event 使用
+pub struct SyntheticEvents {
+    rng: SeededRng,
+    groups: Vec<i64>,
+    users: Vec<i64>,
+    texts: Vec<String>,
+    private_ratio: f64,
+    seq: i32,
+}
+
+impl SyntheticEvents {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: SeededRng::new(seed),
+            groups: (0..10).map(|i| 100000 + i).collect(),
+            users: (0..100).map(|i| 200000 + i).collect(),
+            texts: vec![
+                "hello".to_owned(),
+                "签到".to_owned(),
+                "/help".to_owned(),
+                "天气 北京".to_owned(),
+                "今天大家都在做什么呢, 有没有人一起打游戏".to_owned(),
+            ],
+            private_ratio: 0.1,
+            seq: 0,
+        }
+    }
+
+    pub fn groups(mut self, groups: Vec<i64>) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    pub fn users(mut self, users: Vec<i64>) -> Self {
+        self.users = users;
+        self
+    }
+
+    /// 消息内容从中随机选择
+    pub fn texts<S: Into<String>>(mut self, texts: Vec<S>) -> Self {
+        self.texts = texts.into_iter().map(|t| t.into()).collect();
+        self
+    }
+
+    /// 好友消息的比例 (0.0 - 1.0), 其余为群消息
+    pub fn private_ratio(mut self, private_ratio: f64) -> Self {
+        self.private_ratio = private_ratio;
+        self
+    }
+
+    pub fn next_event(&mut self, client: &Arc<rs_qq::Client>) -> QEvent {
+        self.seq += 1;
+        let from_uin = self.rng.choose(&self.users).copied().unwrap_or(10000);
+        let text = self.rng.choose(&self.texts).cloned().unwrap_or_default();
+        let elements = MessageChain::new(Text::new(text));
+        if self.groups.is_empty() || self.rng.gen_bool(self.private_ratio) {
+            QEvent::FriendMessage(FriendMessageEvent {
+                client: client.clone(),
+                message: FriendMessage {
+                    seqs: vec![self.seq],
+                    rands: vec![self.seq],
+                    from_uin,
+                    elements,
+                    ..Default::default()
+                },
+            })
+        } else {
+            let group_code = self.rng.choose(&self.groups).copied().unwrap_or_default();
+            QEvent::GroupMessage(GroupMessageEvent {
+                client: client.clone(),
+                message: GroupMessage {
+                    seqs: vec![self.seq],
+                    rands: vec![self.seq],
+                    group_code,
+                    from_uin,
+                    elements,
+                    ..Default::default()
+                },
+            })
+        }
+    }
+
+    pub fn take(&mut self, client: &Arc<rs_qq::Client>, n: usize) -> Vec<QEvent> {
+        (0..n).map(|_| self.next_event(client)).collect()
+    }
+}
@@ -0,0 +1,90 @@
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OwnedMutexGuard;
+
+use crate::{ClientTrait, MessageTarget, MessageTargetTrait};
+
+lazy_static! {
+    static ref TARGET_LOCKS: Mutex<HashMap<MessageTarget, Arc<tokio::sync::Mutex<()>>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// 群消息的目标中带有发送者, 同一个群共用一把锁
+fn lock_key(target: MessageTarget) -> MessageTarget {
+    match target {
+        MessageTarget::Group(group_code, _) => MessageTarget::Group(group_code, 0),
+        target => target,
+    }
+}
+
+/// 持有期间其他处理器无法锁定同一个目标, 离开作用域时释放
+pub struct TargetGuard {
+    key: MessageTarget,
+    lock: Arc<tokio::sync::Mutex<()>>,
+    _guard: OwnedMutexGuard<()>,
+}
+
+impl Drop for TargetGuard {
+    fn drop(&mut self) {
+        let mut locks = TARGET_LOCKS.lock().unwrap();
+        // 表中, lock和_guard各持有一个引用, 没有其他等待者时移除, 以免表一直增长
+        if Arc::strong_count(&self.lock) <= 3 {
+            locks.remove(&self.key);
+        }
+    }
+}
+
+/// 锁定一个会话 (群/好友/临时会话), 用于串行修改同一个群的状态 (游戏, 积分等)
+///
+/// 按照等待的顺序获得锁, 不可重入, 在持有时再次锁定同一个目标会死锁
+pub async fn lock_target(target: &impl MessageTargetTrait) -> TargetGuard {
+    let key = lock_key(target.target());
+    let lock = TARGET_LOCKS.lock().unwrap().entry(key).or_default().clone();
+    let guard = lock.clone().lock_owned().await;
+    TargetGuard {
+        key,
+        lock,
+        _guard: guard,
+    }
+}
+
+#[async_trait]
+pub trait TargetLockTrait: ClientTrait {
+    /// 同 lock_target
+    async fn lock_target(&self, target: &impl MessageTargetTrait) -> TargetGuard;
+}
+
+#[async_trait]
+impl<C: ClientTrait> TargetLockTrait for C {
+    async fn lock_target(&self, target: &impl MessageTargetTrait) -> TargetGuard {
+        lock_target(target).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn serializes_the_same_group() {
+        let first = lock_target(&MessageTarget::Group(7_000_718, 1)).await;
+        // 同群的其他成员需要等待, 其他群不受影响
+        let other_group = lock_target(&MessageTarget::Group(7_000_719, 1)).await;
+        let same_group = tokio::time::timeout(
+            Duration::from_millis(20),
+            lock_target(&MessageTarget::Group(7_000_718, 2)),
+        )
+        .await;
+        assert!(same_group.is_err());
+        drop(first);
+        drop(other_group);
+        let _second = lock_target(&MessageTarget::Group(7_000_718, 2)).await;
+        assert!(!TARGET_LOCKS
+            .lock()
+            .unwrap()
+            .contains_key(&MessageTarget::Group(7_000_719, 0)));
+    }
+}
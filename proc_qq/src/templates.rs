@@ -0,0 +1,108 @@
+use crate::{group_config, Formality, GroupProfile, Language};
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+// (key, 语言, 语气)
+type TemplateKey = (String, Language, Option<Formality>);
+
+lazy_static! {
+    static ref TEMPLATES: RwLock<HashMap<TemplateKey, String>> = RwLock::new(HashMap::new());
+}
+
+/// 注册文字模版, 模版中使用 {name} 作为占位符, formality为None时适用于任意语气
+pub fn register_template<K: Into<String>, S: Into<String>>(
+    key: K,
+    language: Language,
+    formality: Option<Formality>,
+    text: S,
+) {
+    TEMPLATES
+        .write()
+        .unwrap()
+        .insert((key.into(), language, formality), text.into());
+}
+
+/// 根据群设置渲染模版, 依次尝试 (语言, 语气) (语言) (中文)
+pub fn render_template(
+    key: &str,
+    group_code: Option<i64>,
+    args: &[(&str, &str)],
+) -> Option<String> {
+    let profile = group_code
+        .and_then(group_config)
+        .map(|config| config.profile)
+        .unwrap_or_default();
+    render_template_with_profile(key, &profile, args)
+}
+
+pub fn render_template_with_profile(
+    key: &str,
+    profile: &GroupProfile,
+    args: &[(&str, &str)],
+) -> Option<String> {
+    let templates = TEMPLATES.read().unwrap();
+    let candidates = [
+        (
+            key.to_owned(),
+            profile.language.clone(),
+            Some(profile.formality),
+        ),
+        (key.to_owned(), profile.language.clone(), None),
+        (key.to_owned(), Language::Chinese, None),
+    ];
+    let text = candidates.iter().find_map(|k| templates.get(k))?;
+    let mut result = text.clone();
+    for (name, value) in args {
+        result = result.replace(&format!("{{{}}}", name), value);
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{set_group_config, GroupConfig};
+
+    #[test]
+    fn falls_back_by_language_and_formality() {
+        register_template("templates_test", Language::Chinese, None, "你好 {name}");
+        register_template("templates_test", Language::English, None, "Hi {name}");
+        register_template(
+            "templates_test",
+            Language::English,
+            Some(Formality::Formal),
+            "Dear {name}",
+        );
+        let args = [("name", "Alice")];
+        assert_eq!(
+            render_template("templates_test", None, &args).as_deref(),
+            Some("你好 Alice")
+        );
+        let mut profile = GroupProfile {
+            language: Language::English,
+            ..Default::default()
+        };
+        set_group_config(
+            7_000_647,
+            GroupConfig {
+                profile: profile.clone(),
+            },
+        );
+        assert_eq!(
+            render_template("templates_test", Some(7_000_647), &args).as_deref(),
+            Some("Hi Alice")
+        );
+        profile.formality = Formality::Formal;
+        assert_eq!(
+            render_template_with_profile("templates_test", &profile, &args).as_deref(),
+            Some("Dear Alice")
+        );
+        profile.language = Language::Custom("ja".to_owned());
+        assert_eq!(
+            render_template_with_profile("templates_test", &profile, &[]).as_deref(),
+            Some("你好 {name}")
+        );
+        assert_eq!(render_template("templates_missing", None, &args), None);
+    }
+}
@@ -0,0 +1,141 @@
+use lazy_static::lazy_static;
+use rq_engine::msg::elem::{FlashImage, RQElem};
+use rq_engine::msg::MessageChain;
+use rq_engine::structs::MessageReceipt;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::MessageTarget;
+
+lazy_static! {
+    static ref SENT: Mutex<Option<Vec<SentMessage>>> = Mutex::new(None);
+}
+
+/// 被拦截的消息
+#[derive(Debug, Clone)]
+pub struct SentMessage {
+    pub target: MessageTarget,
+    pub chain: MessageChain,
+}
+
+impl SentMessage {
+    /// 如 "[group 123] 你好[@456][image:0a1b...]"
+    pub fn to_snapshot(&self) -> String {
+        let target = match self.target {
+            MessageTarget::Group(group_code, _) => format!("group {}", group_code),
+            MessageTarget::Private(uin) => format!("private {}", uin),
+            MessageTarget::Temp(_, uin) => format!("temp {}", uin),
+        };
+        format!("[{}] {}", target, snapshot_text(&self.chain))
+    }
+}
+
+/// 拦截发送的消息, 用于在测试中检查监听器的回复
+///
+/// 拦截期间消息不会真正发出, 拦截是全局的, 同时运行的测试会互相影响
+pub struct MockSender {
+    _private: (),
+}
+
+/// 开始拦截, MockSender被drop时停止
+pub fn mock_sender() -> MockSender {
+    *SENT.lock().unwrap() = Some(vec![]);
+    MockSender { _private: () }
+}
+
+impl MockSender {
+    pub fn sent_messages(&self) -> Vec<SentMessage> {
+        SENT.lock().unwrap().clone().unwrap_or_default()
+    }
+
+    /// 所有消息的文字形式, 每条一行
+    pub fn snapshot(&self) -> String {
+        self.sent_messages()
+            .iter()
+            .map(|m| m.to_snapshot())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// 与期望的文字比较, 忽略首尾空白和每行的缩进
+    pub fn assert_snapshot(&self, expected: &str) {
+        let actual = normalize(&self.snapshot());
+        let expected = normalize(expected);
+        assert_eq!(
+            actual, expected,
+            "\n发出的消息与期望不同\n== 实际 ==\n{}\n== 期望 ==\n{}\n",
+            actual, expected
+        );
+    }
+
+    pub fn clear(&self) {
+        if let Some(sent) = SENT.lock().unwrap().as_mut() {
+            sent.clear();
+        }
+    }
+}
+
+impl Drop for MockSender {
+    fn drop(&mut self) {
+        *SENT.lock().unwrap() = None;
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.trim()
+        .lines()
+        .map(|line| line.trim())
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+/// 消息的文字形式, 图片以md5表示, 其他元素以类型表示
+pub fn snapshot_text(chain: &MessageChain) -> String {
+    chain
+        .clone()
+        .into_iter()
+        .map(|elem| match elem {
+            RQElem::Text(text) => text.content,
+            RQElem::At(at) => format!("[@{}]", at.target),
+            RQElem::Face(face) => format!("[face:{}]", face.index),
+            RQElem::Dice(dice) => format!("[dice:{}]", dice.value),
+            RQElem::FriendImage(image) => format!("[image:{}]", hex(&image.md5)),
+            RQElem::GroupImage(image) => format!("[image:{}]", hex(&image.md5)),
+            RQElem::FlashImage(FlashImage::FriendImage(image)) => {
+                format!("[flash_image:{}]", hex(&image.md5))
+            }
+            RQElem::FlashImage(FlashImage::GroupImage(image)) => {
+                format!("[flash_image:{}]", hex(&image.md5))
+            }
+            RQElem::MarketFace(face) => format!("[market_face:{}]", face.name),
+            RQElem::FingerGuessing(_) => "[finger_guessing]".to_owned(),
+            RQElem::LightApp(_) => "[light_app]".to_owned(),
+            RQElem::RichMsg(_) => "[rich_msg]".to_owned(),
+            RQElem::VideoFile(_) => "[video]".to_owned(),
+            _ => "[other]".to_owned(),
+        })
+        .collect()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 拦截中时记录消息并返回回执
+pub(crate) fn capture_send(target: &MessageTarget, chain: &MessageChain) -> Option<MessageReceipt> {
+    let mut lock = SENT.lock().unwrap();
+    let sent = lock.as_mut()?;
+    sent.push(SentMessage {
+        target: *target,
+        chain: chain.clone(),
+    });
+    let seq = sent.len() as i32;
+    Some(MessageReceipt {
+        seqs: vec![seq],
+        rands: vec![seq],
+        time: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default(),
+    })
+}
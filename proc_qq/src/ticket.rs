@@ -0,0 +1,102 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use std::time::Duration;
+use tokio::io::AsyncBufReadExt;
+
+/// 密码登录需要滑动验证码时, 通过verify_url获取ticket
+#[async_trait]
+pub trait TicketResolver: Send + Sync + std::fmt::Debug {
+    async fn resolve(&self, verify_url: &str) -> anyhow::Result<String>;
+}
+
+/// 使用 https://github.com/mzdluo123/TxCaptchaHelper 的APP滑动 (默认)
+#[derive(Debug, Clone, Default)]
+pub struct TxHelperTicketResolver;
+
+#[async_trait]
+impl TicketResolver for TxHelperTicketResolver {
+    async fn resolve(&self, verify_url: &str) -> anyhow::Result<String> {
+        let helper_url = verify_url.replace("ssl.captcha.qq.com", "txhelper.glitch.me");
+        tracing::info!("滑动条 (改URL) : {:?}", helper_url);
+        let txt = http_get(&helper_url)
+            .await
+            .with_context(|| "http请求失败")?;
+        tracing::info!("您需要使用该仓库 提供的APP进行滑动 , 滑动后请等待, https://github.com/mzdluo123/TxCaptchaHelper : {}", txt);
+        loop {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            let rsp = http_get(&helper_url)
+                .await
+                .with_context(|| "http请求失败")?;
+            if !rsp.eq(&txt) {
+                return Ok(rsp);
+            }
+        }
+    }
+}
+
+/// 打印验证地址, 在浏览器中完成滑动后将ticket输入到控制台
+#[derive(Debug, Clone, Default)]
+pub struct ManualTicketResolver;
+
+#[async_trait]
+impl TicketResolver for ManualTicketResolver {
+    async fn resolve(&self, verify_url: &str) -> anyhow::Result<String> {
+        tracing::info!(
+            "请打开以下地址完成滑动, 并将ticket输入到控制台 : {}",
+            verify_url
+        );
+        let mut lines = tokio::io::BufReader::new(tokio::io::stdin()).lines();
+        loop {
+            match lines.next_line().await? {
+                Some(line) if !line.trim().is_empty() => return Ok(line.trim().to_owned()),
+                Some(_) => continue,
+                None => return Err(anyhow::Error::msg("控制台已关闭, 无法读取ticket")),
+            }
+        }
+    }
+}
+
+/// 请求外部服务 (如打码平台的适配服务) 获取ticket, 请求为 GET {endpoint}?url={verify_url}, 返回内容为ticket
+#[derive(Debug, Clone)]
+pub struct HttpTicketResolver {
+    pub endpoint: String,
+    pub timeout: Duration,
+}
+
+impl HttpTicketResolver {
+    pub fn new<S: Into<String>>(endpoint: S) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            timeout: Duration::from_secs(300),
+        }
+    }
+}
+
+#[async_trait]
+impl TicketResolver for HttpTicketResolver {
+    async fn resolve(&self, verify_url: &str) -> anyhow::Result<String> {
+        let url = reqwest::Url::parse_with_params(&self.endpoint, &[("url", verify_url)])?;
+        let ticket = reqwest::ClientBuilder::new()
+            .timeout(self.timeout)
+            .build()?
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        let ticket = ticket.trim();
+        if ticket.is_empty() {
+            return Err(anyhow::Error::msg("ticket服务返回了空的ticket"));
+        }
+        Ok(ticket.to_owned())
+    }
+}
+
+pub(crate) async fn http_get(url: &str) -> anyhow::Result<String> {
+    Ok(reqwest::ClientBuilder::new().build().unwrap().get(url).header(
+        "user-agent", "Mozilla/5.0 (Linux; Android 6.0; Nexus 5 Build/MRA58N) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/98.0.4758.80 Mobile Safari/537.36",
+    ).send().await?
+        .text()
+        .await?)
+}
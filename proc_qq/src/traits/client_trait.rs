@@ -1,50 +1,65 @@
 use async_trait::async_trait;
+use rq_engine::msg::elem::RQElem;
 use rq_engine::msg::MessageChain;
-use rq_engine::structs::MessageReceipt;
+use rq_engine::structs::{FriendInfo, MessageReceipt};
 use rq_engine::{RQError, RQResult};
 use rs_qq::structs::Group;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::{MessageTarget, MessageTargetTrait};
+use crate::{
+    forward_threshold, framework_messages, AtAllQuota, MessageId, MessageReceiptTrait,
+    MessageTarget, MessageTargetTrait, RetryPolicy, UserProfile,
+};
 
 #[async_trait]
 pub trait ClientTrait: Send + Sync {
-    async fn send_message_to_target<S: Into<MessageChain> + Send + Sync>(
-        &self,
-        source: &impl MessageTargetTrait,
-        message: S,
-    ) -> RQResult<MessageReceipt>;
-    async fn must_find_group(&self, group_code: i64, auto_reload: bool) -> RQResult<Arc<Group>>;
-    async fn bot_uin(&self) -> i64;
-}
+    /// 调用协议的rs-qq客户端, 其他方法都基于它实现
+    fn protocol_client(&self) -> &rs_qq::Client;
 
-#[async_trait]
-impl ClientTrait for rs_qq::Client {
     async fn send_message_to_target<S: Into<MessageChain> + Send + Sync>(
         &self,
         source: &impl MessageTargetTrait,
         message: S,
     ) -> RQResult<MessageReceipt> {
+        let target = source.target();
         let message = message.into();
-        match source.target() {
-            MessageTarget::Group(group_code, _) => {
-                self.send_group_message(group_code, message).await
-            }
-            MessageTarget::Private(uin) => self.send_friend_message(uin, message).await,
-            MessageTarget::Temp(group_code, uin) => {
-                if let Some(group_code) = group_code {
-                    match self.send_temp_message(group_code, uin, message).await {
-                        Ok(_) => RQResult::Ok(MessageReceipt::default()),
-                        Err(err) => RQResult::Err(err),
-                    }
-                } else {
-                    RQResult::Err(RQError::Other("不存在GroupCode".to_owned()))
-                }
-            }
+        if let Some(receipt) = crate::capture_dry_run(&target, &message) {
+            return Ok(receipt);
         }
+        let (message, waiters) = match crate::batch_send(&target, message).await {
+            crate::Batched::Send(message, waiters) => (message, waiters),
+            crate::Batched::Merged(result) => return result,
+        };
+        if !crate::reserve_send(&target, &message) {
+            tracing::debug!(
+                target = "proc_qq",
+                "SEND DEDUPLICATED {} : {}",
+                crate::log_target(&target),
+                crate::log_chain(&message)
+            );
+            return Ok(crate::deduplicated_receipt());
+        }
+        crate::humanize_send(&message).await;
+        let start = Instant::now();
+        let result = match chaos_point("send_message_to_target").await {
+            Ok(_) => send_to_target(self.protocol_client(), &target, message.clone()).await,
+            Err(err) => Err(err),
+        };
+        if result.is_err() {
+            crate::release_send(&target, &message);
+        }
+        log_outgoing(&target, &message, &result, start.elapsed());
+        crate::finish_batch(waiters, &result);
+        result
     }
+
     async fn must_find_group(&self, group_code: i64, auto_reload: bool) -> RQResult<Arc<Group>> {
-        let group = self.find_group(group_code, auto_reload).await;
+        chaos_point("must_find_group").await?;
+        let group = self
+            .protocol_client()
+            .find_group(group_code, auto_reload)
+            .await;
         match group {
             Some(group) => RQResult::Ok(group),
             None => RQResult::Err(RQError::Other(format!("Group not found : {}", group_code))),
@@ -52,29 +67,438 @@ impl ClientTrait for rs_qq::Client {
     }
 
     async fn bot_uin(&self) -> i64 {
-        self.uin().await
+        self.protocol_client().uin().await
     }
-}
 
-#[async_trait]
-impl ClientTrait for crate::Client {
-    async fn send_message_to_target<S: Into<MessageChain> + Send + Sync>(
+    async fn friend_list(&self, auto_reload: bool) -> RQResult<Vec<Arc<FriendInfo>>> {
+        chaos_point("friend_list").await?;
+        if auto_reload {
+            self.protocol_client().reload_friends().await?;
+        }
+        Ok(self
+            .protocol_client()
+            .friends
+            .read()
+            .await
+            .values()
+            .cloned()
+            .collect())
+    }
+
+    async fn must_find_friend(&self, uin: i64) -> RQResult<Arc<FriendInfo>> {
+        chaos_point("must_find_friend").await?;
+        match self.protocol_client().find_friend(uin).await {
+            Some(friend) => RQResult::Ok(friend),
+            None => RQResult::Err(RQError::Other(format!("Friend not found : {}", uin))),
+        }
+    }
+
+    async fn remove_friend(&self, uin: i64) -> RQResult<()> {
+        if crate::capture_dry_run_action("remove_friend", || uin.to_string()) {
+            return Ok(());
+        }
+        chaos_point("remove_friend").await?;
+        self.protocol_client().delete_friend(uin).await?;
+        self.protocol_client().friends.write().await.remove(&uin);
+        crate::record_audit("remove_friend", None, Some(uin), String::new()).await;
+        Ok(())
+    }
+
+    /// 设置群头衔, 需要机器人是群主
+    async fn set_member_special_title(
+        &self,
+        group_code: i64,
+        uin: i64,
+        title: &str,
+    ) -> RQResult<()> {
+        if crate::capture_dry_run_action("set_member_special_title", || {
+            format!("{} {} {}", group_code, uin, title)
+        }) {
+            return Ok(());
+        }
+        chaos_point("set_member_special_title").await?;
+        self.protocol_client()
+            .group_edit_special_title(group_code, uin, title.to_owned())
+            .await?;
+        if let Some(group) = self.protocol_client().find_group(group_code, false).await {
+            for member in group.members.write().await.iter_mut() {
+                if member.uin == uin {
+                    member.special_title = title.to_owned();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 撤回消息, 群消息需要是机器人发送的或机器人是管理员
+    async fn recall(&self, message_id: &MessageId) -> RQResult<()> {
+        if crate::capture_dry_run_action("recall", || {
+            format!(
+                "{} {:?}",
+                crate::log_target(&message_id.target),
+                message_id.seqs
+            )
+        }) {
+            return Ok(());
+        }
+        chaos_point("recall").await?;
+        match message_id.target {
+            MessageTarget::Group(group_code, _) => {
+                self.protocol_client()
+                    .recall_group_message(
+                        group_code,
+                        message_id.seqs.clone(),
+                        message_id.rands.clone(),
+                    )
+                    .await
+            }
+            MessageTarget::Private(uin) => {
+                self.protocol_client()
+                    .recall_friend_message(
+                        uin,
+                        message_id.time,
+                        message_id.seqs.clone(),
+                        message_id.rands.clone(),
+                    )
+                    .await
+            }
+            MessageTarget::Temp(..) => {
+                return RQResult::Err(RQError::Other(
+                    framework_messages().unsupported("recall temp message"),
+                ))
+            }
+        }?;
+        crate::record_audit(
+            "recall",
+            match message_id.target {
+                MessageTarget::Group(group_code, _) => Some(group_code),
+                _ => None,
+            },
+            Some(message_id.sender),
+            format!("seqs={:?}", message_id.seqs),
+        )
+        .await;
+        Ok(())
+    }
+
+    /// 将消息及之前的消息标记为已读
+    async fn mark_read(&self, message_id: &MessageId) -> RQResult<()> {
+        if crate::capture_dry_run_action("mark_read", || crate::log_target(&message_id.target)) {
+            return Ok(());
+        }
+        chaos_point("mark_read").await?;
+        match message_id.target {
+            MessageTarget::Group(group_code, _) => {
+                self.protocol_client()
+                    .mark_group_message_readed(group_code, message_id.seq())
+                    .await
+            }
+            MessageTarget::Private(uin) => {
+                self.protocol_client()
+                    .mark_friend_message_readed(uin, message_id.time)
+                    .await
+            }
+            MessageTarget::Temp(..) => RQResult::Err(RQError::Other(
+                framework_messages().unsupported("mark temp message read"),
+            )),
+        }
+    }
+
+    /// 修改个性签名
+    async fn set_signature(&self, signature: &str) -> RQResult<()> {
+        if crate::capture_dry_run_action("set_signature", || signature.to_owned()) {
+            return Ok(());
+        }
+        chaos_point("set_signature").await?;
+        self.protocol_client()
+            .update_signature(signature.to_owned())
+            .await
+    }
+
+    /// 用户的资料卡概要, 有缓存
+    async fn user_profile(&self, uin: i64) -> RQResult<UserProfile> {
+        if let Some(profile) = crate::cached_profile(uin) {
+            return Ok(profile);
+        }
+        chaos_point("user_profile").await?;
+        let info = self.protocol_client().get_summary_info(uin).await?;
+        let profile = UserProfile {
+            uin,
+            nickname: info.nickname,
+            level: info.level,
+            sex: info.sex,
+            age: info.age,
+            city: info.city,
+            sign: info.sign,
+        };
+        crate::cache_profile(&profile);
+        Ok(profile)
+    }
+
+    /// 用户的头像 (640x640), 有缓存
+    async fn user_avatar(&self, uin: i64) -> RQResult<Arc<Vec<u8>>> {
+        if let Some(avatar) = crate::cached_avatar(uin) {
+            return Ok(avatar);
+        }
+        chaos_point("user_avatar").await?;
+        let response = reqwest::get(crate::avatar_url(uin))
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| RQError::Other(format!("下载头像失败 : {}", err)))?;
+        let avatar = Arc::new(
+            response
+                .bytes()
+                .await
+                .map_err(|err| RQError::Other(format!("下载头像失败 : {}", err)))?
+                .to_vec(),
+        );
+        crate::cache_avatar(uin, avatar.clone());
+        Ok(avatar)
+    }
+
+    /// 群中剩余的@全体成员次数
+    async fn at_all_quota(&self, group_code: i64) -> RQResult<AtAllQuota> {
+        chaos_point("at_all_quota").await?;
+        let info = self
+            .protocol_client()
+            .get_group_at_all_remain(group_code)
+            .await?;
+        Ok(AtAllQuota {
+            can_at_all: info.can_at_all,
+            remaining_for_group: info.remain_at_all_count_for_group,
+            remaining_for_bot: info.remain_at_all_count_for_uin,
+        })
+    }
+
+    /// 踢出群成员, 记录到审计日志
+    async fn kick_member(
+        &self,
+        group_code: i64,
+        uin: i64,
+        reason: &str,
+        block: bool,
+    ) -> RQResult<()> {
+        if crate::capture_dry_run_action("kick", || {
+            format!("{} {} reason={} block={}", group_code, uin, reason, block)
+        }) {
+            return Ok(());
+        }
+        chaos_point("kick_member").await?;
+        self.protocol_client()
+            .group_kick(group_code, vec![uin], reason, block)
+            .await?;
+        crate::record_audit(
+            "kick",
+            Some(group_code),
+            Some(uin),
+            format!("reason={} block={}", reason, block),
+        )
+        .await;
+        Ok(())
+    }
+
+    /// 禁言群成员, duration为0时解除禁言, 记录到审计日志
+    async fn mute_member(&self, group_code: i64, uin: i64, duration: Duration) -> RQResult<()> {
+        if crate::capture_dry_run_action("mute", || {
+            format!("{} {} {}s", group_code, uin, duration.as_secs())
+        }) {
+            return Ok(());
+        }
+        chaos_point("mute_member").await?;
+        self.protocol_client()
+            .group_mute(group_code, uin, duration)
+            .await?;
+        crate::record_audit(
+            if duration.is_zero() { "unmute" } else { "mute" },
+            Some(group_code),
+            Some(uin),
+            format!("{}s", duration.as_secs()),
+        )
+        .await;
+        Ok(())
+    }
+
+    /// 退出群, 开启了退群确认 (set_leave_confirmation) 时需要调用两次, 记录到审计日志
+    async fn leave_group(&self, group_code: i64) -> RQResult<()> {
+        if crate::capture_dry_run_action("leave_group", || group_code.to_string()) {
+            return Ok(());
+        }
+        chaos_point("leave_group").await?;
+        crate::confirm_leave(group_code)?;
+        self.protocol_client().group_quit(group_code).await?;
+        crate::record_audit("leave_group", Some(group_code), None, String::new()).await;
+        Ok(())
+    }
+
+    /// 发送消息并检查服务器回执, 发送失败或未回执时按照重发策略重试
+    async fn send_message_to_target_confirmed<S: Into<MessageChain> + Send + Sync>(
         &self,
         source: &impl MessageTargetTrait,
         message: S,
+        retry_policy: &RetryPolicy,
     ) -> RQResult<MessageReceipt> {
-        self.rq_client
-            .send_message_to_target(source, message.into())
-            .await
+        let message = message.into();
+        // 临时会话不返回回执
+        let need_receipt = !matches!(source.target(), MessageTarget::Temp(..));
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let result = self.send_message_to_target(source, message.clone()).await;
+            let retry = match &result {
+                Ok(receipt) => need_receipt && !receipt.confirmed() && !receipt.is_deduplicated(),
+                Err(_) => true,
+            };
+            if !retry || attempts >= retry_policy.max_attempts {
+                return result;
+            }
+            // 重发的消息不应被去重
+            crate::release_send(&source.target(), &message);
+            tracing::warn!(target = "proc_qq", "消息未确认送达, 第{}次重发", attempts);
+            tokio::time::sleep(retry_policy.interval).await;
+        }
     }
 
-    async fn must_find_group(&self, group_code: i64, auto_reload: bool) -> RQResult<Arc<Group>> {
-        self.rq_client
-            .must_find_group(group_code, auto_reload)
-            .await
+    /// 发送消息并返回消息标识, 可用于之后撤回或引用
+    async fn send_message_for_id<S: Into<MessageChain> + Send + Sync>(
+        &self,
+        source: &impl MessageTargetTrait,
+        message: S,
+    ) -> RQResult<MessageId> {
+        let target = source.target();
+        let receipt = self.send_message_to_target(source, message).await?;
+        Ok(MessageId::from_receipt(
+            target,
+            self.bot_uin().await,
+            &receipt,
+        ))
     }
+}
 
-    async fn bot_uin(&self) -> i64 {
-        self.rq_client.bot_uin().await
+impl ClientTrait for rs_qq::Client {
+    fn protocol_client(&self) -> &rs_qq::Client {
+        self
+    }
+}
+
+impl ClientTrait for crate::Client {
+    fn protocol_client(&self) -> &rs_qq::Client {
+        &self.rq_client
+    }
+}
+
+async fn send_to_target(
+    client: &rs_qq::Client,
+    target: &MessageTarget,
+    message: MessageChain,
+) -> RQResult<MessageReceipt> {
+    #[cfg(feature = "testing")]
+    if let Some(receipt) = crate::testing::capture_send(target, &message) {
+        return Ok(receipt);
+    }
+    match *target {
+        MessageTarget::Group(group_code, _) => {
+            if let Some(threshold) = forward_threshold() {
+                if threshold.exceeds(&message.to_string()) {
+                    let nodes = threshold.forward_nodes(message, client.uin().await);
+                    return client.send_group_forward_message(group_code, nodes).await;
+                }
+            }
+            client.send_group_message(group_code, message).await
+        }
+        MessageTarget::Private(uin) => client.send_friend_message(uin, message).await,
+        MessageTarget::Temp(group_code, uin) => {
+            if let Some(group_code) = group_code {
+                let message = crate::degrade_temp_message(message);
+                match client.send_temp_message(group_code, uin, message).await {
+                    Ok(_) => RQResult::Ok(MessageReceipt::default()),
+                    Err(err) => RQResult::Err(err),
+                }
+            } else {
+                RQResult::Err(RQError::Other(
+                    framework_messages().unsupported("temp message without group code"),
+                ))
+            }
+        }
     }
 }
+
+/// 消息元素概要, 如 "text:2,image:1"
+fn element_summary(message: &MessageChain) -> String {
+    let mut counts: Vec<(&str, usize)> = vec![];
+    for elem in message.clone().into_iter() {
+        let name = match elem {
+            RQElem::At(_) => "at",
+            RQElem::Text(_) => "text",
+            RQElem::Face(_) => "face",
+            RQElem::MarketFace(_) => "market_face",
+            RQElem::Dice(_) => "dice",
+            RQElem::FingerGuessing(_) => "finger_guessing",
+            RQElem::LightApp(_) => "light_app",
+            RQElem::RichMsg(_) => "rich_msg",
+            RQElem::FriendImage(_) | RQElem::GroupImage(_) => "image",
+            RQElem::FlashImage(_) => "flash_image",
+            RQElem::VideoFile(_) => "video",
+            _ => "other",
+        };
+        match counts.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((name, 1)),
+        }
+    }
+    counts
+        .iter()
+        .map(|(name, count)| format!("{}:{}", name, count))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// 与接收消息的日志对应, 记录每条发出的消息
+///
+/// 消息内容只在日志级别开启时才转换为文字
+fn log_outgoing(
+    target: &MessageTarget,
+    message: &MessageChain,
+    result: &RQResult<MessageReceipt>,
+    latency: Duration,
+) {
+    let (to, group_code, uin) = match *target {
+        MessageTarget::Group(group_code, _) => ("group", Some(group_code), None),
+        MessageTarget::Private(uin) => ("private", None, Some(crate::log_uin(uin))),
+        MessageTarget::Temp(group_code, uin) => ("temp", group_code, Some(crate::log_uin(uin))),
+    };
+    let latency_ms = latency.as_millis() as u64;
+    match result {
+        Ok(receipt) => tracing::debug!(
+            target = "proc_qq",
+            to,
+            group_code = ?group_code,
+            uin = ?uin,
+            elements = %element_summary(message),
+            seqs = ?receipt.seqs,
+            latency_ms,
+            "SEND {} : {}",
+            crate::log_target(target),
+            crate::log_chain(message)
+        ),
+        Err(err) => tracing::warn!(
+            target = "proc_qq",
+            to,
+            group_code = ?group_code,
+            uin = ?uin,
+            elements = %element_summary(message),
+            latency_ms,
+            error = ?err,
+            "SEND FAILED {} : {}",
+            crate::log_target(target),
+            crate::log_chain(message)
+        ),
+    }
+}
+
+/// 开启chaos时注入失败和延迟
+async fn chaos_point(_method: &str) -> RQResult<()> {
+    #[cfg(feature = "chaos")]
+    return crate::inject_fault(_method).await;
+    #[cfg(not(feature = "chaos"))]
+    Ok(())
+}
@@ -1,8 +1,22 @@
+use rq_engine::msg::elem::{Dice, FingerGuessing, FlashImage, RQElem};
 use rq_engine::msg::MessageChain;
 use rq_engine::pb::msg::elem::Elem;
+use std::hash::Hasher;
+
+use crate::FnvHasher;
 
 pub trait MessageChainTrait {
     fn append<S: Into<Vec<Elem>>>(self, elem: S) -> Self;
+    /// 消息中骰子的点数
+    fn dice(&self) -> Option<i32>;
+    /// 消息中的猜拳结果
+    fn finger_guessing(&self) -> Option<FingerGuessing>;
+    /// 消息内容的hash, 忽略At的显示名称和图片地址等会变化的字段, 图片按md5计算
+    ///
+    /// 使用64位FNV-1a, 对每段内容的字节计算, 各段之间以0xff分隔, 不同进程和版本之间结果相同, 可以保存
+    fn content_hash(&self) -> u64;
+    /// 消息内容是否相同, 规则和content_hash一致
+    fn same_content(&self, other: &MessageChain) -> bool;
 }
 
 impl MessageChainTrait for MessageChain {
@@ -11,4 +25,80 @@ impl MessageChainTrait for MessageChain {
         chain.push(elem);
         chain
     }
+
+    fn dice(&self) -> Option<i32> {
+        self.clone().into_iter().find_map(|e| match e {
+            RQElem::Dice(Dice { value }) => Some(value),
+            _ => None,
+        })
+    }
+
+    fn finger_guessing(&self) -> Option<FingerGuessing> {
+        self.clone().into_iter().find_map(|e| match e {
+            RQElem::FingerGuessing(finger_guessing) => Some(finger_guessing),
+            _ => None,
+        })
+    }
+
+    fn content_hash(&self) -> u64 {
+        let mut hasher = FnvHasher::default();
+        for part in content_key(self) {
+            hasher.write(part.as_bytes());
+            hasher.write_u8(0xff);
+        }
+        hasher.finish()
+    }
+
+    fn same_content(&self, other: &MessageChain) -> bool {
+        content_key(self) == content_key(other)
+    }
+}
+
+/// 比较用的内容, 相邻的文字合并, 不能识别的元素忽略, 回复只比较被回复的消息
+fn content_key(chain: &MessageChain) -> Vec<String> {
+    let mut key: Vec<String> = vec![];
+    if let Some(reply) = chain.reply() {
+        key.push(format!("reply:{}:{}", reply.sender, reply.reply_seq));
+    }
+    let mut text = String::new();
+    for elem in chain.clone() {
+        let part = match elem {
+            RQElem::Text(t) => {
+                text.push_str(&t.content);
+                continue;
+            }
+            RQElem::At(at) => format!("at:{}", at.target),
+            RQElem::Face(face) => format!("face:{}", face.index),
+            RQElem::MarketFace(face) => format!("market_face:{}", hex(&face.face_id)),
+            RQElem::Dice(dice) => format!("dice:{}", dice.value),
+            RQElem::FingerGuessing(finger_guessing) => {
+                format!("finger_guessing:{:?}", finger_guessing)
+            }
+            RQElem::LightApp(app) => format!("light_app:{}", app.content),
+            RQElem::RichMsg(rich) => format!("rich_msg:{}:{}", rich.service_id, rich.template1),
+            // 好友图片和群图片只要是同一张图就相同
+            RQElem::FriendImage(image) => format!("image:{}", hex(&image.md5)),
+            RQElem::GroupImage(image) => format!("image:{}", hex(&image.md5)),
+            RQElem::FlashImage(FlashImage::FriendImage(image)) => {
+                format!("flash_image:{}", hex(&image.md5))
+            }
+            RQElem::FlashImage(FlashImage::GroupImage(image)) => {
+                format!("flash_image:{}", hex(&image.md5))
+            }
+            RQElem::VideoFile(video) => format!("video:{}", hex(&video.uuid)),
+            _ => continue,
+        };
+        if !text.is_empty() {
+            key.push(format!("text:{}", std::mem::take(&mut text)));
+        }
+        key.push(part);
+    }
+    if !text.is_empty() {
+        key.push(format!("text:{}", text));
+    }
+    key
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
 }
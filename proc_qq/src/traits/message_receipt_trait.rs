@@ -0,0 +1,19 @@
+use rq_engine::structs::MessageReceipt;
+
+pub trait MessageReceiptTrait {
+    /// 服务器是否已经回执 (拿到了有效的seq)
+    fn confirmed(&self) -> bool;
+
+    /// 消息和最近发送的消息重复, 没有实际发送 (见set_send_dedup_window)
+    fn is_deduplicated(&self) -> bool;
+}
+
+impl MessageReceiptTrait for MessageReceipt {
+    fn confirmed(&self) -> bool {
+        !self.seqs.is_empty() && self.seqs.iter().all(|seq| *seq != 0)
+    }
+
+    fn is_deduplicated(&self) -> bool {
+        self.time == crate::DEDUPLICATED_TIME && self.seqs.is_empty()
+    }
+}
@@ -1,16 +1,20 @@
 use async_trait::async_trait;
-use rq_engine::msg::elem::{FlashImage, FriendImage, GroupImage, Text};
+use rq_engine::msg::elem::{Dice, FingerGuessing, FlashImage, FriendImage, GroupImage, Text};
 use rq_engine::msg::MessageChain;
 use rq_engine::pb::msg::elem::Elem;
 use rq_engine::structs::{FriendMessage, GroupMessage, MessageReceipt, TempMessage};
 use rq_engine::{RQError, RQResult};
 use rs_qq::client::event::{FriendMessageEvent, GroupMessageEvent, TempMessageEvent};
-use rs_qq::structs::Group;
+use serde_derive::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::ops::Deref;
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::{ClientTrait, MessageEvent};
+use crate::handler::{content_key, dispatch_content};
+use crate::{degradation, framework_messages, ClientTrait, MessageEvent};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum MessageTarget {
     // Group(group_code,uin)
     Group(i64, i64),
@@ -34,12 +38,89 @@ impl Into<Vec<Elem>> for UploadImage {
     }
 }
 
+/// GIF图片的image_type
+pub const GIF_IMAGE_TYPE: i32 = 2000;
+
+impl UploadImage {
+    pub fn image_type(&self) -> i32 {
+        match self {
+            UploadImage::FriendImage(i) => i.image_type,
+            UploadImage::GroupImage(i) => i.image_type,
+        }
+    }
+
+    /// 是否为GIF格式, 不区分是否为动图, 动图需要在上传前用 is_animated_gif 判断
+    pub fn is_gif(&self) -> bool {
+        self.image_type() == GIF_IMAGE_TYPE
+    }
+}
+
 pub trait MessageTargetTrait: Send + Sync {
     fn target(&self) -> MessageTarget;
 }
 
 pub trait MessageContentTrait: Send + Sync {
-    fn message_content(&self) -> String;
+    fn message_content(&self) -> MessageContent;
+}
+
+/// 消息的文字内容, 同一次分发中只转换一次, 过滤器和处理器共用同一份 (clone不复制文字)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MessageContent(Arc<str>);
+
+impl MessageContent {
+    pub fn into_owned(self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl From<String> for MessageContent {
+    fn from(content: String) -> Self {
+        MessageContent(Arc::from(content))
+    }
+}
+
+impl From<Arc<str>> for MessageContent {
+    fn from(content: Arc<str>) -> Self {
+        MessageContent(content)
+    }
+}
+
+impl Deref for MessageContent {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for MessageContent {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for MessageContent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl PartialEq<str> for MessageContent {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for MessageContent {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for MessageContent {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
 }
 
 #[async_trait]
@@ -71,8 +152,14 @@ pub trait MessageChainParseTrait {
 }
 
 impl MessageContentTrait for MessageChain {
-    fn message_content(&self) -> String {
-        self.to_string()
+    fn message_content(&self) -> MessageContent {
+        MessageContent::from(self.to_string())
+    }
+}
+
+impl MessageTargetTrait for MessageTarget {
+    fn target(&self) -> MessageTarget {
+        *self
     }
 }
 
@@ -83,8 +170,11 @@ impl MessageTargetTrait for GroupMessage {
 }
 
 impl MessageContentTrait for GroupMessage {
-    fn message_content(&self) -> String {
-        self.elements.message_content()
+    fn message_content(&self) -> MessageContent {
+        dispatch_content(
+            content_key(self.from_uin, &self.seqs, &self.rands),
+            &self.elements,
+        )
     }
 }
 
@@ -95,27 +185,14 @@ impl MessageTargetTrait for GroupMessageEvent {
 }
 
 impl MessageContentTrait for GroupMessageEvent {
-    fn message_content(&self) -> String {
+    fn message_content(&self) -> MessageContent {
         self.message.message_content()
     }
 }
 
-#[async_trait]
 impl ClientTrait for GroupMessageEvent {
-    async fn send_message_to_target<S: Into<MessageChain> + Send + Sync>(
-        &self,
-        source: &impl MessageTargetTrait,
-        message: S,
-    ) -> RQResult<MessageReceipt> {
-        self.client.send_message_to_target(source, message).await
-    }
-
-    async fn must_find_group(&self, group_code: i64, auto_reload: bool) -> RQResult<Arc<Group>> {
-        self.client.must_find_group(group_code, auto_reload).await
-    }
-
-    async fn bot_uin(&self) -> i64 {
-        self.client.bot_uin().await
+    fn protocol_client(&self) -> &rs_qq::Client {
+        &self.client
     }
 }
 
@@ -134,7 +211,10 @@ impl MessageSendToSourceTrait for GroupMessageEvent {
     ) -> RQResult<UploadImage> {
         Ok(UploadImage::GroupImage(
             self.client
-                .upload_group_image(self.message.group_code, data.into())
+                .upload_group_image(
+                    self.message.group_code,
+                    prepare_upload_image(data.into()).await?,
+                )
                 .await?,
         ))
     }
@@ -143,11 +223,16 @@ impl MessageSendToSourceTrait for GroupMessageEvent {
         &self,
         data: S,
         codec: u32,
-        _audio_duration: Duration,
+        audio_duration: Duration,
     ) -> RQResult<MessageReceipt> {
+        let data = data.into();
+        crate::validate_audio(&data, audio_duration)?;
+        if crate::capture_dry_run_action("send_audio", || crate::log_target(&self.target())) {
+            return Ok(MessageReceipt::default());
+        }
         let group_audio = self
             .client
-            .upload_group_audio(self.message.group_code, data.into(), codec)
+            .upload_group_audio(self.message.group_code, data, codec)
             .await?;
         self.client
             .send_group_audio(self.message.group_code, group_audio)
@@ -162,8 +247,11 @@ impl MessageTargetTrait for FriendMessage {
 }
 
 impl MessageContentTrait for FriendMessage {
-    fn message_content(&self) -> String {
-        self.elements.to_string()
+    fn message_content(&self) -> MessageContent {
+        dispatch_content(
+            content_key(self.from_uin, &self.seqs, &self.rands),
+            &self.elements,
+        )
     }
 }
 
@@ -174,27 +262,14 @@ impl MessageTargetTrait for FriendMessageEvent {
 }
 
 impl MessageContentTrait for FriendMessageEvent {
-    fn message_content(&self) -> String {
+    fn message_content(&self) -> MessageContent {
         self.message.message_content()
     }
 }
 
-#[async_trait]
 impl ClientTrait for FriendMessageEvent {
-    async fn send_message_to_target<S: Into<MessageChain> + Send + Sync>(
-        &self,
-        source: &impl MessageTargetTrait,
-        message: S,
-    ) -> RQResult<MessageReceipt> {
-        self.client.send_message_to_target(source, message).await
-    }
-
-    async fn must_find_group(&self, group_code: i64, auto_reload: bool) -> RQResult<Arc<Group>> {
-        self.client.must_find_group(group_code, auto_reload).await
-    }
-
-    async fn bot_uin(&self) -> i64 {
-        self.client.bot_uin().await
+    fn protocol_client(&self) -> &rs_qq::Client {
+        &self.client
     }
 }
 
@@ -213,7 +288,10 @@ impl MessageSendToSourceTrait for FriendMessageEvent {
     ) -> RQResult<UploadImage> {
         Ok(UploadImage::FriendImage(
             self.client
-                .upload_friend_image(self.message.from_uin, data.into())
+                .upload_friend_image(
+                    self.message.from_uin,
+                    prepare_upload_image(data.into()).await?,
+                )
                 .await?,
         ))
     }
@@ -224,9 +302,14 @@ impl MessageSendToSourceTrait for FriendMessageEvent {
         _codec: u32,
         audio_duration: Duration,
     ) -> RQResult<MessageReceipt> {
+        let data = data.into();
+        crate::validate_audio(&data, audio_duration)?;
+        if crate::capture_dry_run_action("send_audio", || crate::log_target(&self.target())) {
+            return Ok(MessageReceipt::default());
+        }
         let friend_audio = self
             .client
-            .upload_friend_audio(self.message.from_uin, data.into(), audio_duration)
+            .upload_friend_audio(self.message.from_uin, data, audio_duration)
             .await?;
         self.client
             .send_friend_audio(self.message.from_uin, friend_audio)
@@ -241,8 +324,11 @@ impl MessageTargetTrait for TempMessage {
 }
 
 impl MessageContentTrait for TempMessage {
-    fn message_content(&self) -> String {
-        self.elements.to_string()
+    fn message_content(&self) -> MessageContent {
+        dispatch_content(
+            content_key(self.from_uin, &self.seqs, &self.rands),
+            &self.elements,
+        )
     }
 }
 
@@ -253,27 +339,14 @@ impl MessageTargetTrait for TempMessageEvent {
 }
 
 impl MessageContentTrait for TempMessageEvent {
-    fn message_content(&self) -> String {
+    fn message_content(&self) -> MessageContent {
         self.message.message_content()
     }
 }
 
-#[async_trait]
 impl ClientTrait for TempMessageEvent {
-    async fn send_message_to_target<S: Into<MessageChain> + Send + Sync>(
-        &self,
-        source: &impl MessageTargetTrait,
-        message: S,
-    ) -> RQResult<MessageReceipt> {
-        self.client.send_message_to_target(source, message).await
-    }
-
-    async fn must_find_group(&self, group_code: i64, auto_reload: bool) -> RQResult<Arc<Group>> {
-        self.client.must_find_group(group_code, auto_reload).await
-    }
-
-    async fn bot_uin(&self) -> i64 {
-        self.client.bot_uin().await
+    fn protocol_client(&self) -> &rs_qq::Client {
+        &self.client
     }
 }
 
@@ -288,11 +361,19 @@ impl MessageSendToSourceTrait for TempMessageEvent {
 
     async fn upload_image_to_source<S: Into<Vec<u8>> + Send + Sync>(
         &self,
-        _: S,
+        data: S,
     ) -> RQResult<UploadImage> {
-        RQResult::Err(RQError::Other(
-            "tmp message not supported upload image".to_owned(),
-        ))
+        // 降级时上传为群图片, 发送时替换为图片链接
+        match self.message.group_code {
+            Some(group_code) if degradation().image_as_url => Ok(UploadImage::GroupImage(
+                self.client
+                    .upload_group_image(group_code, prepare_upload_image(data.into()).await?)
+                    .await?,
+            )),
+            _ => RQResult::Err(RQError::Other(
+                framework_messages().unsupported("upload image to temp message"),
+            )),
+        }
     }
 
     async fn send_audio_to_source<S: Into<Vec<u8>> + Send + Sync>(
@@ -301,9 +382,15 @@ impl MessageSendToSourceTrait for TempMessageEvent {
         _codec: u32,
         _audio_duration: Duration,
     ) -> RQResult<MessageReceipt> {
-        RQResult::Err(RQError::Other(
-            "tmp message not supported upload audio".to_owned(),
-        ))
+        match degradation().audio_as_text {
+            Some(text) => {
+                self.send_message_to_source(text.parse_message_chain())
+                    .await
+            }
+            None => RQResult::Err(RQError::Other(
+                framework_messages().unsupported("send audio to temp message"),
+            )),
+        }
     }
 }
 
@@ -318,7 +405,7 @@ impl MessageTargetTrait for MessageEvent {
 }
 
 impl MessageContentTrait for MessageEvent {
-    fn message_content(&self) -> String {
+    fn message_content(&self) -> MessageContent {
         match self {
             MessageEvent::GroupMessage(event) => event.message_content(),
             MessageEvent::FriendMessage(event) => event.message_content(),
@@ -327,22 +414,13 @@ impl MessageContentTrait for MessageEvent {
     }
 }
 
-#[async_trait]
 impl ClientTrait for MessageEvent {
-    async fn send_message_to_target<S: Into<MessageChain> + Send + Sync>(
-        &self,
-        source: &impl MessageTargetTrait,
-        message: S,
-    ) -> RQResult<MessageReceipt> {
-        self.client().send_message_to_target(source, message).await
-    }
-
-    async fn must_find_group(&self, group_code: i64, auto_reload: bool) -> RQResult<Arc<Group>> {
-        self.client().must_find_group(group_code, auto_reload).await
-    }
-
-    async fn bot_uin(&self) -> i64 {
-        self.client().bot_uin().await
+    fn protocol_client(&self) -> &rs_qq::Client {
+        match self {
+            MessageEvent::GroupMessage(e) => &e.client,
+            MessageEvent::FriendMessage(e) => &e.client,
+            MessageEvent::TempMessage(e) => &e.client,
+        }
     }
 }
 
@@ -449,3 +527,27 @@ impl MessageChainParseTrait for UploadImage {
         }
     }
 }
+
+impl MessageChainParseTrait for Dice {
+    fn parse_message_chain(self) -> MessageChain {
+        let mut chain = MessageChain::default();
+        chain.push(self);
+        chain
+    }
+}
+
+impl MessageChainParseTrait for FingerGuessing {
+    fn parse_message_chain(self) -> MessageChain {
+        let mut chain = MessageChain::default();
+        chain.push(self);
+        chain
+    }
+}
+
+/// 开启image_compress时, 上传前压缩过大的图片, 然后按照附件限制检查
+pub(crate) async fn prepare_upload_image(data: Vec<u8>) -> RQResult<Vec<u8>> {
+    #[cfg(feature = "image_compress")]
+    let data = crate::compress_image_for_upload(data).await;
+    crate::validate_image(&data)?;
+    Ok(data)
+}
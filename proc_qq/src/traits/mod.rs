@@ -2,10 +2,16 @@ pub use client_trait::*;
 pub use group_trait::*;
 pub use member_trait::*;
 pub use message_chain_trait::*;
+pub use message_receipt_trait::*;
 pub use message_trait::*;
+pub use recall_trait::*;
+pub use request_trait::*;
 
 mod client_trait;
 mod group_trait;
 mod member_trait;
 mod message_chain_trait;
+mod message_receipt_trait;
 mod message_trait;
+mod recall_trait;
+mod request_trait;
@@ -0,0 +1,40 @@
+use async_trait::async_trait;
+use rq_engine::RQResult;
+use rs_qq::client::event::GroupMessageEvent;
+
+use crate::{recent_group_messages, ClientTrait, MessageIdTrait};
+
+#[async_trait]
+pub trait GroupMessageRecallTrait {
+    /// 撤回触发事件的消息, 机器人需要是管理员
+    async fn recall_message(&self) -> RQResult<()>;
+
+    /// 撤回uin在本群缓存中最近的n条消息, 返回撤回的条数
+    async fn recall_recent(&self, uin: i64, n: usize) -> RQResult<usize>;
+}
+
+#[async_trait]
+impl GroupMessageRecallTrait for GroupMessageEvent {
+    async fn recall_message(&self) -> RQResult<()> {
+        self.recall(&self.message_id()).await
+    }
+
+    async fn recall_recent(&self, uin: i64, n: usize) -> RQResult<usize> {
+        let mut messages: Vec<_> = recent_group_messages(self.message.group_code, usize::MAX)
+            .into_iter()
+            .filter(|message| message.from_uin == uin)
+            .collect();
+        let messages = messages.split_off(messages.len().saturating_sub(n));
+        let mut recalled = 0;
+        for message in messages.iter().rev() {
+            match self.recall(&message.message_id()).await {
+                Ok(_) => recalled += 1,
+                Err(err) => {
+                    // 消息可能已经被撤回, 继续撤回其他消息
+                    tracing::warn!(target = "proc_qq", "撤回消息失败 : {:?}", err);
+                }
+            }
+        }
+        Ok(recalled)
+    }
+}
@@ -0,0 +1,159 @@
+use async_trait::async_trait;
+use rq_engine::RQResult;
+use rs_qq::client::event::{FriendRequestEvent, GroupRequestEvent, SelfInvitedEvent};
+
+#[async_trait]
+pub trait RequestTrait {
+    /// 同意请求, 记录到审计日志
+    async fn accept(&self) -> RQResult<()>;
+
+    /// 拒绝请求, 记录到审计日志
+    async fn reject(&self, reason: &str) -> RQResult<()>;
+}
+
+async fn solve_group_request(
+    event: &GroupRequestEvent,
+    accept: bool,
+    reason: &str,
+) -> RQResult<()> {
+    let request = &event.request;
+    if crate::capture_dry_run_action(
+        if accept {
+            "accept_request"
+        } else {
+            "reject_request"
+        },
+        || format!("{} {}", request.group_code, request.req_uin),
+    ) {
+        return Ok(());
+    }
+    event
+        .client
+        .solve_group_system_message(
+            request.msg_seq,
+            request.req_uin,
+            request.group_code,
+            request.suspicious,
+            request.invitor_uin.is_some(),
+            accept,
+            false,
+            reason.to_owned(),
+        )
+        .await?;
+    crate::record_audit(
+        if accept {
+            "accept_request"
+        } else {
+            "reject_request"
+        },
+        Some(request.group_code),
+        Some(request.req_uin),
+        request.message.clone(),
+    )
+    .await;
+    Ok(())
+}
+
+#[async_trait]
+impl RequestTrait for GroupRequestEvent {
+    async fn accept(&self) -> RQResult<()> {
+        solve_group_request(self, true, "").await
+    }
+
+    async fn reject(&self, reason: &str) -> RQResult<()> {
+        solve_group_request(self, false, reason).await
+    }
+}
+
+async fn solve_friend_request(event: &FriendRequestEvent, accept: bool) -> RQResult<()> {
+    let request = &event.request;
+    if crate::capture_dry_run_action(
+        if accept {
+            "accept_friend"
+        } else {
+            "reject_friend"
+        },
+        || request.req_uin.to_string(),
+    ) {
+        return Ok(());
+    }
+    event
+        .client
+        .solve_friend_system_message(request.msg_seq, request.req_uin, accept)
+        .await?;
+    crate::record_audit(
+        if accept {
+            "accept_friend"
+        } else {
+            "reject_friend"
+        },
+        None,
+        Some(request.req_uin),
+        request.message.clone(),
+    )
+    .await;
+    Ok(())
+}
+
+#[async_trait]
+impl RequestTrait for FriendRequestEvent {
+    async fn accept(&self) -> RQResult<()> {
+        solve_friend_request(self, true).await
+    }
+
+    /// 好友请求不能附带拒绝的理由
+    async fn reject(&self, _reason: &str) -> RQResult<()> {
+        solve_friend_request(self, false).await
+    }
+}
+
+async fn solve_self_invited(event: &SelfInvitedEvent, accept: bool, reason: &str) -> RQResult<()> {
+    let request = &event.request;
+    if crate::capture_dry_run_action(
+        if accept {
+            "accept_invitation"
+        } else {
+            "reject_invitation"
+        },
+        || format!("{} {}", request.group_code, request.invitor_uin),
+    ) {
+        return Ok(());
+    }
+    event
+        .client
+        .solve_group_system_message(
+            request.msg_seq,
+            request.invitor_uin,
+            request.group_code,
+            false,
+            true,
+            accept,
+            false,
+            reason.to_owned(),
+        )
+        .await?;
+    crate::record_audit(
+        if accept {
+            "accept_invitation"
+        } else {
+            "reject_invitation"
+        },
+        Some(request.group_code),
+        Some(request.invitor_uin),
+        request.group_name.clone(),
+    )
+    .await;
+    Ok(())
+}
+
+/// 机器人被邀请入群, 同意后加入群
+#[async_trait]
+impl RequestTrait for SelfInvitedEvent {
+    async fn accept(&self) -> RQResult<()> {
+        solve_self_invited(self, true, "").await
+    }
+
+    async fn reject(&self, reason: &str) -> RQResult<()> {
+        solve_self_invited(self, false, reason).await
+    }
+}
@@ -0,0 +1,53 @@
+use rq_engine::msg::elem::{FlashImage, RQElem, Text};
+use rq_engine::msg::MessageChain;
+use rq_engine::{RQError, RQResult};
+
+use crate::MessageTarget;
+
+/// 把收到的消息转换为可以发送到target的消息
+///
+/// 图片会下载后重新上传到target, 临时会话不能上传图片, 用 "[图片]" 代替
+pub async fn transfer_message_chain(
+    client: &rs_qq::Client,
+    chain: &MessageChain,
+    target: &MessageTarget,
+) -> RQResult<MessageChain> {
+    let mut result = MessageChain::default();
+    for elem in chain.clone().into_iter() {
+        let url = match elem {
+            RQElem::GroupImage(image) => image.url(),
+            RQElem::FriendImage(image) => image.url(),
+            RQElem::FlashImage(FlashImage::GroupImage(image)) => image.url(),
+            RQElem::FlashImage(FlashImage::FriendImage(image)) => image.url(),
+            // 回复、语音等无法转发的元素
+            RQElem::Other(_) => continue,
+            elem => {
+                result.push(elem);
+                continue;
+            }
+        };
+        let data = download(&url).await?;
+        match target {
+            MessageTarget::Group(group_code, _) => {
+                result.push(client.upload_group_image(*group_code, data).await?)
+            }
+            MessageTarget::Private(uin) => {
+                result.push(client.upload_friend_image(*uin, data).await?)
+            }
+            MessageTarget::Temp(..) => result.push(Text::new("[图片]".to_owned())),
+        }
+    }
+    Ok(result)
+}
+
+async fn download(url: &str) -> RQResult<Vec<u8>> {
+    let response = reqwest::get(url)
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| RQError::Other(format!("download image : {}", err)))?;
+    Ok(response
+        .bytes()
+        .await
+        .map_err(|err| RQError::Other(format!("download image : {}", err)))?
+        .to_vec())
+}
@@ -0,0 +1,149 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::{BridgeMessage, BridgeSegment, BridgeTransport};
+
+/// 翻译服务, 由使用者实现 (调用翻译API或本地模型)
+#[async_trait]
+pub trait Translator: Send + Sync {
+    /// 翻译文字, from为None时由翻译服务判断
+    async fn translate(&self, text: &str, from: Option<&str>, to: &str) -> anyhow::Result<String>;
+
+    /// 判断文字的语言, 默认按照使用的字符判断
+    fn detect_language(&self, text: &str) -> Option<String> {
+        detect_language(text).map(|lang| lang.to_owned())
+    }
+}
+
+/// 按照文字中最多的字符种类简单判断语言, 返回ISO 639-1代码, 无法判断时返回None
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let mut counts: HashMap<&'static str, usize> = HashMap::new();
+    let mut kana = false;
+    for c in text.chars() {
+        let lang = match c as u32 {
+            0x3040..=0x30FF => {
+                kana = true;
+                "ja"
+            }
+            0x4E00..=0x9FFF | 0x3400..=0x4DBF => "zh",
+            0xAC00..=0xD7AF | 0x1100..=0x11FF => "ko",
+            0x0400..=0x04FF => "ru",
+            0x0600..=0x06FF => "ar",
+            0x0E00..=0x0E7F => "th",
+            _ if c.is_ascii_alphabetic() => "en",
+            _ => continue,
+        };
+        *counts.entry(lang).or_default() += 1;
+    }
+    // 日文中的汉字不单独作为中文
+    if kana {
+        if let Some(zh) = counts.remove("zh") {
+            *counts.entry("ja").or_default() += zh;
+        }
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(lang, _)| lang)
+}
+
+// 最多缓存的翻译数量
+const CACHE_CAPACITY: usize = 1000;
+
+/// 缓存翻译结果, 群里重复的短句不再调用翻译服务
+pub struct CachedTranslator {
+    inner: Arc<dyn Translator>,
+    cache: Mutex<HashMap<(String, String), String>>,
+}
+
+impl CachedTranslator {
+    pub fn new(inner: Arc<dyn Translator>) -> Self {
+        Self {
+            inner,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl Translator for CachedTranslator {
+    async fn translate(&self, text: &str, from: Option<&str>, to: &str) -> anyhow::Result<String> {
+        let key = (text.to_owned(), to.to_owned());
+        if let Some(translated) = self.cache.lock().unwrap().get(&key) {
+            return Ok(translated.clone());
+        }
+        let translated = self.inner.translate(text, from, to).await?;
+        let mut cache = self.cache.lock().unwrap();
+        if cache.len() >= CACHE_CAPACITY {
+            cache.clear();
+        }
+        cache.insert(key, translated.clone());
+        Ok(translated)
+    }
+
+    fn detect_language(&self, text: &str) -> Option<String> {
+        self.inner.detect_language(text)
+    }
+}
+
+/// 包装BridgeTransport, 互通的消息在两边的语言之间翻译
+pub struct TranslatingTransport<T: BridgeTransport> {
+    inner: T,
+    translator: CachedTranslator,
+    // QQ群使用的语言
+    local_lang: String,
+    // 其他平台使用的语言
+    remote_lang: String,
+}
+
+impl<T: BridgeTransport> TranslatingTransport<T> {
+    pub fn new<L: Into<String>, R: Into<String>>(
+        inner: T,
+        translator: Arc<dyn Translator>,
+        local_lang: L,
+        remote_lang: R,
+    ) -> Self {
+        Self {
+            inner,
+            translator: CachedTranslator::new(translator),
+            local_lang: local_lang.into(),
+            remote_lang: remote_lang.into(),
+        }
+    }
+
+    async fn translate_message(&self, mut message: BridgeMessage, to: &str) -> BridgeMessage {
+        for segment in message.segments.iter_mut() {
+            if let BridgeSegment::Text(text) = segment {
+                let from = self.translator.detect_language(text);
+                if from.is_none() || from.as_deref() == Some(to) {
+                    continue;
+                }
+                match self.translator.translate(text, from.as_deref(), to).await {
+                    Ok(translated) => *text = translated,
+                    Err(err) => tracing::warn!(target = "proc_qq", "翻译失败 : {:?}", err),
+                }
+            }
+        }
+        message
+    }
+}
+
+#[async_trait]
+impl<T: BridgeTransport> BridgeTransport for TranslatingTransport<T> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn send(&self, message: BridgeMessage) -> anyhow::Result<()> {
+        let message = self.translate_message(message, &self.remote_lang).await;
+        self.inner.send(message).await
+    }
+
+    async fn receive(&self) -> anyhow::Result<Option<BridgeMessage>> {
+        Ok(match self.inner.receive().await? {
+            Some(message) => Some(self.translate_message(message, &self.local_lang).await),
+            None => None,
+        })
+    }
+}
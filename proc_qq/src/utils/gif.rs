@@ -0,0 +1,96 @@
+/// 是否为GIF图片
+pub fn is_gif(data: &[u8]) -> bool {
+    data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a")
+}
+
+/// 是否为多帧的GIF动图, 框架中判断动图 (压缩时跳过) 都使用这个函数
+pub fn is_animated_gif(data: &[u8]) -> bool {
+    gif_frame_count(data)
+        .map(|count| count > 1)
+        .unwrap_or(false)
+}
+
+/// GIF的帧数, 不是GIF或数据不完整时返回None
+pub fn gif_frame_count(data: &[u8]) -> Option<usize> {
+    if !is_gif(data) || data.len() < 13 {
+        return None;
+    }
+    let mut pos = 13 + color_table_size(data[10]);
+    let mut frames = 0;
+    loop {
+        match *data.get(pos)? {
+            // 扩展块
+            0x21 => pos = skip_sub_blocks(data, pos + 2)?,
+            // 图像块
+            0x2C => {
+                let flags = *data.get(pos + 9)?;
+                pos += 10 + color_table_size(flags);
+                // LZW最小码长
+                pos = skip_sub_blocks(data, pos + 1)?;
+                frames += 1;
+            }
+            // 结束
+            0x3B => return Some(frames),
+            _ => return None,
+        }
+    }
+}
+
+fn color_table_size(flags: u8) -> usize {
+    if flags & 0x80 != 0 {
+        3 * (1 << ((flags & 0x07) + 1))
+    } else {
+        0
+    }
+}
+
+fn skip_sub_blocks(data: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let size = *data.get(pos)? as usize;
+        pos += 1;
+        if size == 0 {
+            return Some(pos);
+        }
+        pos += size;
+    }
+}
+
+/// 1x1像素, frames帧的GIF, 用于测试
+#[cfg(test)]
+pub(crate) fn sample_gif(frames: usize) -> Vec<u8> {
+    let mut data = b"GIF89a".to_vec();
+    // 逻辑屏幕 1x1, 2色的全局颜色表
+    data.extend([1, 0, 1, 0, 0x80, 0, 0]);
+    data.extend([0, 0, 0, 0xFF, 0xFF, 0xFF]);
+    for _ in 0..frames {
+        // 图形控制扩展, 延迟0.1秒
+        data.extend([0x21, 0xF9, 4, 0, 10, 0, 0, 0]);
+        // 图像块和LZW数据
+        data.extend([0x2C, 0, 0, 0, 0, 1, 0, 1, 0, 0]);
+        data.extend([2, 2, 0x44, 0x01, 0]);
+    }
+    data.push(0x3B);
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_counts() {
+        assert_eq!(gif_frame_count(&sample_gif(1)), Some(1));
+        assert_eq!(gif_frame_count(&sample_gif(3)), Some(3));
+        assert!(!is_animated_gif(&sample_gif(1)));
+        assert!(is_animated_gif(&sample_gif(2)));
+    }
+
+    #[test]
+    fn invalid_data() {
+        let gif = sample_gif(2);
+        assert_eq!(gif_frame_count(&gif[..gif.len() - 1]), None);
+        assert_eq!(gif_frame_count(b"\x89PNG\r\n\x1a\n"), None);
+        assert!(!is_gif(b"GIF"));
+        assert!(!is_animated_gif(&gif[..20]));
+    }
+}
@@ -0,0 +1,11 @@
+pub use base64::*;
+pub use fnv::*;
+pub use gif::*;
+pub use random::*;
+pub use text::*;
+
+mod base64;
+mod fnv;
+mod gif;
+mod random;
+mod text;
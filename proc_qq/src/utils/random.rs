@@ -0,0 +1,99 @@
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::FnvHasher;
+
+/// 可指定种子的随机数生成器 (SplitMix64), 相同的种子得到相同的序列
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// 使用当前时间作为种子
+    pub fn from_time() -> Self {
+        Self::new(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos() as u64,
+        )
+    }
+
+    /// 由任意可哈希的值生成种子, 比如 (群号, QQ号, 日期) 得到每人每天固定的结果
+    ///
+    /// 使用FNV-1a哈希, 重启之后同样的key仍然得到同样的结果
+    pub fn from_key<K: Hash>(key: K) -> Self {
+        let mut hasher = FnvHasher::default();
+        key.hash(&mut hasher);
+        Self::new(hasher.finish())
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// [start, end) 范围内的随机数
+    pub fn gen_range(&mut self, range: Range<i64>) -> i64 {
+        if range.end <= range.start {
+            return range.start;
+        }
+        let span = (range.end - range.start) as u64;
+        range.start + (self.next_u64() % span) as i64
+    }
+
+    /// 概率为p时返回true
+    pub fn gen_bool(&mut self, p: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            items.get(self.gen_range(0..items.len() as i64) as usize)
+        }
+    }
+
+    /// 掷骰子, 1-6
+    pub fn roll_dice(&mut self) -> i32 {
+        self.gen_range(1..7) as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_key_same_sequence() {
+        let mut a = SeededRng::from_key((123456i64, 10000i64, "2024-05-01"));
+        let mut b = SeededRng::from_key((123456i64, 10000i64, "2024-05-01"));
+        let mut c = SeededRng::from_key((123456i64, 10001i64, "2024-05-01"));
+        let a: Vec<u64> = (0..4).map(|_| a.next_u64()).collect();
+        let b: Vec<u64> = (0..4).map(|_| b.next_u64()).collect();
+        let c: Vec<u64> = (0..4).map(|_| c.next_u64()).collect();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn ranges() {
+        let mut rng = SeededRng::new(650);
+        for _ in 0..1000 {
+            assert!((1..7).contains(&rng.roll_dice()));
+            assert!((-5..5).contains(&rng.gen_range(-5..5)));
+        }
+        assert_eq!(rng.gen_range(3..3), 3);
+        assert_eq!(rng.choose::<i32>(&[]), None);
+    }
+}
@@ -0,0 +1,155 @@
+/// CQ码形式的占位符 (如 [CQ:face,id=1]) 按一个表情计算宽度
+const PLACEHOLDER_WIDTH: usize = 2;
+
+/// 单个字符的显示宽度: 全角字符及emoji为2, 组合字符与控制字符为0, 其他为1
+pub fn char_display_width(c: char) -> usize {
+    if c.is_control() || is_zero_width(c) {
+        0
+    } else if is_wide(c) {
+        2
+    } else {
+        1
+    }
+}
+
+/// 文本的显示宽度
+pub fn display_width(text: &str) -> usize {
+    text_units(text).iter().map(|(_, width)| width).sum()
+}
+
+/// 按显示宽度截断文本, 超出时追加ellipsis, 不会拆开emoji组合以及CQ码
+pub fn truncate_by_width(text: &str, max_width: usize, ellipsis: &str) -> String {
+    if display_width(text) <= max_width {
+        return text.to_owned();
+    }
+    let budget = max_width.saturating_sub(display_width(ellipsis));
+    let mut result = String::new();
+    let mut width = 0;
+    for (unit, unit_width) in text_units(text) {
+        if width + unit_width > budget {
+            break;
+        }
+        width += unit_width;
+        result.push_str(unit);
+    }
+    result.push_str(ellipsis);
+    result
+}
+
+/// 按显示宽度切分文本, 每段宽度不超过max_width (单个超宽的字符单独成段)
+pub fn split_by_width(text: &str, max_width: usize) -> Vec<String> {
+    let mut result = vec![];
+    let mut current = String::new();
+    let mut width = 0;
+    for (unit, unit_width) in text_units(text) {
+        if width + unit_width > max_width && !current.is_empty() {
+            result.push(std::mem::take(&mut current));
+            width = 0;
+        }
+        width += unit_width;
+        current.push_str(unit);
+    }
+    if !current.is_empty() {
+        result.push(current);
+    }
+    result
+}
+
+/// 将文本拆成不可分割的显示单元及其宽度
+fn text_units(text: &str) -> Vec<(&str, usize)> {
+    let mut units = vec![];
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if text[start..].starts_with("[CQ:") {
+            if let Some(len) = text[start..].find(']') {
+                let end = start + len + 1;
+                while let Some(&(i, _)) = chars.peek() {
+                    if i >= end {
+                        break;
+                    }
+                    chars.next();
+                }
+                units.push((&text[start..end], PLACEHOLDER_WIDTH));
+                continue;
+            }
+        }
+        let mut end = start + c.len_utf8();
+        // 组合字符附着于前一个字符, 零宽连接符连接下一个字符
+        let mut join = c == '\u{200D}';
+        while let Some(&(i, next)) = chars.peek() {
+            if !join && !is_zero_width(next) {
+                break;
+            }
+            join = next == '\u{200D}';
+            end = i + next.len_utf8();
+            chars.next();
+        }
+        units.push((&text[start..end], char_display_width(c)));
+    }
+    units
+}
+
+fn is_zero_width(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x0300..=0x036F
+            | 0x200B..=0x200F
+            | 0x20D0..=0x20FF
+            | 0xFE00..=0xFE0F
+            | 0xFE20..=0xFE2F
+            | 0x1F3FB..=0x1F3FF
+            | 0xE0020..=0xE007F
+    )
+}
+
+fn is_wide(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1100..=0x115F
+            | 0x2600..=0x27BF
+            | 0x2E80..=0x303E
+            | 0x3041..=0x33FF
+            | 0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xA000..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x1F300..=0x1F64F
+            | 0x1F680..=0x1F6FF
+            | 0x1F900..=0x1F9FF
+            | 0x20000..=0x3FFFD
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn widths() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("你好"), 4);
+        assert_eq!(display_width("👍🏻"), 2);
+        assert_eq!(display_width("👨‍👩‍👧"), 2);
+        assert_eq!(display_width("a[CQ:face,id=1]b"), 4);
+    }
+
+    #[test]
+    fn truncates_whole_units() {
+        assert_eq!(truncate_by_width("你好世界", 8, "…"), "你好世界");
+        assert_eq!(truncate_by_width("你好世界", 6, "…"), "你好…");
+        assert_eq!(truncate_by_width("ab[CQ:face,id=1]cd", 4, ".."), "ab..");
+        assert_eq!(truncate_by_width("a👨‍👩‍👧b", 3, ""), "a👨‍👩‍👧");
+    }
+
+    #[test]
+    fn splits_by_width() {
+        assert_eq!(split_by_width("abcde", 2), vec!["ab", "cd", "e"]);
+        assert_eq!(split_by_width("a你b", 2), vec!["a", "你", "b"]);
+        assert_eq!(split_by_width("你", 1), vec!["你"]);
+        assert!(split_by_width("", 3).is_empty());
+    }
+}
@@ -0,0 +1,191 @@
+use crate::{
+    MessageChainParseTrait, MessageEvent, MessageEventProcess, MessageSendToSourceTrait, Module,
+    ModuleEventHandler, ModuleEventProcess,
+};
+use async_trait::async_trait;
+use serde_derive::Deserialize;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use wasmi::{Config, Engine, Linker, Store};
+
+// 插件返回内容的最大长度, 长度由插件给出, 分配之前先检查
+const MAX_OUTPUT: usize = 1024 * 1024;
+
+/// 实验性的wasm插件
+///
+/// 插件需要导出 :
+///
+/// ```text
+/// memory                                  线性内存
+/// alloc(len: i32) -> i32                  分配len字节, 返回地址
+/// handle(ptr: i32, len: i32) -> i64       输入为 MessageEvent::to_json 的JSON
+///                                         返回 (地址 << 32) | 长度, 0 表示不处理
+/// ```
+///
+/// 返回的内容为 {"handled": true, "replies": ["回复的文字"]}
+pub struct WasmPlugin {
+    path: PathBuf,
+    engine: Engine,
+    module: RwLock<Arc<wasmi::Module>>,
+    // 每次调用可用的燃料, 防止插件死循环
+    fuel: u64,
+}
+
+#[derive(Deserialize, Default)]
+struct PluginOutput {
+    #[serde(default)]
+    handled: bool,
+    #[serde(default)]
+    replies: Vec<String>,
+}
+
+impl WasmPlugin {
+    pub fn load<P: Into<PathBuf>>(path: P) -> anyhow::Result<Self> {
+        let path = path.into();
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = compile(&engine, &path)?;
+        Ok(Self {
+            path,
+            engine,
+            module: RwLock::new(Arc::new(module)),
+            fuel: 10_000_000,
+        })
+    }
+
+    /// 重新读取wasm文件, 之后的事件使用新的插件
+    pub fn reload(&self) -> anyhow::Result<()> {
+        let module = compile(&self.engine, &self.path)?;
+        *self.module.write().unwrap() = Arc::new(module);
+        tracing::info!(target = "proc_qq", "重新加载插件 : {:?}", self.path);
+        Ok(())
+    }
+
+    // 每次调用使用新的实例, 插件之间和事件之间不共享状态
+    fn call(&self, input: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+        let module = self.module.read().unwrap().clone();
+        let mut store = Store::new(&self.engine, ());
+        store.add_fuel(self.fuel).map_err(wasm_error)?;
+        let instance = Linker::<()>::new(&self.engine)
+            .instantiate(&mut store, &module)?
+            .start(&mut store)?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| anyhow::Error::msg("plugin does not export memory"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&store, "alloc")?;
+        let handle = instance.get_typed_func::<(i32, i32), i64>(&store, "handle")?;
+        let ptr = alloc.call(&mut store, input.len() as i32)?;
+        memory
+            .write(&mut store, ptr as usize, input)
+            .map_err(wasm_error)?;
+        let packed = handle.call(&mut store, (ptr, input.len() as i32))?;
+        let (ptr, len) = match output_range(packed, memory.data(&store).len())? {
+            Some(range) => range,
+            None => return Ok(None),
+        };
+        let mut output = vec![0; len];
+        memory.read(&store, ptr, &mut output).map_err(wasm_error)?;
+        Ok(Some(output))
+    }
+}
+
+// 解析handle的返回值 (地址 << 32) | 长度, 超出线性内存或者MAX_OUTPUT时返回错误
+fn output_range(packed: i64, memory_size: usize) -> anyhow::Result<Option<(usize, usize)>> {
+    if packed == 0 {
+        return Ok(None);
+    }
+    let packed = packed as u64;
+    let ptr = (packed >> 32) as usize;
+    let len = (packed & 0xFFFF_FFFF) as usize;
+    if len > MAX_OUTPUT {
+        return Err(anyhow::Error::msg(format!(
+            "plugin output too large : {} bytes",
+            len
+        )));
+    }
+    if !matches!(ptr.checked_add(len), Some(end) if end <= memory_size) {
+        return Err(anyhow::Error::msg(format!(
+            "plugin output out of memory bounds : {}+{}",
+            ptr, len
+        )));
+    }
+    Ok(Some((ptr, len)))
+}
+
+fn wasm_error<E: std::fmt::Display>(err: E) -> anyhow::Error {
+    anyhow::Error::msg(err.to_string())
+}
+
+fn compile(engine: &Engine, path: &PathBuf) -> anyhow::Result<wasmi::Module> {
+    let bytes = std::fs::read(path)?;
+    Ok(wasmi::Module::new(engine, &bytes[..])?)
+}
+
+struct WasmPluginHandler {
+    plugin: Arc<WasmPlugin>,
+}
+
+#[async_trait]
+impl MessageEventProcess for WasmPluginHandler {
+    async fn handle(&self, event: &MessageEvent) -> anyhow::Result<bool> {
+        let input = serde_json::to_vec(&event.to_json())?;
+        let plugin = self.plugin.clone();
+        let output = match tokio::task::spawn_blocking(move || plugin.call(&input)).await?? {
+            Some(output) => output,
+            None => return Ok(false),
+        };
+        let output: PluginOutput = serde_json::from_slice(&output)?;
+        for reply in output.replies {
+            event
+                .send_message_to_source(reply.parse_message_chain())
+                .await?;
+        }
+        Ok(output.handled)
+    }
+}
+
+impl Into<ModuleEventHandler> for WasmPluginHandler {
+    fn into(self) -> ModuleEventHandler {
+        ModuleEventHandler::new("wasm_plugin", ModuleEventProcess::Message(Box::new(self)))
+    }
+}
+
+/// 加载wasm插件作为模块, 和其他模块一起注册, 返回的WasmPlugin可以用于热更新
+pub fn wasm_plugin_module<P: Into<PathBuf>>(
+    id: &str,
+    path: P,
+) -> anyhow::Result<(Module, Arc<WasmPlugin>)> {
+    let plugin = Arc::new(WasmPlugin::load(path)?);
+    let module = crate::module!(
+        id,
+        id,
+        WasmPluginHandler {
+            plugin: plugin.clone()
+        }
+    );
+    Ok((module, plugin))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packed(ptr: u32, len: u32) -> i64 {
+        (((ptr as u64) << 32) | len as u64) as i64
+    }
+
+    #[test]
+    fn output_is_clamped() {
+        let memory = 64 * 1024;
+        assert_eq!(output_range(0, memory).unwrap(), None);
+        assert_eq!(
+            output_range(packed(1024, 10), memory).unwrap(),
+            Some((1024, 10))
+        );
+        assert!(output_range(packed(memory as u32 - 5, 10), memory).is_err());
+        assert!(output_range(packed(0, u32::MAX), usize::MAX).is_err());
+        assert!(output_range(packed(u32::MAX, 1), memory).is_err());
+        assert!(output_range(-1, memory).is_err());
+    }
+}
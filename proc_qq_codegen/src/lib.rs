@@ -1,9 +1,15 @@
 use proc_macro::TokenStream;
 
+use proc_macro2::TokenStream as TokenStream2;
 use proc_macro_error::{abort, proc_macro_error};
-use quote::quote;
+use quote::{format_ident, quote};
+use syn::parse::ParseStream;
+use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
-use syn::{parse_macro_input, FnArg};
+use syn::{
+    parse_macro_input, Attribute, AttributeArgs, FnArg, GenericArgument, Ident, Lit, LitFloat,
+    LitInt, LitStr, Meta, NestedMeta, Pat, PathArguments, Token, Type,
+};
 
 /// debug = note expanded codes if env PROC_QQ_CODEGEN_DEBUG exists
 macro_rules! emit {
@@ -25,16 +31,22 @@ macro_rules! emit {
 /// event proc
 #[proc_macro_error]
 #[proc_macro_attribute]
-pub fn event(_: TokenStream, input: TokenStream) -> TokenStream {
+pub fn event(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as AttributeArgs);
+    let regexp = parse_event_args(&args);
     // must append to async fn
     let method = parse_macro_input!(input as syn::ItemFn);
     if method.sig.asyncness.is_none() {
         abort!(&method.sig.span(), "必须是async方法");
     }
-    // params check
+    // params check, 使用regexp时可以追加捕获组参数, #[config]参数从配置文件中读取
     let params = &method.sig.inputs;
-    if params.len() != 1 {
-        abort!(&method.sig.span(), "必须有且只能有一个参数");
+    let (config_params, capture_params): (Vec<&FnArg>, Vec<&FnArg>) = params
+        .iter()
+        .skip(1)
+        .partition(|param| config_section(param).is_some());
+    if params.is_empty() || (regexp.is_none() && !capture_params.is_empty()) {
+        abort!(&method.sig.span(), "必须有且只能有一个事件参数");
     };
     let param = params.first().unwrap();
     let param = match param {
@@ -65,6 +77,10 @@ pub fn event(_: TokenStream, input: TokenStream) -> TokenStream {
             quote! {::proc_qq::GroupRequestEventProcess},
             quote! {::proc_qq::ModuleEventProcess::GroupRequest},
         ),
+        "& SelfInvitedEvent" => (
+            quote! {::proc_qq::SelfInvitedEventProcess},
+            quote! {::proc_qq::ModuleEventProcess::SelfInvited},
+        ),
         "& FriendRequestEvent" => (
             quote! {::proc_qq::FriendRequestEventProcess},
             quote! {::proc_qq::ModuleEventProcess::FriendRequest},
@@ -81,10 +97,30 @@ pub fn event(_: TokenStream, input: TokenStream) -> TokenStream {
             quote! {::proc_qq::DeleteFriendEventProcess},
             quote! {::proc_qq::ModuleEventProcess::DeleteFriend},
         ),
+        "& FriendAddedEvent" => (
+            quote! {::proc_qq::FriendAddedEventProcess},
+            quote! {::proc_qq::ModuleEventProcess::FriendAdded},
+        ),
+        "& FriendRemovedEvent" => (
+            quote! {::proc_qq::FriendRemovedEventProcess},
+            quote! {::proc_qq::ModuleEventProcess::FriendRemoved},
+        ),
+        "& FriendUpdatedEvent" => (
+            quote! {::proc_qq::FriendUpdatedEventProcess},
+            quote! {::proc_qq::ModuleEventProcess::FriendUpdated},
+        ),
+        "& FriendPresenceChangedEvent" => (
+            quote! {::proc_qq::FriendPresenceChangedEventProcess},
+            quote! {::proc_qq::ModuleEventProcess::FriendPresenceChanged},
+        ),
         "& GroupMuteEvent" => (
             quote! {::proc_qq::GroupMuteEventProcess},
             quote! {::proc_qq::ModuleEventProcess::GroupMute},
         ),
+        "& MuteExpiredEvent" => (
+            quote! {::proc_qq::MuteExpiredEventProcess},
+            quote! {::proc_qq::ModuleEventProcess::MuteExpired},
+        ),
         "& GroupLeaveEvent" => (
             quote! {::proc_qq::GroupLeaveEventProcess},
             quote! {::proc_qq::ModuleEventProcess::GroupLeave},
@@ -93,6 +129,22 @@ pub fn event(_: TokenStream, input: TokenStream) -> TokenStream {
             quote! {::proc_qq::GroupNameUpdateEventProcess},
             quote! {::proc_qq::ModuleEventProcess::GroupNameUpdate},
         ),
+        "& BotJoinedGroupEvent" => (
+            quote! {::proc_qq::BotJoinedGroupEventProcess},
+            quote! {::proc_qq::ModuleEventProcess::BotJoinedGroup},
+        ),
+        "& BotRemovedFromGroupEvent" => (
+            quote! {::proc_qq::BotRemovedFromGroupEventProcess},
+            quote! {::proc_qq::ModuleEventProcess::BotRemovedFromGroup},
+        ),
+        "& BotPermissionChangedEvent" => (
+            quote! {::proc_qq::BotPermissionChangedEventProcess},
+            quote! {::proc_qq::ModuleEventProcess::BotPermissionChanged},
+        ),
+        "& AutoLeftGroupEvent" => (
+            quote! {::proc_qq::AutoLeftGroupEventProcess},
+            quote! {::proc_qq::ModuleEventProcess::AutoLeftGroup},
+        ),
         "& GroupMessageRecallEvent" => (
             quote! {::proc_qq::GroupMessageRecallEventProcess},
             quote! {::proc_qq::ModuleEventProcess::GroupMessageRecall},
@@ -136,6 +188,81 @@ pub fn event(_: TokenStream, input: TokenStream) -> TokenStream {
         pub struct #ident {}
     };
     let block = &method.block;
+    let filters = parse_filters(&method.attrs);
+    let after = parse_order(&method.attrs, "after");
+    let before = parse_order(&method.attrs, "before");
+    let own_messages = method
+        .attrs
+        .iter()
+        .any(|attr| attr.path.is_ident("own_messages"));
+    let description = match parse_doc(&method.attrs) {
+        Some(doc) => quote! {Some(#doc.to_owned())},
+        None => quote! {None},
+    };
+    let command = match &regexp {
+        Some(regexp) => quote! {Some(#regexp.to_owned())},
+        None => quote! {None},
+    };
+    let (build_regex, block, init_regex) = match regexp {
+        Some(regexp) => {
+            match param_ty.to_string().as_str() {
+                "& MessageEvent"
+                | "& GroupMessageEvent"
+                | "& FriendMessageEvent"
+                | "& TempMessageEvent" => (),
+                _ => abort!(param.span(), "regexp只能用于消息事件"),
+            }
+            // 每个监听器一个静态的Regex, 只编译一次
+            let regex_static = format_ident!("__PROC_QQ_REGEX_{}", ident_str.to_uppercase());
+            let build_regex = quote! {
+                ::proc_qq::re_exports::lazy_static::lazy_static! {
+                    static ref #regex_static: ::proc_qq::re_exports::regex::Regex =
+                        ::proc_qq::re_exports::regex::Regex::new(#regexp).unwrap_or_else(|err| {
+                            panic!("监听器 {} 的regexp格式错误 : {}", #ident_str, err)
+                        });
+                }
+            };
+            let bindings = capture_params
+                .iter()
+                .enumerate()
+                .map(|(index, param)| capture_binding(index + 1, param))
+                .collect::<Vec<TokenStream2>>();
+            let block = quote! {{
+                let __proc_qq_content = ::proc_qq::MessageContentTrait::message_content(#param_pat);
+                let __proc_qq_captures = match #regex_static.captures(&__proc_qq_content) {
+                    Some(captures) => captures,
+                    None => return Ok(false),
+                };
+                #(#bindings)*
+                #block
+            }};
+            // 在注册模块时编译, 格式错误时启动即报错
+            let init_regex = quote! {
+                ::proc_qq::re_exports::lazy_static::initialize(&#regex_static);
+            };
+            (build_regex, block, init_regex)
+        }
+        None => (quote! {}, quote! {#block}, quote! {}),
+    };
+    let config_bindings = config_params
+        .iter()
+        .map(|param| config_binding(param))
+        .collect::<Vec<TokenStream2>>();
+    let config_sections = config_params
+        .iter()
+        .map(|param| {
+            let section = config_section(param).unwrap();
+            let ty = match param {
+                FnArg::Typed(pt) => pt.ty.as_ref(),
+                FnArg::Receiver(_) => abort!(&param.span(), "不支持self"),
+            };
+            quote! {::proc_qq::ConfigSection::new::<#ty>(#section)}
+        })
+        .collect::<Vec<TokenStream2>>();
+    let block = quote! {{
+        #(#config_bindings)*
+        #block
+    }};
     let build_trait = quote! {
         #[::proc_qq::re_exports::async_trait::async_trait]
         impl #trait_name for #ident {
@@ -145,16 +272,299 @@ pub fn event(_: TokenStream, input: TokenStream) -> TokenStream {
     let build_into = quote! {
         impl Into<::proc_qq::ModuleEventHandler> for #ident {
             fn into(self) -> ::proc_qq::ModuleEventHandler {
+                #init_regex
                 ::proc_qq::ModuleEventHandler{
                     name: #ident_str.into(),
                     process: #enum_name(Box::new(self)),
+                    filters: vec![#(Box::new(#filters)),*],
+                    command: #command,
+                    description: #description,
+                    after: vec![#(#after.to_owned()),*],
+                    before: vec![#(#before.to_owned()),*],
+                    own_messages: #own_messages,
+                    config_sections: vec![#(#config_sections),*],
                 }
             }
         }
     };
     emit!(quote! {
         #build_struct
+        #build_regex
         #build_trait
         #build_into
     })
 }
+
+/// #[event(regexp = "^签到$")]
+fn parse_event_args(args: &AttributeArgs) -> Option<LitStr> {
+    let mut regexp = None;
+    for arg in args {
+        match arg {
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("regexp") => match &nv.lit {
+                Lit::Str(lit) => regexp = Some(lit.clone()),
+                _ => abort!(&nv.lit.span(), "regexp必须是字符串"),
+            },
+            _ => abort!(&arg.span(), "未知的参数, 只支持regexp"),
+        }
+    }
+    regexp
+}
+
+/// 将正则的捕获组绑定到参数, 先按参数名找命名组, 再按位置取
+/// 类型为Option<T>时未匹配或解析失败为None, 否则监听器不执行
+fn capture_binding(index: usize, param: &FnArg) -> TokenStream2 {
+    let param = match param {
+        FnArg::Typed(pt) => pt,
+        FnArg::Receiver(_) => abort!(&param.span(), "不支持self"),
+    };
+    let name = match param.pat.as_ref() {
+        Pat::Ident(pat) => &pat.ident,
+        _ => abort!(&param.span(), "捕获组参数必须是标识符"),
+    };
+    let name_str = name.to_string();
+    let name_str = name_str.trim_start_matches('_');
+    let ty = param.ty.as_ref();
+    let capture = quote! {
+        __proc_qq_captures
+            .name(#name_str)
+            .or_else(|| __proc_qq_captures.get(#index))
+    };
+    match option_inner(ty) {
+        Some(inner) => quote! {
+            let #name: #ty = #capture.and_then(|m| m.as_str().parse::<#inner>().ok());
+        },
+        None => quote! {
+            let #name: #ty = match #capture.map(|m| m.as_str().parse::<#ty>()) {
+                Some(Ok(value)) => value,
+                _ => return Ok(false),
+            };
+        },
+    }
+}
+
+/// #[config(section = "weather")] cfg: WeatherCfg
+fn config_section(param: &FnArg) -> Option<LitStr> {
+    let param = match param {
+        FnArg::Typed(pt) => pt,
+        FnArg::Receiver(_) => return None,
+    };
+    let attr = param
+        .attrs
+        .iter()
+        .find(|attr| attr.path.is_ident("config"))?;
+    let meta = match attr.parse_args::<syn::MetaNameValue>() {
+        Ok(meta) if meta.path.is_ident("section") => meta,
+        Ok(meta) => abort!(&meta.path.span(), "config只支持section参数"),
+        Err(err) => abort!(&attr.span(), format!("config格式错误 : {}", err)),
+    };
+    match meta.lit {
+        Lit::Str(section) => Some(section),
+        lit => abort!(&lit.span(), "section必须是字符串"),
+    }
+}
+
+fn config_binding(param: &FnArg) -> TokenStream2 {
+    let section = config_section(param).unwrap();
+    let param = match param {
+        FnArg::Typed(pt) => pt,
+        FnArg::Receiver(_) => abort!(&param.span(), "不支持self"),
+    };
+    let pat = param.pat.as_ref();
+    let ty = param.ty.as_ref();
+    quote! {
+        let #pat: #ty = ::proc_qq::config_section::<#ty>(#section)?;
+    }
+}
+
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let path = match ty {
+        Type::Path(path) => &path.path,
+        _ => return None,
+    };
+    let segment = path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first()? {
+            GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// 监听器的文档注释, 作为命令的说明导出
+fn parse_doc(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(nv)) => match nv.lit {
+                Lit::Str(lit) => Some(lit.value().trim().to_owned()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
+
+/// 解析写在event宏下方的过滤属性, 如 #[accounts(123456, 654321)]
+fn parse_filters(attrs: &[Attribute]) -> Vec<TokenStream2> {
+    let mut filters = vec![];
+    for attr in attrs {
+        if attr.path.is_ident("accounts") {
+            let accounts =
+                match attr.parse_args_with(Punctuated::<LitInt, Token![,]>::parse_terminated) {
+                    Ok(accounts) => accounts,
+                    Err(err) => abort!(&attr.span(), format!("accounts格式错误 : {}", err)),
+                };
+            let accounts = accounts
+                .iter()
+                .map(|lit| match lit.base10_parse::<i64>() {
+                    Ok(uin) => uin,
+                    Err(err) => abort!(&lit.span(), format!("accounts格式错误 : {}", err)),
+                })
+                .collect::<Vec<i64>>();
+            filters.push(quote! {::proc_qq::AccountsFilter::new(vec![#(#accounts),*])});
+        } else if attr.path.is_ident("active_hours") {
+            let (range, timezone) = match attr.parse_args_with(parse_active_hours) {
+                Ok(args) => args,
+                Err(err) => abort!(&attr.span(), format!("active_hours格式错误 : {}", err)),
+            };
+            // 编译时检查, 运行时不会再出错
+            let (start, end) = match parse_time_range(&range.value()) {
+                Some(range) => range,
+                None => abort!(
+                    &range.span(),
+                    "active_hours格式错误 : 时间段应为 HH:MM-HH:MM, 如 08:00-23:00"
+                ),
+            };
+            let timezone = match timezone {
+                Some(timezone) => match parse_utc_offset(&timezone.value()) {
+                    Some(offset) => quote! {Some(#offset)},
+                    None => abort!(
+                        &timezone.span(),
+                        "active_hours格式错误 : 时区应为 UTC 或者 +08:00 形式的偏移"
+                    ),
+                },
+                None => quote! {None},
+            };
+            filters.push(quote! {::proc_qq::ActiveHoursFilter::new(#start, #end, #timezone)});
+        } else if attr.path.is_ident("feature") {
+            let flag: LitStr = match attr.parse_args() {
+                Ok(flag) => flag,
+                Err(err) => abort!(&attr.span(), format!("feature格式错误 : {}", err)),
+            };
+            filters.push(quote! {::proc_qq::FeatureFilter::new(#flag)});
+        } else if attr.path.is_ident("sample") {
+            let rate: LitFloat = match attr.parse_args() {
+                Ok(rate) => rate,
+                Err(err) => abort!(&attr.span(), format!("sample格式错误 : {}", err)),
+            };
+            match rate.base10_parse::<f64>() {
+                Ok(value) if (0.0..=1.0).contains(&value) => (),
+                _ => abort!(&rate.span(), "sample的比例需要在0.0到1.0之间"),
+            }
+            filters.push(quote! {::proc_qq::SampleFilter::new(#rate)});
+        }
+    }
+    filters
+}
+
+/// #[after("other_handler")] / #[before("other_handler")], 可以重复声明
+fn parse_order(attrs: &[Attribute], name: &str) -> Vec<LitStr> {
+    let mut handlers = vec![];
+    for attr in attrs {
+        if attr.path.is_ident(name) {
+            let names =
+                match attr.parse_args_with(Punctuated::<LitStr, Token![,]>::parse_terminated) {
+                    Ok(names) => names,
+                    Err(err) => abort!(&attr.span(), format!("{}格式错误 : {}", name, err)),
+                };
+            handlers.extend(names);
+        }
+    }
+    handlers
+}
+
+/// #[active_hours("08:00-23:00", tz = "+08:00")]
+fn parse_active_hours(input: ParseStream) -> syn::Result<(LitStr, Option<LitStr>)> {
+    let range: LitStr = input.parse()?;
+    let mut timezone = None;
+    if input.peek(Token![,]) {
+        input.parse::<Token![,]>()?;
+        let key: Ident = input.parse()?;
+        if key != "tz" {
+            return Err(syn::Error::new(key.span(), "只支持tz参数"));
+        }
+        input.parse::<Token![=]>()?;
+        timezone = Some(input.parse()?);
+    }
+    Ok((range, timezone))
+}
+
+// 和 proc_qq::ActiveHoursFilter::parse 的规则一致, 返回一天中的分钟数
+fn parse_time_range(range: &str) -> Option<(u32, u32)> {
+    let (start, end) = range.split_once('-')?;
+    Some((parse_minute_of_day(start)?, parse_minute_of_day(end)?))
+}
+
+fn parse_minute_of_day(time: &str) -> Option<u32> {
+    let (hour, minute) = time.trim().split_once(':')?;
+    let hour: u32 = hour.parse().ok()?;
+    let minute: u32 = minute.parse().ok()?;
+    if hour > 24 || minute > 59 || (hour == 24 && minute != 0) {
+        return None;
+    }
+    Some(hour * 60 + minute)
+}
+
+fn parse_utc_offset(timezone: &str) -> Option<i32> {
+    if timezone == "UTC" {
+        return Some(0);
+    }
+    let (sign, rest) = match timezone.chars().next()? {
+        '+' => (1, &timezone[1..]),
+        '-' => (-1, &timezone[1..]),
+        _ => return None,
+    };
+    let (hour, minute) = rest.split_once(':').unwrap_or((rest, "0"));
+    let hour: i32 = hour.parse().ok()?;
+    let minute: i32 = minute.parse().ok()?;
+    if hour > 14 || minute > 59 {
+        return None;
+    }
+    Some(sign * (hour * 60 + minute))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_ranges() {
+        assert_eq!(parse_time_range("08:00-23:00"), Some((480, 1380)));
+        assert_eq!(parse_time_range("22:00-06:30"), Some((1320, 390)));
+        assert_eq!(parse_time_range("00:00-24:00"), Some((0, 1440)));
+        assert_eq!(parse_time_range("08:00"), None);
+        assert_eq!(parse_time_range("25:00-06:00"), None);
+        assert_eq!(parse_time_range("08:60-09:00"), None);
+        assert_eq!(parse_time_range("24:30-09:00"), None);
+    }
+
+    #[test]
+    fn utc_offsets() {
+        assert_eq!(parse_utc_offset("UTC"), Some(0));
+        assert_eq!(parse_utc_offset("+08:00"), Some(480));
+        assert_eq!(parse_utc_offset("-03:30"), Some(-210));
+        assert_eq!(parse_utc_offset("+9"), Some(540));
+        assert_eq!(parse_utc_offset("Asia/Shanghai"), None);
+        assert_eq!(parse_utc_offset("+15:00"), None);
+    }
+}
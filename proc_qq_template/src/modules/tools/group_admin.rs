@@ -2,7 +2,8 @@ use crate::utils::CanReply;
 use lazy_static::lazy_static;
 use proc_qq::re_exports::rq_engine::msg::elem::RQElem;
 use proc_qq::{
-    event, module, ClientTrait, GroupTrait, MemberTrait, MessageContentTrait, MessageEvent, Module,
+    event, framework_messages, module, ClientTrait, GroupTrait, MemberTrait, MessageContentTrait,
+    MessageEvent, Module,
 };
 use regex::Regex;
 use std::time::Duration;
@@ -21,7 +22,9 @@ pub fn module() -> Module {
 
 async fn not_in_group_and_reply(event: &MessageEvent) -> anyhow::Result<bool> {
     Ok(if !event.is_group_message() {
-        event.reply_text("只能在群中使用").await?;
+        event
+            .reply_text(&framework_messages().only_in_group())
+            .await?;
         true
     } else {
         false
@@ -59,13 +62,13 @@ async fn on_message(event: &MessageEvent) -> anyhow::Result<bool> {
         let bot_member = group.must_find_member(event.bot_uin().await).await?;
         if call_member.is_member() {
             group_message
-                .reply_text("您必须是群主或管理员才能使用")
+                .reply_text(&framework_messages().permission_denied())
                 .await?;
             return Ok(true);
         }
         if bot_member.is_member() {
             group_message
-                .reply_text("机器人必须是群主或管理员才能使用")
+                .reply_text(&framework_messages().bot_permission_denied())
                 .await?;
             return Ok(true);
         }